@@ -0,0 +1,438 @@
+use crate::commitment::Commitment;
+use crate::error::{Conflict, ConflictReason, Error};
+
+use std::collections::BTreeMap;
+
+use ckb_types::{
+    bytes::Bytes,
+    core::{Capacity, TransactionBuilder, TransactionView},
+    packed::{self, CellDep, CellInput, CellOutput},
+    prelude::*,
+};
+use otx_sighash_lock_sdk::{MergeConflictKind, Psot, PsotInput};
+
+/// Cell of the aggregator paying the fee, the change going back to its lock.
+pub struct FeeCell {
+    pub input: CellInput,
+    pub cell_output: CellOutput,
+    pub data: Bytes,
+    /// Deps unlocking the fee cell.
+    pub cell_deps: Vec<CellDep>,
+    /// Witness of the fee input as it will be signed, the signature zeroed, so that the
+    /// fee covers its size.
+    pub witness: packed::Bytes,
+}
+
+/// Transaction laid out by an `Aggregator`.
+pub struct Aggregate {
+    /// The otxs followed by the fee input, still to be signed, and the change if any.
+    pub psot: Psot,
+    /// Indexes of the otxs in the transaction, in their order.
+    pub otxs: Vec<usize>,
+    /// The otxs left out.
+    pub conflicts: Vec<Conflict>,
+    pub fee: u64,
+}
+
+/// Merges signed otxs into one transaction, keeping every signature valid.
+///
+/// SINGLE modes and limit orders sign the output at the index of their input: the otxs with
+/// as many inputs as outputs come first, then at most one unbalanced otx with such
+/// signatures, then the unbalanced otxs without any. ALL|ANYONECANPAY without an otx scope signs all outputs:
+/// such otxs are merged with each other when they signed the same outputs in other lock
+/// groups, and only when no other otx is left. There is no change output then, so the fee
+/// cell must not bring more than the fee.
+pub struct Aggregator {
+    fee_cell: FeeCell,
+    fee_rate: u64,
+    otxs: Vec<Psot>,
+}
+
+impl Aggregator {
+    /// `fee_rate` is in shannons per 1000 bytes.
+    pub fn new(fee_cell: FeeCell, fee_rate: u64) -> Self {
+        Aggregator {
+            fee_cell,
+            fee_rate,
+            otxs: Vec::new(),
+        }
+    }
+
+    /// Adds a signed otx, returning the index it is reported with.
+    pub fn add_otx(&mut self, otx: Psot) -> usize {
+        self.otxs.push(otx);
+        self.otxs.len() - 1
+    }
+
+    pub fn aggregate(&self) -> Result<Aggregate, Error> {
+        let mut conflicts = Vec::new();
+
+        // the otxs that may be laid out, with what their signatures commit to
+        let mut candidates: Vec<(usize, Commitment)> = Vec::new();
+        let mut spent: Vec<(packed::OutPoint, usize)> = Vec::new();
+        'otxs: for (i, otx) in self.otxs.iter().enumerate() {
+            let commitment = match otx.validate().and_then(|_| Commitment::of(otx)) {
+                Ok(_) if !otx.is_complete() => {
+                    let reason = ConflictReason::Incomplete("unsigned inputs".to_string());
+                    conflicts.push(Conflict { otx: i, reason });
+                    continue;
+                }
+                Ok(commitment) => commitment,
+                Err(err) => {
+                    let reason = ConflictReason::Incomplete(err.to_string());
+                    conflicts.push(Conflict { otx: i, reason });
+                    continue;
+                }
+            };
+            if otx.tx.witnesses().len() > otx.tx.inputs().len() {
+                let reason = ConflictReason::ExtraWitnesses;
+                conflicts.push(Conflict { otx: i, reason });
+                continue;
+            }
+            for input in otx.tx.inputs() {
+                let out_point = input.previous_output();
+                if let Some((_, j)) = spent
+                    .iter()
+                    .find(|(spent, _)| spent.as_slice() == out_point.as_slice())
+                {
+                    let reason = ConflictReason::DoubleSpend(*j);
+                    conflicts.push(Conflict { otx: i, reason });
+                    continue 'otxs;
+                }
+            }
+            spent.extend(
+                otx.tx
+                    .inputs()
+                    .into_iter()
+                    .map(|input| (input.previous_output(), i)),
+            );
+            candidates.push((i, commitment));
+        }
+
+        let (fixed, flexible): (Vec<_>, Vec<_>) = candidates
+            .into_iter()
+            .partition(|(_, commitment)| commitment.all_outputs);
+        let (psot, otxs, change) = if flexible.is_empty() {
+            let (psot, otxs) = self.merge_fixed(fixed, &mut conflicts);
+            // their signatures would not cover a change output
+            let change = otxs.is_empty();
+            (psot, otxs, change)
+        } else {
+            let adding_outputs = flexible
+                .iter()
+                .find(|(i, _)| !self.otxs[*i].tx.outputs().is_empty())
+                .unwrap_or(&flexible[0])
+                .0;
+            for (i, _) in fixed {
+                let reason = ConflictReason::OutputsAdded(adding_outputs);
+                conflicts.push(Conflict { otx: i, reason });
+            }
+            let (psot, otxs) = self.merge_flexible(flexible, &mut conflicts)?;
+            (psot, otxs, true)
+        };
+        let (psot, fee) = self.add_fee(psot, change)?;
+
+        conflicts.sort_by_key(|conflict| conflict.otx);
+        Ok(Aggregate {
+            psot,
+            otxs,
+            conflicts,
+            fee,
+        })
+    }
+
+    // lays the otxs out one after the other, the balanced ones first
+    fn merge_flexible(
+        &self,
+        candidates: Vec<(usize, Commitment)>,
+        conflicts: &mut Vec<Conflict>,
+    ) -> Result<(Psot, Vec<usize>), Error> {
+        let (balanced, unbalanced): (Vec<_>, Vec<_>) =
+            candidates.into_iter().partition(|(i, _)| {
+                let tx = &self.otxs[*i].tx;
+                tx.inputs().len() == tx.outputs().len()
+            });
+        let mut ordered: Vec<usize> = balanced.into_iter().map(|(i, _)| i).collect();
+        let mut unaligned = Vec::new();
+        let mut unbalanced_single = None;
+        for (i, commitment) in unbalanced {
+            if !commitment.positional {
+                unaligned.push(i);
+            } else if let Some(j) = unbalanced_single {
+                let reason = ConflictReason::Unaligned(j);
+                conflicts.push(Conflict { otx: i, reason });
+            } else {
+                unbalanced_single = Some(i);
+                ordered.push(i);
+            }
+        }
+        ordered.extend(unaligned);
+
+        // the positions are settled, the merge should only fail on committed deps
+        let mut psot = self.empty_psot();
+        let mut otxs: Vec<(usize, usize)> = Vec::new();
+        for i in ordered {
+            match psot.merge(&self.otxs[i]) {
+                Ok(merged) => {
+                    otxs.push((i, psot.inputs.len()));
+                    psot = merged;
+                }
+                Err(otx_sighash_lock_sdk::Error::MergeConflict(index, kind)) => {
+                    // the otx whose signature breaks, this one or one already laid out
+                    let owner = otxs
+                        .iter()
+                        .rev()
+                        .find(|(j, start)| {
+                            *start <= index && index < start + self.otxs[*j].inputs.len()
+                        })
+                        .map(|(j, _)| *j);
+                    let previous = otxs.last().map_or(i, |(j, _)| *j);
+                    let reason = match (kind, owner) {
+                        (MergeConflictKind::CellDepsChanged, Some(owner))
+                        | (MergeConflictKind::HeaderDepsChanged, Some(owner)) => {
+                            ConflictReason::DepsCommitted(owner)
+                        }
+                        (MergeConflictKind::CellDepsChanged, None)
+                        | (MergeConflictKind::HeaderDepsChanged, None) => {
+                            ConflictReason::DepsChanged
+                        }
+                        (MergeConflictKind::Unaligned, _) => ConflictReason::Unaligned(previous),
+                        (MergeConflictKind::ExtraWitnesses, _) => ConflictReason::ExtraWitnesses,
+                        (MergeConflictKind::AllOutputs, _) => {
+                            ConflictReason::OutputsAdded(previous)
+                        }
+                    };
+                    conflicts.push(Conflict { otx: i, reason });
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok((psot, otxs.into_iter().map(|(i, _)| i).collect()))
+    }
+
+    // ALL|ANYONECANPAY otxs without an otx scope share the outputs they all signed
+    fn merge_fixed(
+        &self,
+        candidates: Vec<(usize, Commitment)>,
+        conflicts: &mut Vec<Conflict>,
+    ) -> (Psot, Vec<usize>) {
+        let mut otxs: Vec<(usize, Commitment)> = Vec::new();
+        for (i, commitment) in candidates {
+            let first = otxs.first().map(|(first, _)| *first);
+            // a signature of the lock group would miss the witnesses of the other otx
+            let shared = otxs.iter().find(|(j, taken)| {
+                taken.covers_group_of(&self.otxs[i]) || commitment.covers_group_of(&self.otxs[*j])
+            });
+            if let Some((j, _)) = shared {
+                let reason = ConflictReason::LockGroupShared(*j);
+                conflicts.push(Conflict { otx: i, reason });
+                continue;
+            }
+            match first {
+                // the inputs of the next otxs do not start at the index of their outputs
+                Some(first) if commitment.positional => {
+                    let reason = ConflictReason::Unaligned(first);
+                    conflicts.push(Conflict { otx: i, reason });
+                }
+                Some(first) if !same_outputs(&self.otxs[first].tx, &self.otxs[i].tx) => {
+                    let reason = ConflictReason::OutputsDiffer(first);
+                    conflicts.push(Conflict { otx: i, reason });
+                }
+                _ => otxs.push((i, commitment)),
+            }
+        }
+
+        // leave out the otxs whose committed deps are extended by the others
+        let (cell_deps, header_deps) = loop {
+            let cell_deps = merge_deps(
+                self.fee_cell.cell_deps.clone(),
+                otxs.iter().map(|(i, _)| self.otxs[*i].tx.cell_deps()),
+            );
+            let header_deps = merge_deps(
+                Vec::new(),
+                otxs.iter().map(|(i, _)| self.otxs[*i].tx.header_deps()),
+            );
+            let changed = otxs.iter().position(|(i, commitment)| {
+                let tx = &self.otxs[*i].tx;
+                (commitment.cell_deps && !same_deps(&cell_deps, tx.cell_deps()))
+                    || (commitment.header_deps && !same_deps(&header_deps, tx.header_deps()))
+            });
+            match changed {
+                Some(position) => {
+                    let (i, _) = otxs.remove(position);
+                    let reason = ConflictReason::DepsChanged;
+                    conflicts.push(Conflict { otx: i, reason });
+                }
+                None => break (cell_deps, header_deps),
+            }
+        };
+
+        let mut builder = TransactionBuilder::default()
+            .set_cell_deps(cell_deps)
+            .set_header_deps(header_deps);
+        if let Some((first, _)) = otxs.first() {
+            let tx = &self.otxs[*first].tx;
+            builder = builder
+                .outputs(tx.outputs())
+                .outputs_data(tx.outputs_data());
+        }
+        // the metadata of the first otxs wins
+        let mut metadata = BTreeMap::new();
+        for (i, _) in otxs.iter().rev() {
+            metadata.extend(self.otxs[*i].metadata.clone());
+        }
+        let mut inputs = Vec::new();
+        for (i, _) in &otxs {
+            let otx = &self.otxs[*i];
+            let mut witnesses: Vec<packed::Bytes> = otx.tx.witnesses().into_iter().collect();
+            witnesses.resize(otx.inputs.len(), packed::Bytes::default());
+            builder = builder.inputs(otx.tx.inputs()).witnesses(witnesses);
+            inputs.extend(otx.inputs.iter().cloned());
+        }
+
+        let psot = Psot {
+            tx: builder.build(),
+            inputs,
+            metadata,
+        };
+        (psot, otxs.into_iter().map(|(i, _)| i).collect())
+    }
+
+    // adds the fee input, and the change back to the fee cell when outputs may be added
+    fn add_fee(&self, psot: Psot, change: bool) -> Result<(Psot, u64), Error> {
+        let Psot {
+            tx,
+            mut inputs,
+            metadata,
+        } = psot;
+        inputs.push(PsotInput::new(
+            self.fee_cell.cell_output.clone(),
+            self.fee_cell.data.clone(),
+            None,
+        ));
+        let inputs_capacity = sum_capacity(inputs.iter().map(|input| input.cell_output.clone()))?;
+        let outputs_capacity = sum_capacity(tx.outputs().into_iter())?;
+
+        let mut witnesses: Vec<packed::Bytes> = tx.witnesses().into_iter().collect();
+        witnesses.resize(inputs.len() - 1, packed::Bytes::default());
+        witnesses.push(self.fee_cell.witness.clone());
+        let tx = tx
+            .as_advanced_builder()
+            .input(self.fee_cell.input.clone())
+            .set_witnesses(witnesses)
+            .build();
+
+        // the change is only added when it can hold its own cell
+        if change {
+            let change_output = CellOutput::new_builder()
+                .lock(self.fee_cell.cell_output.lock())
+                .build();
+            let occupied = change_output
+                .occupied_capacity(Capacity::zero())
+                .expect("change capacity")
+                .as_u64();
+            let with_change = tx
+                .as_advanced_builder()
+                .output(change_output.clone())
+                .output_data(Bytes::new().pack())
+                .build();
+            let fee = self.fee(&with_change);
+            let change_capacity = inputs_capacity
+                .checked_sub(outputs_capacity)
+                .and_then(|surplus| surplus.checked_sub(fee))
+                .filter(|change_capacity| *change_capacity >= occupied);
+            if let Some(change_capacity) = change_capacity {
+                let tx = tx
+                    .as_advanced_builder()
+                    .output(
+                        change_output
+                            .as_builder()
+                            .capacity(change_capacity.pack())
+                            .build(),
+                    )
+                    .output_data(Bytes::new().pack())
+                    .build();
+                let psot = Psot {
+                    tx,
+                    inputs,
+                    metadata,
+                };
+                return Ok((psot, fee));
+            }
+        }
+
+        let fee = self.fee(&tx);
+        let required = outputs_capacity
+            .checked_add(fee)
+            .ok_or(Error::CapacityOverflow)?;
+        if inputs_capacity < required {
+            return Err(Error::InsufficientCapacity(required - inputs_capacity));
+        }
+        // without a change output the fee takes what the fee cell brings beyond it
+        let excess = (inputs_capacity - required).min(capacity(&self.fee_cell.cell_output));
+        if !change && excess > 0 {
+            return Err(Error::ExcessFee(excess));
+        }
+        let psot = Psot {
+            tx,
+            inputs,
+            metadata,
+        };
+        Ok((psot, inputs_capacity - outputs_capacity))
+    }
+
+    fn fee(&self, tx: &TransactionView) -> u64 {
+        let size = tx.data().serialized_size_in_block() as u64;
+        (size * self.fee_rate + 999) / 1000
+    }
+
+    fn empty_psot(&self) -> Psot {
+        let tx = TransactionBuilder::default()
+            .cell_deps(self.fee_cell.cell_deps.clone())
+            .build();
+        Psot::new(tx, Vec::new())
+    }
+}
+
+fn capacity(output: &CellOutput) -> u64 {
+    output.capacity().unpack()
+}
+
+fn sum_capacity(mut outputs: impl Iterator<Item = CellOutput>) -> Result<u64, Error> {
+    outputs.try_fold(0u64, |total, output| {
+        total
+            .checked_add(capacity(&output))
+            .ok_or(Error::CapacityOverflow)
+    })
+}
+
+fn same_outputs(tx: &TransactionView, other: &TransactionView) -> bool {
+    tx.outputs().as_slice() == other.outputs().as_slice()
+        && tx.outputs_data().as_slice() == other.outputs_data().as_slice()
+}
+
+fn same_deps<T: Entity>(merged: &[T], deps: impl IntoIterator<Item = T>) -> bool {
+    let deps: Vec<T> = deps.into_iter().collect();
+    merged.len() == deps.len()
+        && merged
+            .iter()
+            .zip(&deps)
+            .all(|(merged, dep)| merged.as_slice() == dep.as_slice())
+}
+
+// `deps` followed by those of `others` it misses
+fn merge_deps<T: Entity, I: IntoIterator<Item = T>>(
+    deps: Vec<T>,
+    others: impl Iterator<Item = I>,
+) -> Vec<T> {
+    let mut merged = deps;
+    for dep in others.flatten() {
+        if !merged
+            .iter()
+            .any(|merged| merged.as_slice() == dep.as_slice())
+        {
+            merged.push(dep);
+        }
+    }
+    merged
+}
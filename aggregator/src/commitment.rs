@@ -0,0 +1,78 @@
+use ckb_types::{
+    bytes::Bytes,
+    packed::{Byte32, WitnessArgs},
+    prelude::*,
+};
+use otx_sighash_core::{SighashMode, WitnessLock};
+use otx_sighash_lock_sdk::{Error, Psot};
+
+/// What the signatures of an otx commit to beyond their own input, deciding where it can
+/// be placed.
+#[derive(Default)]
+pub(crate) struct Commitment {
+    /// SINGLE modes and limit orders sign the output at the index of their input.
+    pub positional: bool,
+    /// ALL|ANYONECANPAY without an otx scope signs all outputs of the transaction.
+    pub all_outputs: bool,
+    /// Lock hashes of the inputs signed that way, whose signatures also cover the witnesses
+    /// of every input of the lock group.
+    pub all_outputs_locks: Vec<Byte32>,
+    pub cell_deps: bool,
+    pub header_deps: bool,
+}
+
+impl Commitment {
+    pub fn of(psot: &Psot) -> Result<Commitment, Error> {
+        let mut commitment = Commitment::default();
+        for (index, input) in psot.inputs.iter().enumerate() {
+            if input.mode.is_none() || !input.signed {
+                continue;
+            }
+            let witness_lock = witness_lock(psot, index)?;
+            let witness_lock = WitnessLock::from_slice(&witness_lock)?;
+            commitment.positional |= matches!(
+                witness_lock.mode,
+                SighashMode::SingleAnyoneCanPay
+                    | SighashMode::SingleUdtAnyoneCanPay
+                    | SighashMode::LimitOrder
+            );
+            if witness_lock.mode == SighashMode::AllAnyoneCanPay
+                && witness_lock.extension.otx_scope.is_none()
+            {
+                commitment.all_outputs = true;
+                commitment
+                    .all_outputs_locks
+                    .push(input.cell_output.lock().calc_script_hash());
+            }
+            commitment.cell_deps |= witness_lock.extension.commit_cell_deps;
+            commitment.header_deps |= witness_lock.extension.commit_header_deps;
+        }
+        Ok(commitment)
+    }
+
+    /// Whether `psot` has inputs in a lock group whose witnesses these signatures cover.
+    pub fn covers_group_of(&self, psot: &Psot) -> bool {
+        psot.inputs.iter().any(|input| {
+            let lock_hash = input.cell_output.lock().calc_script_hash();
+            self.all_outputs_locks
+                .iter()
+                .any(|lock| lock.as_slice() == lock_hash.as_slice())
+        })
+    }
+}
+
+// the witness lock of a signed input
+fn witness_lock(psot: &Psot, index: usize) -> Result<Bytes, Error> {
+    let witness = psot
+        .tx
+        .witnesses()
+        .get(index)
+        .ok_or(Error::SignatureStatus(index))?;
+    let witness_args =
+        WitnessArgs::from_slice(&witness.raw_data()).map_err(|_| Error::InvalidWitness(index))?;
+    Ok(witness_args
+        .lock()
+        .to_opt()
+        .ok_or(Error::SignatureStatus(index))?
+        .raw_data())
+}
@@ -0,0 +1,43 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("the fee cell lacks {0} shannons")]
+    InsufficientCapacity(u64),
+    #[error("without a change output, the fee cell would pay {0} shannons beyond the fee")]
+    ExcessFee(u64),
+    #[error("the capacity of the transaction overflows")]
+    CapacityOverflow,
+    #[error("psot: {0}")]
+    Psot(#[from] otx_sighash_lock_sdk::Error),
+}
+
+/// An otx left out of the aggregate.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("otx {otx} {reason}")]
+pub struct Conflict {
+    pub otx: usize,
+    pub reason: ConflictReason,
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ConflictReason {
+    #[error("is not completely signed: {0}")]
+    Incomplete(String),
+    #[error("spends an input of otx {0}")]
+    DoubleSpend(usize),
+    #[error("has more witnesses than inputs, shifting those of the next otxs")]
+    ExtraWitnesses,
+    #[error("has SINGLE or limit order signatures, which cannot be aligned after otx {0}")]
+    Unaligned(usize),
+    #[error("signed all outputs with ALL|ANYONECANPAY, while otx {0} adds outputs")]
+    OutputsAdded(usize),
+    #[error("signed other outputs with ALL|ANYONECANPAY than otx {0}")]
+    OutputsDiffer(usize),
+    #[error("would change the deps committed by otx {0}")]
+    DepsCommitted(usize),
+    #[error("committed to deps the other otxs or the fee cell add to")]
+    DepsChanged,
+    #[error("shares with otx {0} a lock group signed whole with ALL|ANYONECANPAY")]
+    LockGroupShared(usize),
+}
@@ -0,0 +1,12 @@
+//! Lays out signed otxs of the otx-sighash-lock in one transaction.
+//!
+//! `Aggregator` takes complete `Psot`s, places them so that every signature still holds,
+//! adds the fee cell of the aggregator with its change, and reports the otxs it had to
+//! leave out with the reason why.
+
+mod aggregator;
+mod commitment;
+mod error;
+
+pub use aggregator::{Aggregate, Aggregator, FeeCell};
+pub use error::{Conflict, ConflictReason, Error};
@@ -0,0 +1,14 @@
+//! Plugs the otx-sighash-lock into the unlocker framework of ckb-sdk-rust.
+//!
+//! ```ignore
+//! let signer = OtxScriptSigner::new(Box::new(key_signer), SighashMode::SingleAnyoneCanPay);
+//! OtxUnlocker::new(signer).register(otx_lock_script_id, &mut unlockers);
+//! ```
+
+mod signer;
+mod unlocker;
+
+pub use signer::OtxScriptSigner;
+pub use unlocker::OtxUnlocker;
+
+pub use otx_sighash_lock_sdk::SighashMode;
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use ckb_sdk::{
+    traits::{Signer, SignerError},
+    unlock::{ScriptSignError, ScriptSigner},
+    ScriptGroup,
+};
+use ckb_types::core::TransactionView;
+use otx_sighash_lock_sdk::{
+    KeyBackend, LockArgs, OtxSigner, SighashMode, SIGHASH_ALL_SIGNATURE_SIZE,
+};
+
+// size of a pubkey hash in the lock args and the identity cell
+const BLAKE160_SIZE: usize = 20;
+
+/// Signs every input of an otx-sighash-lock script group with one mode.
+///
+/// All inputs share the extension, and the payload unless one is set for the input. The key
+/// is found by the pubkey hash of the lock args, or among the pubkey hashes of the identity
+/// cell when the args name one.
+pub struct OtxScriptSigner {
+    signer: Box<dyn Signer>,
+    mode: SighashMode,
+    payload: Vec<u8>,
+    input_payloads: HashMap<usize, Vec<u8>>,
+    extension: Vec<u8>,
+}
+
+impl OtxScriptSigner {
+    pub fn new(signer: Box<dyn Signer>, mode: SighashMode) -> Self {
+        OtxScriptSigner {
+            signer,
+            mode,
+            payload: Vec::new(),
+            input_payloads: HashMap::new(),
+            extension: Vec::new(),
+        }
+    }
+
+    /// Sets the mode specific payload following the signature.
+    pub fn payload(mut self, payload: Vec<u8>) -> Self {
+        self.payload = payload;
+        self
+    }
+
+    /// Sets the payload of the input at `index` of the transaction, such as the payment index
+    /// of a limit order or the requirement of a SINGLE|ANYONECANPAY|UDT input, in place of
+    /// the shared one.
+    pub fn input_payload(mut self, index: usize, payload: Vec<u8>) -> Self {
+        self.input_payloads.insert(index, payload);
+        self
+    }
+
+    /// Sets the witness extension, see `otx_sighash_lock_sdk::ExtensionBuilder`.
+    pub fn extension(mut self, extension: Vec<u8>) -> Self {
+        self.extension = extension;
+        self
+    }
+
+    /// Size of the signed witness lock of the input at `index`: `mode (1 byte) | signature
+    /// (65 bytes) | payload | extension`.
+    pub fn witness_lock_size(&self, index: usize) -> usize {
+        1 + SIGHASH_ALL_SIGNATURE_SIZE + self.input_payload_of(index).len() + self.extension.len()
+    }
+
+    /// Signs the group with the key found in `identity`, the data of the identity cell, when
+    /// the lock args name one.
+    pub(crate) fn sign_group(
+        &self,
+        tx: &TransactionView,
+        script_group: &ScriptGroup,
+        identity: Option<&[u8]>,
+    ) -> Result<TransactionView, ScriptSignError> {
+        let args = script_group.script.args().raw_data();
+        let lock_args = LockArgs::from_slice(&args)
+            .map_err(|_| ScriptSignError::Other(anyhow!("invalid lock args")))?;
+        let id = match (lock_args.identity_type_hash, identity) {
+            (None, _) => lock_args.pubkey_hash.to_vec(),
+            (Some(_), Some(identity)) => identity
+                .chunks(BLAKE160_SIZE)
+                .find(|id| id.len() == BLAKE160_SIZE && self.signer.match_id(id))
+                .ok_or_else(|| ScriptSignError::Other(anyhow!("no key in the identity cell")))?
+                .to_vec(),
+            (Some(_), None) => {
+                return Err(ScriptSignError::Other(anyhow!(
+                    "the lock args name an identity cell, unlock with `OtxUnlocker`"
+                )))
+            }
+        };
+        if !self.signer.match_id(&id) {
+            return Err(ScriptSignError::Other(anyhow!("no key for the lock args")));
+        }
+
+        let mut tx = tx.clone();
+        for &index in &script_group.input_indices {
+            let key = SignerKey {
+                signer: self.signer.as_ref(),
+                id: &id,
+                tx: &tx,
+            };
+            let signed = OtxSigner::new(key)
+                .group_inputs(script_group.input_indices.clone())
+                .sign(
+                    &tx,
+                    index,
+                    self.mode,
+                    self.input_payload_of(index),
+                    &self.extension,
+                )
+                .map_err(|err| ScriptSignError::Other(err.into()))?;
+            tx = signed;
+        }
+        Ok(tx)
+    }
+
+    fn input_payload_of(&self, index: usize) -> &[u8] {
+        self.input_payloads.get(&index).unwrap_or(&self.payload)
+    }
+}
+
+impl ScriptSigner for OtxScriptSigner {
+    /// Identity args always match, their key is only known once the identity cell is loaded
+    /// from the cell deps when unlocking.
+    fn match_args(&self, args: &[u8]) -> bool {
+        match LockArgs::from_slice(args) {
+            Ok(lock_args) => {
+                lock_args.identity_type_hash.is_some()
+                    || self.signer.match_id(&lock_args.pubkey_hash)
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn sign_tx(
+        &self,
+        tx: &TransactionView,
+        script_group: &ScriptGroup,
+    ) -> Result<TransactionView, ScriptSignError> {
+        self.sign_group(tx, script_group, None)
+    }
+}
+
+// a key of the ckb-sdk signer, which may look at the transaction being signed
+struct SignerKey<'a> {
+    signer: &'a dyn Signer,
+    id: &'a [u8],
+    tx: &'a TransactionView,
+}
+
+impl KeyBackend for SignerKey<'_> {
+    type Error = SignerError;
+
+    fn sign_recoverable(
+        &self,
+        message: &[u8; 32],
+    ) -> Result<[u8; SIGHASH_ALL_SIGNATURE_SIZE], SignerError> {
+        let signature = self.signer.sign(self.id, message, true, self.tx)?;
+        if signature.len() != SIGHASH_ALL_SIGNATURE_SIZE {
+            return Err(SignerError::Other(anyhow!(
+                "expected a recoverable signature, got {} bytes",
+                signature.len()
+            )));
+        }
+        let mut serialized = [0u8; SIGHASH_ALL_SIGNATURE_SIZE];
+        serialized.copy_from_slice(&signature);
+        Ok(serialized)
+    }
+}
@@ -0,0 +1,140 @@
+use crate::signer::OtxScriptSigner;
+
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use ckb_sdk::{
+    traits::TransactionDependencyProvider,
+    unlock::{ScriptSigner, ScriptUnlocker, UnlockError},
+    ScriptGroup, ScriptId,
+};
+use ckb_types::{
+    bytes::Bytes,
+    core::{DepType, TransactionView},
+    packed::{self, OutPoint, OutPointVec, WitnessArgs},
+    prelude::*,
+};
+use otx_sighash_lock_sdk::LockArgs;
+
+/// Unlocks otx-sighash-lock script groups, see `OtxScriptSigner`.
+pub struct OtxUnlocker {
+    signer: OtxScriptSigner,
+}
+
+impl OtxUnlocker {
+    pub fn new(signer: OtxScriptSigner) -> Self {
+        OtxUnlocker { signer }
+    }
+
+    /// Registers the unlocker for the lock deployed as `script_id`.
+    pub fn register(
+        self,
+        script_id: ScriptId,
+        unlockers: &mut HashMap<ScriptId, Box<dyn ScriptUnlocker>>,
+    ) {
+        unlockers.insert(script_id, Box::new(self));
+    }
+}
+
+impl ScriptUnlocker for OtxUnlocker {
+    fn match_args(&self, args: &[u8]) -> bool {
+        self.signer.match_args(args)
+    }
+
+    /// Signs the group, with a key of the identity cell in the cell deps when the lock args
+    /// name one.
+    fn unlock(
+        &self,
+        tx: &TransactionView,
+        script_group: &ScriptGroup,
+        tx_dep_provider: &dyn TransactionDependencyProvider,
+    ) -> Result<TransactionView, UnlockError> {
+        let args = script_group.script.args().raw_data();
+        let identity = match LockArgs::from_slice(&args)
+            .ok()
+            .and_then(|lock_args| lock_args.identity_type_hash)
+        {
+            Some(identity_type_hash) => Some(
+                load_cell_dep_data(tx, &identity_type_hash, tx_dep_provider)?
+                    .ok_or_else(|| anyhow!("identity cell missing from the cell deps"))?,
+            ),
+            None => None,
+        };
+        Ok(self
+            .signer
+            .sign_group(tx, script_group, identity.as_deref())?)
+    }
+
+    /// Gives every input of the group a zeroed witness lock of the signed size, unlike the
+    /// sighash-all lock which only signs the first witness of a group.
+    fn fill_placeholder_witness(
+        &self,
+        tx: &TransactionView,
+        script_group: &ScriptGroup,
+        _tx_dep_provider: &dyn TransactionDependencyProvider,
+    ) -> Result<TransactionView, UnlockError> {
+        let mut witnesses: Vec<packed::Bytes> = tx.witnesses().into_iter().collect();
+        for &index in &script_group.input_indices {
+            if witnesses.len() <= index {
+                witnesses.resize(index + 1, packed::Bytes::default());
+            }
+            let witness = witnesses[index].raw_data();
+            let witness_args = if witness.is_empty() {
+                WitnessArgs::default()
+            } else {
+                WitnessArgs::from_slice(&witness)
+                    .map_err(|_| UnlockError::InvalidWitnessArgs(index))?
+            };
+            let placeholder = Bytes::from(vec![0u8; self.signer.witness_lock_size(index)]);
+            let lock_size = witness_args
+                .lock()
+                .to_opt()
+                .map_or(0, |lock| lock.raw_data().len());
+            if lock_size != placeholder.len() {
+                witnesses[index] = witness_args
+                    .as_builder()
+                    .lock(Some(placeholder).pack())
+                    .build()
+                    .as_bytes()
+                    .pack();
+            }
+        }
+        Ok(tx.as_advanced_builder().set_witnesses(witnesses).build())
+    }
+}
+
+// data of the cell dep with the type hash, dep groups being expanded as the chain resolves them
+fn load_cell_dep_data(
+    tx: &TransactionView,
+    type_hash: &[u8; 32],
+    tx_dep_provider: &dyn TransactionDependencyProvider,
+) -> Result<Option<Bytes>, UnlockError> {
+    let mut out_points = Vec::new();
+    for cell_dep in tx.cell_deps() {
+        if cell_dep.dep_type() == DepType::DepGroup.into() {
+            let data = tx_dep_provider.get_cell_data(&cell_dep.out_point())?;
+            let group =
+                OutPointVec::from_slice(&data).map_err(|_| anyhow!("invalid dep group data"))?;
+            out_points.extend(group);
+        } else {
+            out_points.push(cell_dep.out_point());
+        }
+    }
+    for out_point in out_points {
+        if has_type_hash(&out_point, type_hash, tx_dep_provider)? {
+            return Ok(Some(tx_dep_provider.get_cell_data(&out_point)?));
+        }
+    }
+    Ok(None)
+}
+
+fn has_type_hash(
+    out_point: &OutPoint,
+    type_hash: &[u8; 32],
+    tx_dep_provider: &dyn TransactionDependencyProvider,
+) -> Result<bool, UnlockError> {
+    let cell = tx_dep_provider.get_cell(out_point)?;
+    Ok(cell.type_().to_opt().map_or(false, |script| {
+        script.calc_script_hash().as_slice() == type_hash
+    }))
+}
@@ -0,0 +1,37 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("invalid private key")]
+    InvalidPrivkey,
+    #[error("invalid public key")]
+    InvalidPubkey,
+    #[error("invalid lock args")]
+    InvalidArgs,
+    #[error("invalid hex string")]
+    InvalidHex,
+    #[error("input {0} is out of bound")]
+    InputOutOfBound(usize),
+    #[error("the cell of input {0} is missing from the mock transaction")]
+    UnknownCell(usize),
+    #[error("cell dep {0} is out of bound")]
+    CellDepOutOfBound(usize),
+    #[error("cell dep {0} is loaded by data hash, its data cannot be replaced")]
+    DataHashReference(usize),
+    #[error("witness lock: {0:?}")]
+    WitnessLock(otx_sighash_core::Error),
+    #[error("rejected: {0}")]
+    Rejected(String),
+    #[error("sdk: {0}")]
+    Sdk(#[from] otx_sighash_lock_sdk::Error),
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("json: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl From<otx_sighash_core::Error> for Error {
+    fn from(err: otx_sighash_core::Error) -> Self {
+        Error::WitnessLock(err)
+    }
+}
@@ -0,0 +1,172 @@
+use crate::error::Error;
+use crate::hex::encode_hex;
+use crate::key::pubkey_hash;
+
+use ckb_crypto::secp::Signature;
+use ckb_types::{core::TransactionView, packed::WitnessArgs, prelude::*, H256};
+use otx_sighash_core::{
+    LimitOrderIntent, SighashMode, UdtRequirement, WitnessExtension, WitnessLock,
+    LIMIT_ORDER_INTENT_SIZE,
+};
+use otx_sighash_lock_sdk::{explain_preimage, signing_preimage, FieldDiff};
+
+/// Describes the witness lock of the input at `index`, one `name: value` line per field:
+/// the decoded witness lock, the parts of the preimage in the order they are hashed, the
+/// signed message and the pubkey hash the signature recovers to.
+///
+/// `group_inputs` lists the inputs of the lock group, see `TxFile::group_inputs`. The
+/// preimage is built in `mode` when given, instead of the mode of the witness lock.
+pub fn inspect(
+    tx: &TransactionView,
+    index: usize,
+    group_inputs: &[usize],
+    mode: Option<SighashMode>,
+) -> Result<String, Error> {
+    let mut lines = Vec::new();
+    let lock = match tx.witnesses().get(index) {
+        Some(witness) if !witness.raw_data().is_empty() => {
+            WitnessArgs::from_slice(&witness.raw_data())
+                .map_err(|_| otx_sighash_lock_sdk::Error::InvalidWitness(index))?
+                .lock()
+                .to_opt()
+                .map(|lock| lock.raw_data())
+        }
+        _ => None,
+    };
+    let witness_lock = match &lock {
+        Some(lock) => Some(WitnessLock::from_slice(lock)?),
+        None => None,
+    };
+    match &witness_lock {
+        Some(witness_lock) => {
+            lines.push(format!(
+                "mode: {:#04x} {:?}",
+                witness_lock.mode as u8, witness_lock.mode
+            ));
+            lines.push(format!(
+                "signature: {}",
+                encode_hex(&witness_lock.signature)
+            ));
+            describe_payload(&mut lines, witness_lock)?;
+            describe_extension(&mut lines, &witness_lock.extension);
+        }
+        None => lines.push("witness lock: none".to_string()),
+    }
+
+    let preimage = signing_preimage(tx, index, group_inputs, mode)?;
+    lines.push("preimage:".to_string());
+    for component in explain_preimage(&preimage) {
+        match component.fields.as_slice() {
+            [(name, value)] if name.is_empty() => {
+                lines.push(format!("  {}: {}", component.name, value));
+            }
+            fields => {
+                lines.push(format!("  {}:", component.name));
+                for (name, value) in fields {
+                    lines.push(format!("    {}: {}", name, value));
+                }
+            }
+        }
+    }
+
+    // a zeroed or foreign signature does not have to recover to any key
+    if let Some(witness_lock) = &witness_lock {
+        let signer = Signature::from_slice(&witness_lock.signature)
+            .and_then(|signature| signature.recover(&H256::from(preimage.message)));
+        match signer {
+            Ok(pubkey) => lines.push(format!("signer: {}", encode_hex(&pubkey_hash(&pubkey)))),
+            Err(err) => lines.push(format!("signer: none, {}", err)),
+        }
+    }
+
+    let mut description = lines.join("\n");
+    description.push('\n');
+    Ok(description)
+}
+
+/// One `field: left -> right` line per field that differs between two preimages, `-` on
+/// the side lacking it.
+pub fn describe_diff(diffs: &[FieldDiff]) -> String {
+    let side = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+    diffs
+        .iter()
+        .map(|diff| {
+            format!(
+                "{}: {} -> {}\n",
+                diff.field,
+                side(&diff.left),
+                side(&diff.right)
+            )
+        })
+        .collect()
+}
+
+fn describe_payload(lines: &mut Vec<String>, witness_lock: &WitnessLock) -> Result<(), Error> {
+    match witness_lock.mode {
+        SighashMode::SingleUdtAnyoneCanPay => {
+            let requirement = UdtRequirement::from_slice(witness_lock.payload)?;
+            lines.push(format!("lock hash: {}", encode_hex(&requirement.lock_hash)));
+            lines.push(format!("capacity: {}", requirement.capacity));
+            if let Some(type_hash) = &requirement.type_hash {
+                lines.push(format!("udt type hash: {}", encode_hex(type_hash)));
+                lines.push(format!("udt amount: {}", requirement.udt_amount));
+            }
+        }
+        SighashMode::LimitOrder => {
+            let (intent, payment_output_index) =
+                witness_lock.payload.split_at(LIMIT_ORDER_INTENT_SIZE);
+            let intent = LimitOrderIntent::from_slice(intent)?;
+            let asset = |type_hash: &Option<[u8; 32]>| match type_hash {
+                Some(type_hash) => encode_hex(type_hash),
+                None => "CKB".to_string(),
+            };
+            lines.push(format!("sell: {}", asset(&intent.sell_type_hash)));
+            lines.push(format!("buy: {}", asset(&intent.buy_type_hash)));
+            lines.push(format!(
+                "price: {}/{}",
+                intent.price_numerator, intent.price_denominator
+            ));
+            let mut index = [0u8; 4];
+            index.copy_from_slice(payment_output_index);
+            lines.push(format!(
+                "payment output index: {}",
+                u32::from_le_bytes(index)
+            ));
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn describe_extension(lines: &mut Vec<String>, extension: &WitnessExtension) {
+    if let Some(expiry) = extension.expiry {
+        lines.push(format!("expiry: {:#x}", expiry));
+    }
+    if extension.commit_cell_deps {
+        lines.push("cell deps: committed".to_string());
+    }
+    if extension.commit_header_deps {
+        lines.push("header deps: committed".to_string());
+    }
+    if let Some(scope) = &extension.otx_scope {
+        lines.push(format!(
+            "otx scope: inputs {}+{}, outputs {}+{}",
+            scope.input_start, scope.input_count, scope.output_start, scope.output_count
+        ));
+    }
+    if let Some(delegation) = &extension.delegation {
+        lines.push(format!(
+            "session pubkey hash: {}",
+            encode_hex(&delegation.session_pubkey_hash)
+        ));
+        lines.push(format!("allowed modes: {:#010b}", delegation.allowed_modes));
+        lines.push(format!("delegation expiry: {:#x}", delegation.expiry));
+        lines.push(format!("max capacity out: {}", delegation.max_capacity_out));
+    }
+    if extension.recovery {
+        lines.push("key: recovery".to_string());
+    }
+    if let Some(max_fee) = extension.max_fee {
+        lines.push(format!("max fee: {}", max_fee));
+    }
+}
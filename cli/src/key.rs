@@ -0,0 +1,51 @@
+use crate::error::Error;
+use crate::hex::decode_hex;
+
+use std::fs;
+use std::path::Path;
+
+use ckb_crypto::secp::{Privkey, Pubkey};
+use ckb_hash::blake2b_256;
+use ckb_types::H256;
+use otx_sighash_core::{SighashMode, BLAKE160_SIZE};
+
+/// Reads a private key from the first line of `path`, in hex as `ckb-cli` exports it.
+pub fn read_privkey(path: &Path) -> Result<Privkey, Error> {
+    let content = fs::read_to_string(path)?;
+    let key = decode_hex(content.lines().next().unwrap_or_default().trim())
+        .map_err(|_| Error::InvalidPrivkey)?;
+    let key = H256::from_slice(&key).map_err(|_| Error::InvalidPrivkey)?;
+    let privkey = Privkey::from(key);
+    privkey.pubkey().map_err(|_| Error::InvalidPrivkey)?;
+    Ok(privkey)
+}
+
+/// Parses a compressed public key in hex.
+pub fn parse_pubkey(hex: &str) -> Result<Pubkey, Error> {
+    let pubkey = decode_hex(hex).map_err(|_| Error::InvalidPubkey)?;
+    Pubkey::from_slice(&pubkey).map_err(|_| Error::InvalidPubkey)
+}
+
+/// The pubkey hash of the lock args, `blake160` of the compressed public key.
+pub fn pubkey_hash(pubkey: &Pubkey) -> [u8; BLAKE160_SIZE] {
+    let mut pubkey_hash = [0u8; BLAKE160_SIZE];
+    pubkey_hash.copy_from_slice(&blake2b_256(pubkey.serialize())[..BLAKE160_SIZE]);
+    pubkey_hash
+}
+
+/// Parses a sighash mode by name, such as `single-acp`, or by its byte, such as `0x83`.
+pub fn parse_mode(value: &str) -> Result<SighashMode, String> {
+    let byte = match value {
+        "all" => 0x01,
+        "none" => 0x02,
+        "single" => 0x03,
+        "all-acp" => 0x81,
+        "none-acp" => 0x82,
+        "single-acp" => 0x83,
+        "single-udt-acp" => 0x84,
+        "limit-order" => 0x85,
+        _ => u8::from_str_radix(value.strip_prefix("0x").unwrap_or(value), 16)
+            .map_err(|_| format!("unknown sighash mode {}", value))?,
+    };
+    SighashMode::from_byte(byte).map_err(|_| format!("unknown sighash mode {}", value))
+}
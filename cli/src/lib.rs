@@ -0,0 +1,23 @@
+//! The library behind `otx-lock`, the command-line tool of the otx-sighash-lock.
+//!
+//! Transactions are read as JSON, either a bare `Transaction` or a mock transaction of
+//! `ckb-debugger`, which also carries the cells and headers the transaction depends on.
+//! `inspect` decodes a witness lock and the preimage its signature covers, `describe_diff`
+//! tells which fields of a preimage changed, and `verify` runs the scripts of a mock
+//! transaction, such as a freshly built contract.
+
+mod error;
+mod hex;
+mod inspect;
+mod key;
+mod script;
+mod tx_file;
+mod verify;
+
+pub use error::Error;
+pub use hex::{decode_hex, encode_hex};
+pub use inspect::{describe_diff, inspect};
+pub use key::{parse_mode, parse_pubkey, pubkey_hash, read_privkey};
+pub use script::lock_script;
+pub use tx_file::TxFile;
+pub use verify::{set_cell_dep_data, verify};
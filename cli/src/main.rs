@@ -0,0 +1,293 @@
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use ckb_types::{bytes::Bytes, core::ScriptHashType, prelude::*, H256};
+use clap::{Parser, Subcommand, ValueEnum};
+use otx_sighash_core::{LockArgs, Recovery, SighashMode, BLAKE160_SIZE};
+use otx_sighash_lock_cli::{
+    decode_hex, describe_diff, encode_hex, inspect, lock_script, parse_mode, parse_pubkey,
+    pubkey_hash, read_privkey, set_cell_dep_data, verify, TxFile,
+};
+use otx_sighash_lock_sdk::{diff_preimages, signing_preimage, OtxSigner, Preimage};
+
+/// Builds, signs, inspects and verifies otx-sighash-lock transactions.
+///
+/// Transactions are JSON files, a bare transaction or a mock transaction of ckb-debugger.
+#[derive(Parser)]
+#[command(name = "otx-lock", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Prints the lock script of a key and its hash
+    LockScript {
+        /// Code hash of the deployed contract
+        #[arg(long, value_parser = parse_h256)]
+        code_hash: H256,
+        #[arg(long, value_enum, default_value_t = HashType::Type)]
+        hash_type: HashType,
+        /// File holding the private key in hex
+        #[arg(
+            long,
+            required_unless_present_any = ["pubkey", "identity"],
+            conflicts_with = "pubkey"
+        )]
+        privkey_file: Option<PathBuf>,
+        /// Compressed public key in hex
+        #[arg(long)]
+        pubkey: Option<String>,
+        /// Least anyone-can-pay payment, 10^x shannons
+        #[arg(long)]
+        min_ckb: Option<u8>,
+        /// Least anyone-can-pay payment, 10^x UDT base units
+        #[arg(long)]
+        min_udt: Option<u8>,
+        /// Type hash of the identity cell listing the owner keys, which replace the key
+        #[arg(long, value_parser = parse_h256)]
+        identity: Option<H256>,
+        /// Compressed public key of the recovery key in hex
+        #[arg(long, requires = "recovery_delay")]
+        recovery_pubkey: Option<String>,
+        /// Relative `since` value an input must age by before the recovery key unlocks it,
+        /// such as 0x8000000000000064 for 100 blocks
+        #[arg(long, value_parser = parse_u64, requires = "recovery_pubkey")]
+        recovery_delay: Option<u64>,
+        /// Type hash of the revocation cell listing revoked signatures
+        #[arg(long, value_parser = parse_h256)]
+        revocation: Option<H256>,
+    },
+    /// Signs the witness lock of an input, keeping the other fields of its witness
+    Sign {
+        #[arg(long)]
+        tx: PathBuf,
+        #[arg(long)]
+        input: usize,
+        /// Sighash mode by name, such as single-acp, or by byte, such as 0x83
+        #[arg(long, value_parser = parse_mode)]
+        mode: SighashMode,
+        /// File holding the private key in hex
+        #[arg(long)]
+        privkey_file: PathBuf,
+        /// Mode specific payload following the signature, in hex
+        #[arg(long, value_parser = parse_bytes, default_value = "0x")]
+        payload: Bytes,
+        /// Witness extension following the payload, in hex
+        #[arg(long, value_parser = parse_bytes, default_value = "0x")]
+        extension: Bytes,
+        /// Inputs of the lock group, read from the cells of a mock transaction by default
+        #[arg(long, value_delimiter = ',')]
+        group_inputs: Option<Vec<usize>>,
+        /// Writes the signed transaction there instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Prints the decoded witness lock of an input and the preimage of its signature
+    Inspect {
+        #[arg(long)]
+        tx: PathBuf,
+        #[arg(long)]
+        input: usize,
+        /// Builds the preimage in this mode instead of the mode of the witness lock
+        #[arg(long, value_parser = parse_mode)]
+        mode: Option<SighashMode>,
+        /// Inputs of the lock group, read from the cells of a mock transaction by default
+        #[arg(long, value_delimiter = ',')]
+        group_inputs: Option<Vec<usize>>,
+    },
+    /// Prints the preimage fields of an input that differ in another transaction
+    Diff {
+        #[arg(long)]
+        tx: PathBuf,
+        #[arg(long)]
+        input: usize,
+        /// The transaction compared with, such as the one that was signed
+        #[arg(long)]
+        other_tx: PathBuf,
+        /// Index of the input in the other transaction, the same by default
+        #[arg(long)]
+        other_input: Option<usize>,
+        /// Builds both preimages in this mode instead of the modes of the witness locks
+        #[arg(long, value_parser = parse_mode)]
+        mode: Option<SighashMode>,
+    },
+    /// Runs the scripts of a mock transaction
+    Verify {
+        #[arg(long)]
+        mock_tx: PathBuf,
+        /// Contract binary replacing the data of a cell dep of the mock info, which the scripts
+        /// must load by type
+        #[arg(long, requires = "cell_dep")]
+        binary: Option<PathBuf>,
+        /// Index of the replaced cell in the cell deps of the mock info
+        #[arg(long, requires = "binary")]
+        cell_dep: Option<usize>,
+        #[arg(long, default_value_t = 70_000_000)]
+        max_cycles: u64,
+    },
+}
+
+#[derive(Clone, ValueEnum)]
+enum HashType {
+    Data,
+    Type,
+    Data1,
+}
+
+impl From<HashType> for ScriptHashType {
+    fn from(hash_type: HashType) -> Self {
+        match hash_type {
+            HashType::Data => ScriptHashType::Data,
+            HashType::Type => ScriptHashType::Type,
+            HashType::Data1 => ScriptHashType::Data1,
+        }
+    }
+}
+
+fn parse_h256(value: &str) -> Result<H256, String> {
+    let hash = decode_hex(value).map_err(|err| err.to_string())?;
+    H256::from_slice(&hash).map_err(|_| "expected 32 bytes".to_string())
+}
+
+fn parse_bytes(value: &str) -> Result<Bytes, String> {
+    decode_hex(value)
+        .map(Bytes::from)
+        .map_err(|err| err.to_string())
+}
+
+fn parse_u64(value: &str) -> Result<u64, String> {
+    match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    }
+    .map_err(|err| err.to_string())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    match Cli::parse().command {
+        Command::LockScript {
+            code_hash,
+            hash_type,
+            privkey_file,
+            pubkey,
+            min_ckb,
+            min_udt,
+            identity,
+            recovery_pubkey,
+            recovery_delay,
+            revocation,
+        } => {
+            let recovery = match (recovery_pubkey, recovery_delay) {
+                (Some(pubkey), Some(delay)) => Some(Recovery {
+                    pubkey_hash: pubkey_hash(&parse_pubkey(&pubkey)?),
+                    delay,
+                }),
+                _ => None,
+            };
+            // the identity cell replaces the key, left zeroed
+            let pubkey_hash = match (privkey_file, pubkey) {
+                (Some(privkey_file), _) => pubkey_hash(&read_privkey(&privkey_file)?.pubkey()?),
+                (None, Some(pubkey)) => pubkey_hash(&parse_pubkey(&pubkey)?),
+                (None, None) => [0u8; BLAKE160_SIZE],
+            };
+            let lock_args = LockArgs {
+                pubkey_hash,
+                min_ckb,
+                min_udt,
+                identity_type_hash: identity.map(Into::into),
+                recovery,
+                revocation_type_hash: revocation.map(Into::into),
+            };
+            let lock = lock_script(&code_hash, hash_type.into(), &lock_args)?;
+            let lock_hash = lock.calc_script_hash();
+            let output = serde_json::json!({
+                "lock": ckb_jsonrpc_types::Script::from(lock),
+                "lock_hash": encode_hex(lock_hash.as_slice()),
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        Command::Sign {
+            tx,
+            input,
+            mode,
+            privkey_file,
+            payload,
+            extension,
+            group_inputs,
+            output,
+        } => {
+            let mut tx_file = TxFile::read(&tx)?;
+            let group_inputs = match group_inputs {
+                Some(group_inputs) => group_inputs,
+                None => tx_file.group_inputs(input)?,
+            };
+            let privkey = read_privkey(&privkey_file)?;
+            let signed = OtxSigner::new(&privkey).group_inputs(group_inputs).sign(
+                &tx_file.tx(),
+                input,
+                mode,
+                &payload,
+                &extension,
+            )?;
+            tx_file.set_tx(&signed);
+            match output {
+                Some(output) => fs::write(output, tx_file.to_json())?,
+                None => println!("{}", tx_file.to_json()),
+            }
+        }
+        Command::Inspect {
+            tx,
+            input,
+            mode,
+            group_inputs,
+        } => {
+            let tx_file = TxFile::read(&tx)?;
+            let group_inputs = match group_inputs {
+                Some(group_inputs) => group_inputs,
+                None => tx_file.group_inputs(input)?,
+            };
+            print!("{}", inspect(&tx_file.tx(), input, &group_inputs, mode)?);
+        }
+        Command::Diff {
+            tx,
+            input,
+            other_tx,
+            other_input,
+            mode,
+        } => {
+            let preimage = |path: &PathBuf, input: usize| -> Result<Preimage, Box<dyn Error>> {
+                let tx_file = TxFile::read(path)?;
+                let group_inputs = tx_file.group_inputs(input)?;
+                Ok(signing_preimage(&tx_file.tx(), input, &group_inputs, mode)?)
+            };
+            let diffs = diff_preimages(
+                &preimage(&tx, input)?,
+                &preimage(&other_tx, other_input.unwrap_or(input))?,
+            );
+            if diffs.is_empty() {
+                println!("the preimages match");
+            }
+            print!("{}", describe_diff(&diffs));
+        }
+        Command::Verify {
+            mock_tx,
+            binary,
+            cell_dep,
+            max_cycles,
+        } => {
+            let mut mock_tx = match TxFile::read(&mock_tx)? {
+                TxFile::Mock(mock_tx) => mock_tx,
+                TxFile::Tx(_) => return Err("expected a mock transaction".into()),
+            };
+            if let (Some(binary), Some(cell_dep)) = (binary, cell_dep) {
+                set_cell_dep_data(&mut mock_tx, cell_dep, fs::read(binary)?.into())?;
+            }
+            let cycles = verify(mock_tx, max_cycles)?;
+            println!("ok, {} cycles", cycles);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use crate::error::Error;
+
+use ckb_types::{bytes::Bytes, core::ScriptHashType, packed::Script, prelude::*, H256};
+use otx_sighash_core::{LockArgs, ARGS_IDENTITY, ARGS_NO_MINIMUM, ARGS_RECOVERY, ARGS_REVOCATION};
+
+/// The lock of `lock_args` under the deployment `code_hash`, see `LockArgs` for the encoding.
+///
+/// The args are the shortest encoding of the fields given, and are checked the way the lock
+/// reads them, so that a recovery delay must be a relative `since` value.
+pub fn lock_script(
+    code_hash: &H256,
+    hash_type: ScriptHashType,
+    lock_args: &LockArgs,
+) -> Result<Script, Error> {
+    let mut args = lock_args.pubkey_hash.to_vec();
+    let mut flags = 0;
+    let mut fields = Vec::new();
+    if let Some(identity_type_hash) = &lock_args.identity_type_hash {
+        flags |= ARGS_IDENTITY;
+        fields.extend_from_slice(identity_type_hash);
+    }
+    if let Some(recovery) = &lock_args.recovery {
+        flags |= ARGS_RECOVERY;
+        fields.extend_from_slice(&recovery.pubkey_hash);
+        fields.extend_from_slice(&recovery.delay.to_le_bytes());
+    }
+    if let Some(revocation_type_hash) = &lock_args.revocation_type_hash {
+        flags |= ARGS_REVOCATION;
+        fields.extend_from_slice(revocation_type_hash);
+    }
+    match (lock_args.min_ckb, lock_args.min_udt, flags) {
+        (None, None, 0) => {}
+        (Some(min_ckb), None, 0) => args.push(min_ckb),
+        (min_ckb, min_udt, flags) => {
+            args.extend_from_slice(&[
+                min_ckb.unwrap_or(ARGS_NO_MINIMUM),
+                min_udt.unwrap_or(ARGS_NO_MINIMUM),
+            ]);
+            if flags != 0 {
+                args.push(flags);
+                args.extend_from_slice(&fields);
+            }
+        }
+    }
+    LockArgs::from_slice(&args).map_err(|_| Error::InvalidArgs)?;
+    Ok(Script::new_builder()
+        .code_hash(code_hash.pack())
+        .hash_type(hash_type.into())
+        .args(Bytes::from(args).pack())
+        .build())
+}
@@ -0,0 +1,79 @@
+use crate::error::Error;
+
+use std::fs;
+use std::path::Path;
+
+use ckb_jsonrpc_types as json;
+use ckb_mock_tx_types::ReprMockTransaction;
+use ckb_types::{core::TransactionView, packed};
+
+/// A transaction read from JSON: a bare `Transaction`, or a mock transaction of
+/// `ckb-debugger` that also carries the cells its inputs consume.
+pub enum TxFile {
+    Tx(json::Transaction),
+    Mock(ReprMockTransaction),
+}
+
+impl TxFile {
+    pub fn read(path: &Path) -> Result<TxFile, Error> {
+        let value: serde_json::Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+        if value.get("mock_info").is_some() {
+            Ok(TxFile::Mock(serde_json::from_value(value)?))
+        } else {
+            Ok(TxFile::Tx(serde_json::from_value(value)?))
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        match self {
+            TxFile::Tx(tx) => serde_json::to_string_pretty(tx),
+            TxFile::Mock(mock_tx) => serde_json::to_string_pretty(mock_tx),
+        }
+        .expect("serialize transaction")
+    }
+
+    pub fn tx(&self) -> TransactionView {
+        let tx = match self {
+            TxFile::Tx(tx) => tx,
+            TxFile::Mock(mock_tx) => &mock_tx.tx,
+        };
+        packed::Transaction::from(tx.clone()).into_view()
+    }
+
+    /// Replaces the transaction, keeping the cells of a mock transaction.
+    pub fn set_tx(&mut self, tx: &TransactionView) {
+        let json_tx = json::Transaction::from(tx.data());
+        match self {
+            TxFile::Tx(tx) => *tx = json_tx,
+            TxFile::Mock(mock_tx) => mock_tx.tx = json_tx,
+        }
+    }
+
+    /// The inputs sharing the lock of the input at `index`. A bare transaction does not
+    /// tell the locks of its inputs, all of them are taken.
+    pub fn group_inputs(&self, index: usize) -> Result<Vec<usize>, Error> {
+        let tx = self.tx();
+        if index >= tx.inputs().len() {
+            return Err(Error::InputOutOfBound(index));
+        }
+        let mock_tx = match self {
+            TxFile::Tx(_) => return Ok((0..tx.inputs().len()).collect()),
+            TxFile::Mock(mock_tx) => mock_tx,
+        };
+
+        // mock inputs are matched by out point, they may be listed in any order
+        let lock = |i: usize| {
+            let previous_output = &mock_tx.tx.inputs[i].previous_output;
+            mock_tx
+                .mock_info
+                .inputs
+                .iter()
+                .find(|input| &input.input.previous_output == previous_output)
+                .map(|input| &input.output.lock)
+        };
+        let group_lock = lock(index).ok_or(Error::UnknownCell(index))?;
+        Ok((0..tx.inputs().len())
+            .filter(|i| lock(*i) == Some(group_lock))
+            .collect())
+    }
+}
@@ -0,0 +1,96 @@
+use crate::error::Error;
+
+use ckb_hash::blake2b_256;
+use ckb_jsonrpc_types::{JsonBytes, ScriptHashType};
+use ckb_mock_tx_types::{MockTransaction, ReprMockTransaction};
+use ckb_testtool::context::Context;
+use ckb_types::{bytes::Bytes, H256};
+
+use std::iter;
+
+/// Replaces the data of the cell at `index` in the cell deps of the mock info, such as a
+/// deployed contract with a fresh build.
+///
+/// The replacement is rejected when a script run by the transaction loads the code of the
+/// cell by data hash: updating its code hash would change the lock hashes and the
+/// transaction, voiding the signatures. Such scripts must reference the code by type.
+pub fn set_cell_dep_data(
+    mock_tx: &mut ReprMockTransaction,
+    index: usize,
+    data: Bytes,
+) -> Result<(), Error> {
+    let cell_dep = mock_tx
+        .mock_info
+        .cell_deps
+        .get(index)
+        .ok_or(Error::CellDepOutOfBound(index))?;
+    let data_hash = H256::from(blake2b_256(cell_dep.data.as_bytes()));
+    if data_hash != H256::from(blake2b_256(&data)) && loads_by_data_hash(mock_tx, &data_hash) {
+        return Err(Error::DataHashReference(index));
+    }
+    mock_tx.mock_info.cell_deps[index].data = JsonBytes::from_bytes(data);
+    Ok(())
+}
+
+// whether a script run by the transaction, an input lock or type or an output type, loads
+// the code of `data_hash` by data hash
+fn loads_by_data_hash(mock_tx: &ReprMockTransaction, data_hash: &H256) -> bool {
+    let input_scripts = mock_tx
+        .mock_info
+        .inputs
+        .iter()
+        .flat_map(|input| iter::once(&input.output.lock).chain(input.output.type_.as_ref()));
+    let output_scripts = mock_tx
+        .tx
+        .outputs
+        .iter()
+        .filter_map(|output| output.type_.as_ref());
+    input_scripts
+        .chain(output_scripts)
+        .any(|script| script.hash_type != ScriptHashType::Type && &script.code_hash == data_hash)
+}
+
+/// Runs every script of the mock transaction, returning the cycles consumed.
+pub fn verify(mock_tx: ReprMockTransaction, max_cycles: u64) -> Result<u64, Error> {
+    let mock_tx = MockTransaction::from(mock_tx);
+
+    let mut context = Context::default();
+    // headers first, cells committed in one of them are linked to it, the block of other
+    // cells cannot be loaded by scripts anyway
+    for header in &mock_tx.mock_info.header_deps {
+        context.insert_header(header.clone());
+    }
+    let cells = mock_tx
+        .mock_info
+        .inputs
+        .iter()
+        .map(|input| {
+            (
+                input.input.previous_output(),
+                &input.output,
+                &input.data,
+                &input.header,
+            )
+        })
+        .chain(mock_tx.mock_info.cell_deps.iter().map(|cell_dep| {
+            (
+                cell_dep.cell_dep.out_point(),
+                &cell_dep.output,
+                &cell_dep.data,
+                &cell_dep.header,
+            )
+        }));
+    for (out_point, output, data, header) in cells {
+        context.create_cell_with_out_point(out_point.clone(), output.clone(), data.clone());
+        if let Some(block_hash) = header
+            .as_ref()
+            .filter(|hash| context.headers.contains_key(hash))
+        {
+            context.link_cell_with_block(out_point, block_hash.clone(), 0);
+        }
+    }
+
+    context
+        .verify_tx(&mock_tx.tx.clone().into_view(), max_cycles)
+        .map_err(|err| Error::Rejected(err.to_string()))
+}
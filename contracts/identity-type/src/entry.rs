@@ -0,0 +1,13 @@
+// Import from `core` instead of from `std` since we are in no-std mode
+use core::result::Result;
+
+use type_id_guard::{validate_type_id, Error};
+
+const BLAKE160_SIZE: usize = 20;
+
+/// Guards the identity cell referenced by otx-sighash-lock args, see `validate_type_id`.
+///
+/// The data lists the authorized pubkey hashes: `n * pubkey_hash (20 bytes)`, at least one.
+pub fn main() -> Result<(), Error> {
+    validate_type_id(|data| !data.is_empty() && data.len() % BLAKE160_SIZE == 0)
+}
@@ -1,29 +1,16 @@
 use ckb_std::error::SysError;
 
-/// Error
-#[repr(i8)]
-pub enum Error {
-    IndexOutOfBound = 1,
-    ItemMissing,
-    LengthNotEnough,
-    Encoding,
-    // Add customized errors here...
-    UnsupportedSighashMode,
-    Secp256k1,
-    WrongPubkey,
-    LoadPrefilledData,
-    RecoverPubkey,
-}
+/// Error, shared with the native verifier, the exit codes are listed in `otx-sighash-core`.
+pub use otx_sighash_core::Error;
 
-impl From<SysError> for Error {
-    fn from(err: SysError) -> Self {
-        use SysError::*;
-        match err {
-            IndexOutOfBound => Self::IndexOutOfBound,
-            ItemMissing => Self::ItemMissing,
-            LengthNotEnough(_) => Self::LengthNotEnough,
-            Encoding => Self::Encoding,
-            Unknown(err_code) => panic!("unexpected sys error {}", err_code),
-        }
+/// Converts a syscall error, `Error` being defined in another crate.
+pub(crate) fn sys_error(err: SysError) -> Error {
+    use SysError::*;
+    match err {
+        IndexOutOfBound => Error::IndexOutOfBound,
+        ItemMissing => Error::ItemMissing,
+        LengthNotEnough(_) => Error::LengthNotEnough,
+        Encoding => Error::Encoding,
+        Unknown(err_code) => panic!("unexpected sys error {}", err_code),
     }
 }
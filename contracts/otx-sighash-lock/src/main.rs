@@ -10,9 +10,7 @@
 // define modules
 mod entry;
 mod error;
-mod helper;
-mod types;
-mod validate;
+mod syscalls;
 
 #[cfg(test)]
 extern crate alloc;
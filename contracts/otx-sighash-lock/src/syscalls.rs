@@ -0,0 +1,179 @@
+use crate::error::{sys_error, Error};
+
+use ckb_lib_secp256k1::LibSecp256k1;
+use otx_sighash_core::{Header, ScriptAccess, Source, TxAccess};
+
+// Import heap related library from `alloc`
+// https://doc.rust-lang.org/alloc/index.html
+use alloc::vec::Vec;
+
+// Import CKB syscalls and structures
+// https://docs.rs/ckb-std/
+use ckb_std::{
+    ckb_constants,
+    ckb_types::{bytes::Bytes, prelude::*},
+    debug,
+    error::SysError,
+    high_level::{
+        load_cell, load_cell_capacity, load_cell_data, load_cell_lock_hash, load_cell_type_hash,
+        load_header, load_input, load_input_since, load_script, load_script_hash, load_transaction,
+        load_tx_hash, load_witness, QueryIter,
+    },
+};
+
+/// The running script and its transaction, read through syscalls.
+pub(crate) struct Syscalls<'a> {
+    pub lib: &'a LibSecp256k1,
+}
+
+fn source(source: Source) -> ckb_constants::Source {
+    match source {
+        Source::Input => ckb_constants::Source::Input,
+        Source::Output => ckb_constants::Source::Output,
+        Source::CellDep => ckb_constants::Source::CellDep,
+        Source::GroupInput => ckb_constants::Source::GroupInput,
+    }
+}
+
+impl<'a> TxAccess for Syscalls<'a> {
+    type Error = Error;
+
+    fn inputs_len(&self) -> Result<usize, Error> {
+        Ok(QueryIter::new(load_input, ckb_constants::Source::Input).count())
+    }
+
+    fn input(&self, index: usize) -> Result<Vec<u8>, Error> {
+        let input = load_input(index, ckb_constants::Source::Input).map_err(sys_error)?;
+        Ok(input.as_slice().to_vec())
+    }
+
+    fn outputs_len(&self) -> Result<usize, Error> {
+        Ok(QueryIter::new(load_cell_lock_hash, ckb_constants::Source::Output).count())
+    }
+
+    fn output(&self, index: usize) -> Result<Vec<u8>, Error> {
+        let output = load_cell(index, ckb_constants::Source::Output).map_err(sys_error)?;
+        Ok(output.as_slice().to_vec())
+    }
+
+    fn output_data(&self, index: usize) -> Result<Vec<u8>, Error> {
+        load_cell_data(index, ckb_constants::Source::Output).map_err(sys_error)
+    }
+
+    fn output_lock_hash(&self, index: usize) -> Result<[u8; 32], Error> {
+        load_cell_lock_hash(index, ckb_constants::Source::Output).map_err(sys_error)
+    }
+
+    fn output_type_hash(&self, index: usize) -> Result<Option<[u8; 32]>, Error> {
+        load_cell_type_hash(index, ckb_constants::Source::Output).map_err(sys_error)
+    }
+
+    fn input_witness(&self, index: usize) -> Result<Option<Vec<u8>>, Error> {
+        match load_witness(index, ckb_constants::Source::Input) {
+            Ok(witness) => Ok(Some(witness)),
+            Err(SysError::IndexOutOfBound) => Ok(None),
+            Err(err) => Err(sys_error(err)),
+        }
+    }
+
+    fn cell_deps(&self) -> Result<Vec<u8>, Error> {
+        let tx = load_transaction().map_err(sys_error)?;
+        Ok(tx.raw().cell_deps().as_slice().to_vec())
+    }
+
+    fn header_deps(&self) -> Result<Vec<u8>, Error> {
+        let tx = load_transaction().map_err(sys_error)?;
+        Ok(tx.raw().header_deps().as_slice().to_vec())
+    }
+}
+
+impl<'a> ScriptAccess for Syscalls<'a> {
+    fn script_args(&self) -> Result<Vec<u8>, Error> {
+        let args: Bytes = load_script().map_err(sys_error)?.args().unpack();
+        debug!("script args is {:?}", args);
+        Ok(args.to_vec())
+    }
+
+    fn script_hash(&self) -> Result<[u8; 32], Error> {
+        load_script_hash().map_err(sys_error)
+    }
+
+    fn tx_hash(&self) -> Result<[u8; 32], Error> {
+        load_tx_hash().map_err(sys_error)
+    }
+
+    fn cells_len(&self, source: Source) -> Result<usize, Error> {
+        Ok(QueryIter::new(load_cell_capacity, self::source(source)).count())
+    }
+
+    fn cell(&self, index: usize, source: Source) -> Result<Vec<u8>, Error> {
+        let cell = load_cell(index, self::source(source)).map_err(sys_error)?;
+        Ok(cell.as_slice().to_vec())
+    }
+
+    fn cell_capacity(&self, index: usize, source: Source) -> Result<u64, Error> {
+        load_cell_capacity(index, self::source(source)).map_err(sys_error)
+    }
+
+    fn cell_data(&self, index: usize, source: Source) -> Result<Vec<u8>, Error> {
+        load_cell_data(index, self::source(source)).map_err(sys_error)
+    }
+
+    fn cell_lock_hash(&self, index: usize, source: Source) -> Result<[u8; 32], Error> {
+        load_cell_lock_hash(index, self::source(source)).map_err(sys_error)
+    }
+
+    fn cell_type_hash(&self, index: usize, source: Source) -> Result<Option<[u8; 32]>, Error> {
+        load_cell_type_hash(index, self::source(source)).map_err(sys_error)
+    }
+
+    fn input_since(&self, index: usize) -> Result<u64, Error> {
+        load_input_since(index, ckb_constants::Source::Input).map_err(sys_error)
+    }
+
+    fn witnesses_len(&self) -> Result<usize, Error> {
+        let tx = load_transaction().map_err(sys_error)?;
+        Ok(tx.witnesses().len())
+    }
+
+    fn witness(&self, index: usize, source: Source) -> Result<Vec<u8>, Error> {
+        load_witness(index, self::source(source)).map_err(sys_error)
+    }
+
+    fn headers(&self) -> Result<Vec<Header>, Error> {
+        Ok(
+            QueryIter::new(load_header, ckb_constants::Source::HeaderDep)
+                .map(|header| Header {
+                    number: header.raw().number().unpack(),
+                    epoch: header.raw().epoch().unpack(),
+                })
+                .collect(),
+        )
+    }
+
+    fn recover_pubkey(&self, message: &[u8; 32], signature: &[u8; 65]) -> Result<Vec<u8>, Error> {
+        let prefilled_data = self.lib.load_prefilled_data().map_err(|err| {
+            debug!("load prefilled data error: {}", err);
+            Error::LoadPrefilledData
+        })?;
+        let pubkey = self
+            .lib
+            .recover_pubkey(&prefilled_data, signature, message)
+            .map_err(|err| {
+                debug!("recover pubkey error: {}", err);
+                Error::RecoverPubkey
+            })?;
+        Ok(pubkey.as_slice().to_vec())
+    }
+
+    fn sighash_all_pubkey_hash(&self) -> Result<[u8; 20], Error> {
+        let mut pubkey_hash = [0u8; 20];
+        self.lib
+            .validate_blake2b_sighash_all(&mut pubkey_hash)
+            .map_err(|err_code| {
+                debug!("secp256k1 error {}", err_code);
+                Error::Secp256k1
+            })?;
+        Ok(pubkey_hash)
+    }
+}
@@ -0,0 +1,14 @@
+// Import from `core` instead of from `std` since we are in no-std mode
+use core::result::Result;
+
+use type_id_guard::{validate_type_id, Error};
+
+const REVOCATION_PREFIX_SIZE: usize = 8;
+
+/// Guards the revocation cell referenced by otx-sighash-lock args, see `validate_type_id`.
+///
+/// Being unique, nobody can publish another cell with the same type hash listing fewer
+/// revocations. The data lists the revoked signatures: `n * r_prefix (8 bytes)`, possibly none.
+pub fn main() -> Result<(), Error> {
+    validate_type_id(|data| data.len() % REVOCATION_PREFIX_SIZE == 0)
+}
@@ -0,0 +1,94 @@
+use crate::error::Error;
+use crate::since::SINCE_RELATIVE_FLAG;
+use crate::types::{take, BLAKE160_SIZE};
+
+pub const ARGS_IDENTITY: u8 = 0x01;
+pub const ARGS_RECOVERY: u8 = 0x02;
+pub const ARGS_REVOCATION: u8 = 0x04;
+pub const REVOCATION_PREFIX_SIZE: usize = 8;
+// leaves out an anyone-can-pay minimum when args flags follow
+pub const ARGS_NO_MINIMUM: u8 = 0xff;
+
+/// Lock args: `pubkey_hash (20 bytes) | [min_ckb (1 byte) | [min_udt (1 byte) |
+/// [flags (1 byte) | fields]]]`, where the fields appear in the order of their flag bits.
+///
+/// The optional minimums follow the anyone-can-pay lock: a value `x` means
+/// `10^x` shannons (or `10^x` UDT base units), `ARGS_NO_MINIMUM` leaves it out.
+/// Receiving payments without a signature is only enabled when `min_ckb` is present.
+///
+/// - `ARGS_IDENTITY`: `identity_type_hash (32 bytes)`, the authorized pubkey hashes are
+///   read from the identity cell with this type hash in the cell deps, `pubkey_hash` is
+///   ignored
+/// - `ARGS_RECOVERY`: `recovery_pubkey_hash (20 bytes) | delay (u64)`, see `Recovery`
+/// - `ARGS_REVOCATION`: `revocation_type_hash (32 bytes)`, the revocation cell with this type
+///   hash must be in the cell deps, its data lists the first `REVOCATION_PREFIX_SIZE` bytes of
+///   the revoked signatures
+pub struct LockArgs {
+    pub pubkey_hash: [u8; BLAKE160_SIZE],
+    pub min_ckb: Option<u8>,
+    pub min_udt: Option<u8>,
+    pub identity_type_hash: Option<[u8; 32]>,
+    pub recovery: Option<Recovery>,
+    pub revocation_type_hash: Option<[u8; 32]>,
+}
+
+/// Backup key of a lock: once an input has aged past `delay`, a relative `since` value,
+/// a signature of `pubkey_hash` unlocks it in any mode.
+pub struct Recovery {
+    pub pubkey_hash: [u8; BLAKE160_SIZE],
+    pub delay: u64,
+}
+
+impl LockArgs {
+    pub fn from_slice(args: &[u8]) -> Result<LockArgs, Error> {
+        if args.len() < BLAKE160_SIZE {
+            return Err(Error::InvalidArgs);
+        }
+        let mut pubkey_hash = [0u8; BLAKE160_SIZE];
+        pubkey_hash.copy_from_slice(&args[..BLAKE160_SIZE]);
+        let minimum = |index: usize| args.get(index).copied().filter(|x| *x != ARGS_NO_MINIMUM);
+        let min_ckb = minimum(BLAKE160_SIZE);
+        let min_udt = minimum(BLAKE160_SIZE + 1);
+
+        // 10^x must fit into u64 capacity and u128 UDT amounts
+        if min_ckb.map_or(false, |x| x > 19) || min_udt.map_or(false, |x| x > 38) {
+            return Err(Error::InvalidArgs);
+        }
+
+        let mut lock_args = LockArgs {
+            pubkey_hash,
+            min_ckb,
+            min_udt,
+            identity_type_hash: None,
+            recovery: None,
+            revocation_type_hash: None,
+        };
+        let flags = match args.get(BLAKE160_SIZE + 2) {
+            Some(flags) => *flags,
+            None => return Ok(lock_args),
+        };
+        if flags & !(ARGS_IDENTITY | ARGS_RECOVERY | ARGS_REVOCATION) != 0 {
+            return Err(Error::InvalidArgs);
+        }
+        let mut fields = &args[BLAKE160_SIZE + 3..];
+        if flags & ARGS_IDENTITY != 0 {
+            lock_args.identity_type_hash = Some(take(&mut fields).map_err(|_| Error::InvalidArgs)?);
+        }
+        if flags & ARGS_RECOVERY != 0 {
+            let pubkey_hash = take(&mut fields).map_err(|_| Error::InvalidArgs)?;
+            let delay = u64::from_le_bytes(take(&mut fields).map_err(|_| Error::InvalidArgs)?);
+            if (delay >> 56) & SINCE_RELATIVE_FLAG == 0 {
+                return Err(Error::InvalidArgs);
+            }
+            lock_args.recovery = Some(Recovery { pubkey_hash, delay });
+        }
+        if flags & ARGS_REVOCATION != 0 {
+            lock_args.revocation_type_hash =
+                Some(take(&mut fields).map_err(|_| Error::InvalidArgs)?);
+        }
+        if !fields.is_empty() {
+            return Err(Error::InvalidArgs);
+        }
+        Ok(lock_args)
+    }
+}
@@ -0,0 +1,186 @@
+//! Minimal reader of the CKB transaction CoBuild witness layout.
+//!
+//! ```text
+//! union WitnessLayout {
+//!     SighashAll: 4278190081,
+//!     SighashAllOnly: 4278190082,
+//!     Otx: 4278190083,
+//!     OtxStart: 4278190084,
+//! }
+//! ```
+//!
+//! Only the fields that the lock needs are decoded, `Message` is kept as raw molecule bytes.
+
+use crate::error::Error;
+
+use alloc::vec::Vec;
+
+use blake2b_ref::{Blake2b, Blake2bBuilder};
+
+pub const WITNESS_LAYOUT_SIGHASH_ALL: u32 = 4278190081;
+pub const WITNESS_LAYOUT_SIGHASH_ALL_ONLY: u32 = 4278190082;
+pub const WITNESS_LAYOUT_OTX: u32 = 4278190083;
+pub const WITNESS_LAYOUT_OTX_START: u32 = 4278190084;
+
+pub struct OtxStart {
+    pub start_input_cell: u32,
+    pub start_output_cell: u32,
+    pub start_cell_deps: u32,
+    pub start_header_deps: u32,
+}
+
+pub struct SealPair<'a> {
+    pub script_hash: &'a [u8],
+    pub seal: &'a [u8],
+}
+
+pub struct Otx<'a> {
+    pub input_cells: u32,
+    pub output_cells: u32,
+    pub cell_deps: u32,
+    pub header_deps: u32,
+    pub message: &'a [u8],
+    pub seals: Vec<SealPair<'a>>,
+}
+
+pub enum WitnessLayout<'a> {
+    SighashAll { message: &'a [u8], seal: &'a [u8] },
+    SighashAllOnly { seal: &'a [u8] },
+    Otx(Otx<'a>),
+    OtxStart(OtxStart),
+}
+
+impl<'a> WitnessLayout<'a> {
+    /// Returns `None` when the witness does not use the CoBuild layout, e.g. a `WitnessArgs`.
+    pub fn from_slice(data: &'a [u8]) -> Result<Option<WitnessLayout<'a>>, Error> {
+        if data.len() < 4 {
+            return Ok(None);
+        }
+        let item = &data[4..];
+        let layout = match read_u32(&data[..4])? {
+            WITNESS_LAYOUT_SIGHASH_ALL => {
+                let fields = table_fields(item, 2)?;
+                WitnessLayout::SighashAll {
+                    message: fields[0],
+                    seal: read_bytes(fields[1])?,
+                }
+            }
+            WITNESS_LAYOUT_SIGHASH_ALL_ONLY => {
+                let fields = table_fields(item, 1)?;
+                WitnessLayout::SighashAllOnly {
+                    seal: read_bytes(fields[0])?,
+                }
+            }
+            WITNESS_LAYOUT_OTX => {
+                let fields = table_fields(item, 6)?;
+                let mut seals = Vec::new();
+                for seal_pair in dynvec_items(fields[5])? {
+                    let seal_pair = table_fields(seal_pair, 2)?;
+                    if seal_pair[0].len() != 32 {
+                        return Err(Error::Encoding);
+                    }
+                    seals.push(SealPair {
+                        script_hash: seal_pair[0],
+                        seal: read_bytes(seal_pair[1])?,
+                    });
+                }
+                WitnessLayout::Otx(Otx {
+                    input_cells: read_u32(fields[0])?,
+                    output_cells: read_u32(fields[1])?,
+                    cell_deps: read_u32(fields[2])?,
+                    header_deps: read_u32(fields[3])?,
+                    message: fields[4],
+                    seals,
+                })
+            }
+            WITNESS_LAYOUT_OTX_START => {
+                let fields = table_fields(item, 4)?;
+                WitnessLayout::OtxStart(OtxStart {
+                    start_input_cell: read_u32(fields[0])?,
+                    start_output_cell: read_u32(fields[1])?,
+                    start_cell_deps: read_u32(fields[2])?,
+                    start_header_deps: read_u32(fields[3])?,
+                })
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(layout))
+    }
+}
+
+pub fn new_sighash_all_blake2b() -> Blake2b {
+    Blake2bBuilder::new(32)
+        .personal(b"ckb-tcob-sighash")
+        .build()
+}
+
+pub fn new_sighash_all_only_blake2b() -> Blake2b {
+    Blake2bBuilder::new(32)
+        .personal(b"ckb-tcob-sgohash")
+        .build()
+}
+
+pub fn new_otx_blake2b() -> Blake2b {
+    Blake2bBuilder::new(32)
+        .personal(b"ckb-tcob-otxhash")
+        .build()
+}
+
+fn read_u32(data: &[u8]) -> Result<u32, Error> {
+    if data.len() != 4 {
+        return Err(Error::Encoding);
+    }
+    let mut buf = [0u8; 4];
+    buf.copy_from_slice(data);
+    Ok(u32::from_le_bytes(buf))
+}
+
+// reads molecule `Bytes`, a fixvec of bytes
+fn read_bytes(data: &[u8]) -> Result<&[u8], Error> {
+    if data.len() < 4 || read_u32(&data[..4])? as usize != data.len() - 4 {
+        return Err(Error::Encoding);
+    }
+    Ok(&data[4..])
+}
+
+// splits a dynvec or a table into its items
+fn dynvec_items(data: &[u8]) -> Result<Vec<&[u8]>, Error> {
+    if data.len() < 4 || read_u32(&data[..4])? as usize != data.len() {
+        return Err(Error::Encoding);
+    }
+    if data.len() == 4 {
+        return Ok(Vec::new());
+    }
+    if data.len() < 8 {
+        return Err(Error::Encoding);
+    }
+    let first_offset = read_u32(&data[4..8])? as usize;
+    if first_offset % 4 != 0 || first_offset < 8 || first_offset > data.len() {
+        return Err(Error::Encoding);
+    }
+    let count = first_offset / 4 - 1;
+    let mut offsets = Vec::with_capacity(count + 1);
+    for i in 0..count {
+        offsets.push(read_u32(&data[4 + i * 4..8 + i * 4])? as usize);
+    }
+    offsets.push(data.len());
+
+    let mut items = Vec::with_capacity(count);
+    for window in offsets.windows(2) {
+        if window[0] > window[1] {
+            return Err(Error::Encoding);
+        }
+        items.push(&data[window[0]..window[1]]);
+    }
+    Ok(items)
+}
+
+// returns the first `count` fields of a table, newer versions may append more fields
+fn table_fields(data: &[u8], count: usize) -> Result<Vec<&[u8]>, Error> {
+    let mut fields = dynvec_items(data)?;
+    if fields.len() < count {
+        return Err(Error::Encoding);
+    }
+    fields.truncate(count);
+    Ok(fields)
+}
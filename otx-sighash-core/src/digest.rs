@@ -0,0 +1,461 @@
+use crate::error::Error;
+use crate::molecule::{bytes, fixvec_len, table, witness_args, witness_args_fields};
+use crate::prefix::{
+    add_prefix, new_blake2b, prefix_preimage, push_hex_line, readable_prefix_preimage,
+};
+use crate::types::{
+    Delegation, OtxScope, SighashMode, UdtRequirement, WitnessLock, DELEGATION_CERTIFICATE,
+    LIMIT_ORDER_INTENT_SIZE, SIGHASH_ALL_SIGNATURE_SIZE, UDT_AMOUNT_SIZE,
+};
+
+use core::fmt::Arguments;
+use core::ops::Range;
+
+use alloc::{format, string::String, vec, vec::Vec};
+
+use blake2b_ref::Blake2b;
+
+/// Read access to the transaction being signed or verified. Structures are returned
+/// molecule encoded, exactly as they are serialized in the transaction.
+pub trait TxAccess {
+    type Error: From<Error>;
+
+    fn inputs_len(&self) -> Result<usize, Self::Error>;
+    /// The `CellInput` at `index`.
+    fn input(&self, index: usize) -> Result<Vec<u8>, Self::Error>;
+    fn outputs_len(&self) -> Result<usize, Self::Error>;
+    /// The `CellOutput` at `index`.
+    fn output(&self, index: usize) -> Result<Vec<u8>, Self::Error>;
+    /// The raw data of the output at `index`, without the `Bytes` header.
+    fn output_data(&self, index: usize) -> Result<Vec<u8>, Self::Error>;
+    fn output_lock_hash(&self, index: usize) -> Result<[u8; 32], Self::Error>;
+    fn output_type_hash(&self, index: usize) -> Result<Option<[u8; 32]>, Self::Error>;
+    /// The raw witness at `index`, `None` past the last witness. Both a missing and an
+    /// empty witness are read as a `WitnessArgs` without any field.
+    fn input_witness(&self, index: usize) -> Result<Option<Vec<u8>>, Self::Error>;
+    /// The `CellDepVec` of the transaction.
+    fn cell_deps(&self) -> Result<Vec<u8>, Self::Error>;
+    /// The `Byte32Vec` of the header deps.
+    fn header_deps(&self) -> Result<Vec<u8>, Self::Error>;
+}
+
+/// Message signed by the witness lock of the input at `index`.
+///
+/// `group_inputs` lists the inputs of the lock group, whose witnesses ALL|ANYONECANPAY
+/// partly commits to.
+pub fn signing_message<T: TxAccess>(
+    tx: &T,
+    index: usize,
+    group_inputs: &[usize],
+    witness_lock: &WitnessLock,
+) -> Result<[u8; 32], T::Error> {
+    Ok(preimage(tx, index, group_inputs, witness_lock, Hasher::new(false))?.message)
+}
+
+/// Like `signing_message`, keeping every field hashed on the way, so that a failing
+/// signature can be traced back to the field that changed.
+pub fn signing_preimage<T: TxAccess>(
+    tx: &T,
+    index: usize,
+    group_inputs: &[usize],
+    witness_lock: &WitnessLock,
+) -> Result<Preimage, T::Error> {
+    preimage(tx, index, group_inputs, witness_lock, Hasher::new(true))
+}
+
+/// A field hashed into the digest of a signing message.
+pub struct PreimagePart {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+/// What a signing message is made of.
+pub struct Preimage {
+    /// The fields hashed into the digest, in order. Lengths and counts are parts of their
+    /// own, named after the field they precede.
+    pub parts: Vec<PreimagePart>,
+    pub digest: [u8; 32],
+    /// The bytes hashed into the message: the prefix of the mode followed by the digest,
+    /// or the readable text ending with it, see `add_readable_prefix`.
+    pub prefixed: Vec<u8>,
+    pub message: [u8; 32],
+}
+
+fn preimage<T: TxAccess>(
+    tx: &T,
+    index: usize,
+    group_inputs: &[usize],
+    witness_lock: &WitnessLock,
+    hasher: Hasher,
+) -> Result<Preimage, T::Error> {
+    match witness_lock.mode {
+        SighashMode::AllAnyoneCanPay => {
+            sighash_all_acp_preimage(tx, index, group_inputs, witness_lock, hasher)
+        }
+        SighashMode::SingleAnyoneCanPay => {
+            sighash_single_acp_preimage(tx, index, witness_lock, hasher)
+        }
+        SighashMode::SingleUdtAnyoneCanPay => {
+            sighash_single_udt_acp_preimage(tx, index, witness_lock, hasher)
+        }
+        SighashMode::LimitOrder => limit_order_preimage(tx, witness_lock, hasher),
+        _ => Err(Error::UnsupportedSighashMode.into()),
+    }
+}
+
+pub fn sighash_single_acp_message<T: TxAccess>(
+    tx: &T,
+    index: usize,
+    witness_lock: &WitnessLock,
+) -> Result<[u8; 32], T::Error> {
+    Ok(sighash_single_acp_preimage(tx, index, witness_lock, Hasher::new(false))?.message)
+}
+
+fn sighash_single_acp_preimage<T: TxAccess>(
+    tx: &T,
+    index: usize,
+    witness_lock: &WitnessLock,
+    mut hasher: Hasher,
+) -> Result<Preimage, T::Error> {
+    let input = tx.input(index)?;
+    let output = tx.output(index)?;
+    let output_data = tx.output_data(index)?;
+    let witness = witness_for_digest(&witness_args(tx.input_witness(index)?), witness_lock)?;
+
+    // hash
+    hasher.update_with_len(format_args!("input"), &input);
+    hasher.update_with_len(format_args!("output"), &output);
+    hasher.update_with_len(format_args!("output data"), &bytes(&output_data));
+    hasher.update_with_len(format_args!("witness"), &witness);
+    digest_deps(&mut hasher, tx, witness_lock)?;
+
+    // add prefix
+    let sighash = SighashMode::SingleAnyoneCanPay as u8;
+    if witness_lock.extension.readable {
+        let summary = output_summary(tx, index, &output, &output_data)?;
+        Ok(hasher.finalize(|digest| readable_prefix_preimage(sighash, &summary, digest)))
+    } else {
+        Ok(hasher.finalize(|digest| prefix_preimage(sighash, digest)))
+    }
+}
+
+/// Unlike `SingleAnyoneCanPay`, the signature does not commit to any output. It commits to
+/// the input and to the amounts in the witness payload.
+pub fn sighash_single_udt_acp_message<T: TxAccess>(
+    tx: &T,
+    index: usize,
+    witness_lock: &WitnessLock,
+) -> Result<[u8; 32], T::Error> {
+    Ok(sighash_single_udt_acp_preimage(tx, index, witness_lock, Hasher::new(false))?.message)
+}
+
+fn sighash_single_udt_acp_preimage<T: TxAccess>(
+    tx: &T,
+    index: usize,
+    witness_lock: &WitnessLock,
+    mut hasher: Hasher,
+) -> Result<Preimage, T::Error> {
+    let requirement = UdtRequirement::from_slice(witness_lock.payload)?;
+    let input = tx.input(index)?;
+    // the payload is kept in the digested witness lock
+    let witness = witness_for_digest(&witness_args(tx.input_witness(index)?), witness_lock)?;
+
+    // hash
+    hasher.update_with_len(format_args!("input"), &input);
+    hasher.update_with_len(format_args!("witness"), &witness);
+    digest_deps(&mut hasher, tx, witness_lock)?;
+
+    // add prefix
+    let sighash = SighashMode::SingleUdtAnyoneCanPay as u8;
+    if witness_lock.extension.readable {
+        let summary = requirement_summary(&requirement);
+        Ok(hasher.finalize(|digest| readable_prefix_preimage(sighash, &summary, digest)))
+    } else {
+        Ok(hasher.finalize(|digest| prefix_preimage(sighash, digest)))
+    }
+}
+
+pub fn sighash_all_acp_message<T: TxAccess>(
+    tx: &T,
+    index: usize,
+    group_inputs: &[usize],
+    witness_lock: &WitnessLock,
+) -> Result<[u8; 32], T::Error> {
+    Ok(
+        sighash_all_acp_preimage(tx, index, group_inputs, witness_lock, Hasher::new(false))?
+            .message,
+    )
+}
+
+fn sighash_all_acp_preimage<T: TxAccess>(
+    tx: &T,
+    index: usize,
+    group_inputs: &[usize],
+    witness_lock: &WitnessLock,
+    mut hasher: Hasher,
+) -> Result<Preimage, T::Error> {
+    let input = tx.input(index)?;
+    let witness = witness_for_digest(&witness_args(tx.input_witness(index)?), witness_lock)?;
+
+    // the otx scope limits the outputs and witnesses to the segment of the signer
+    let (inputs_in_scope, outputs_in_scope) = match &witness_lock.extension.otx_scope {
+        Some(scope) => scope_ranges(tx, scope)?,
+        None => (0..tx.inputs_len()?, 0..tx.outputs_len()?),
+    };
+    if !inputs_in_scope.contains(&index) {
+        return Err(Error::InvalidOtxScope.into());
+    }
+
+    // hash
+    hasher.update_with_len(format_args!("input"), &input);
+    digest_outputs(&mut hasher, tx, outputs_in_scope)?;
+    hasher.update_with_len(format_args!("witness"), &witness);
+
+    // sighash mode ALL|ANYONECANPAY does not cover witnesses at positions beyond the number
+    // of inputs in group, witnesses that are not in this script group may not adhere to
+    // WitnessArgs
+    for i in group_inputs {
+        if i == &index || !inputs_in_scope.contains(i) {
+            continue;
+        }
+        let witness = witness_args(tx.input_witness(*i)?);
+        let output_type = witness_args_fields(&witness)?[2];
+        hasher.update_with_len(
+            format_args!("witness {}", i),
+            &table(&[&[][..], &[][..], output_type]),
+        );
+    }
+
+    digest_deps(&mut hasher, tx, witness_lock)?;
+
+    // add prefix
+    Ok(hasher.finalize(|digest| prefix_preimage(SighashMode::AllAnyoneCanPay as u8, digest)))
+}
+
+/// The signature commits to the intent and the witness extension only, so that it stays
+/// valid for every remainder of the order.
+pub fn limit_order_message<T: TxAccess>(
+    tx: &T,
+    witness_lock: &WitnessLock,
+) -> Result<[u8; 32], T::Error> {
+    Ok(limit_order_preimage(tx, witness_lock, Hasher::new(false))?.message)
+}
+
+fn limit_order_preimage<T: TxAccess>(
+    tx: &T,
+    witness_lock: &WitnessLock,
+    mut hasher: Hasher,
+) -> Result<Preimage, T::Error> {
+    if witness_lock.payload.len() < LIMIT_ORDER_INTENT_SIZE {
+        return Err(Error::Encoding.into());
+    }
+    let intent_hash = limit_order_intent_hash(&witness_lock.payload[..LIMIT_ORDER_INTENT_SIZE]);
+
+    hasher.update(format_args!("intent hash"), &intent_hash);
+    hasher.update(format_args!("extension"), witness_lock.extension_data);
+    digest_deps(&mut hasher, tx, witness_lock)?;
+    Ok(hasher.finalize(|digest| prefix_preimage(SighashMode::LimitOrder as u8, digest)))
+}
+
+/// Hash binding an order cell to the intent of its maker.
+pub fn limit_order_intent_hash(intent: &[u8]) -> [u8; 32] {
+    let mut intent_hash = [0u8; 32];
+    let mut blake2b = new_blake2b();
+    blake2b.update(intent);
+    blake2b.finalize(&mut intent_hash);
+    intent_hash
+}
+
+/// Message the owner signs in a delegation certificate, covering the fields before the
+/// owner signature.
+pub fn delegation_message(delegation: &Delegation) -> [u8; 32] {
+    let mut message = [0u8; 32];
+    let mut blake2b = new_blake2b();
+    blake2b.update(&delegation.session_pubkey_hash);
+    blake2b.update(&[delegation.allowed_modes]);
+    blake2b.update(&delegation.expiry.to_le_bytes());
+    blake2b.update(&delegation.max_capacity_out.to_le_bytes());
+    blake2b.finalize(&mut message);
+    add_prefix(DELEGATION_CERTIFICATE, &mut message);
+    message
+}
+
+// blake2b of a digest, keeping the hashed fields when recording a preimage
+struct Hasher {
+    blake2b: Blake2b,
+    parts: Option<Vec<PreimagePart>>,
+}
+
+impl Hasher {
+    fn new(record: bool) -> Self {
+        Hasher {
+            blake2b: new_blake2b(),
+            parts: if record { Some(Vec::new()) } else { None },
+        }
+    }
+
+    // names are only formatted when recording
+    fn update(&mut self, name: Arguments, data: &[u8]) {
+        self.blake2b.update(data);
+        if let Some(parts) = &mut self.parts {
+            parts.push(PreimagePart {
+                name: format!("{}", name),
+                data: data.to_vec(),
+            });
+        }
+    }
+
+    // hashes `len (u64) | data`
+    fn update_with_len(&mut self, name: Arguments, data: &[u8]) {
+        self.update(
+            format_args!("{} length", name),
+            &(data.len() as u64).to_le_bytes(),
+        );
+        self.update(name, data);
+    }
+
+    // hashes the digest behind the prefix built by `prefix`
+    fn finalize(self, prefix: impl FnOnce(&[u8]) -> Vec<u8>) -> Preimage {
+        let mut digest = [0u8; 32];
+        self.blake2b.finalize(&mut digest);
+        let prefixed = prefix(&digest);
+        let mut message = [0u8; 32];
+        let mut blake2b = new_blake2b();
+        blake2b.update(&prefixed);
+        blake2b.finalize(&mut message);
+        Preimage {
+            parts: self.parts.unwrap_or_default(),
+            digest,
+            prefixed,
+            message,
+        }
+    }
+}
+
+// hashes `count (u64) | len (u64) | outputs | count (u64) | len (u64) | outputs_data` of the
+// outputs in `range`, laid out as the molecule vectors of a standalone transaction
+fn digest_outputs<T: TxAccess>(
+    hasher: &mut Hasher,
+    tx: &T,
+    range: Range<usize>,
+) -> Result<(), T::Error> {
+    let count = range.len() as u64;
+    let mut outputs = Vec::with_capacity(range.len());
+    let mut outputs_data = Vec::with_capacity(range.len());
+    for i in range {
+        outputs.push(tx.output(i)?);
+        outputs_data.push(bytes(&tx.output_data(i)?));
+    }
+
+    hasher.update(format_args!("outputs count"), &count.to_le_bytes());
+    hasher.update_with_len(format_args!("outputs"), &table(&outputs));
+    hasher.update(format_args!("outputs data count"), &count.to_le_bytes());
+    hasher.update_with_len(format_args!("outputs data"), &table(&outputs_data));
+    Ok(())
+}
+
+fn scope_ranges<T: TxAccess>(
+    tx: &T,
+    scope: &OtxScope,
+) -> Result<(Range<usize>, Range<usize>), T::Error> {
+    let inputs = scope_range(scope.input_start, scope.input_count, tx.inputs_len()?)?;
+    let outputs = scope_range(scope.output_start, scope.output_count, tx.outputs_len()?)?;
+    Ok((inputs, outputs))
+}
+
+fn scope_range(start: usize, count: usize, total: usize) -> Result<Range<usize>, Error> {
+    match start.checked_add(count) {
+        Some(end) if end <= total => Ok(start..end),
+        _ => Err(Error::InvalidOtxScope),
+    }
+}
+
+/// Hashes all cell deps and header deps into the digest when the extension asks for them.
+fn digest_deps<T: TxAccess>(
+    hasher: &mut Hasher,
+    tx: &T,
+    witness_lock: &WitnessLock,
+) -> Result<(), T::Error> {
+    if witness_lock.extension.commit_cell_deps {
+        let cell_deps = tx.cell_deps()?;
+        hasher.update(
+            format_args!("cell deps count"),
+            &(fixvec_len(&cell_deps)? as u64).to_le_bytes(),
+        );
+        hasher.update_with_len(format_args!("cell deps"), &cell_deps);
+    }
+    if witness_lock.extension.commit_header_deps {
+        let header_deps = tx.header_deps()?;
+        hasher.update(
+            format_args!("header deps count"),
+            &(fixvec_len(&header_deps)? as u64).to_le_bytes(),
+        );
+        hasher.update_with_len(format_args!("header deps"), &header_deps);
+    }
+    Ok(())
+}
+
+/// Replaces the mode byte and the signature in the witness lock with zeros, keeping
+/// the payload that follows them so that it is covered by the signature.
+///
+/// The starts of an otx scope are zeroed as well, they are filled in by the batcher.
+fn witness_for_digest(witness: &[u8], witness_lock: &WitnessLock) -> Result<Vec<u8>, Error> {
+    let [lock, input_type, output_type] = witness_args_fields(witness)?;
+    let lock = lock.get(4..).unwrap_or_default();
+
+    let mut zero_lock = vec![0u8; 1 + SIGHASH_ALL_SIGNATURE_SIZE];
+    if lock.len() > zero_lock.len() {
+        zero_lock.extend_from_slice(&lock[zero_lock.len()..]);
+    }
+    if let Some(scope) = &witness_lock.extension.otx_scope {
+        let start = zero_lock.len() - witness_lock.extension_data.len() + scope.offset;
+        zero_lock[start..start + 4].fill(0);
+        zero_lock[start + 8..start + 12].fill(0);
+    }
+    Ok(table(&[&bytes(&zero_lock)[..], input_type, output_type]))
+}
+
+// the output at `index`: its lock hash, capacity and, when typed, type hash and UDT amount
+fn output_summary<T: TxAccess>(
+    tx: &T,
+    index: usize,
+    output: &[u8],
+    data: &[u8],
+) -> Result<String, T::Error> {
+    let mut summary = format!("output: {}\n", index);
+    push_hex_line(&mut summary, "lock", &tx.output_lock_hash(index)?);
+    summary.push_str(&format!("capacity: {}\n", output_capacity(output)?));
+    if let Some(type_hash) = tx.output_type_hash(index)? {
+        push_hex_line(&mut summary, "type", &type_hash);
+        if data.len() >= UDT_AMOUNT_SIZE {
+            let mut udt_amount = [0u8; UDT_AMOUNT_SIZE];
+            udt_amount.copy_from_slice(&data[..UDT_AMOUNT_SIZE]);
+            summary.push_str(&format!("udt: {}\n", u128::from_le_bytes(udt_amount)));
+        }
+    }
+    Ok(summary)
+}
+
+// the amounts the outputs under `lock_hash` must carry at least
+fn requirement_summary(requirement: &UdtRequirement) -> String {
+    let mut summary = String::new();
+    push_hex_line(&mut summary, "lock", &requirement.lock_hash);
+    summary.push_str(&format!("capacity: {}\n", requirement.capacity));
+    if let Some(type_hash) = &requirement.type_hash {
+        push_hex_line(&mut summary, "udt type", type_hash);
+        summary.push_str(&format!("udt: {}\n", requirement.udt_amount));
+    }
+    summary
+}
+
+// the capacity, first field of a `CellOutput` table
+fn output_capacity(output: &[u8]) -> Result<u64, Error> {
+    let offset = output
+        .get(4..8)
+        .map(|offset| u32::from_le_bytes([offset[0], offset[1], offset[2], offset[3]]) as usize)
+        .ok_or(Error::Encoding)?;
+    let capacity = output.get(offset..offset + 8).ok_or(Error::Encoding)?;
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(capacity);
+    Ok(u64::from_le_bytes(buf))
+}
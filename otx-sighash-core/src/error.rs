@@ -0,0 +1,63 @@
+use core::fmt;
+
+/// Errors of the lock, with the exit codes of the contract. Building a signing message
+/// only raises the parsing errors, converted into the error type of the `TxAccess` in use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(i8)]
+pub enum Error {
+    IndexOutOfBound = 1,
+    ItemMissing,
+    LengthNotEnough,
+    Encoding,
+    // Add customized errors here...
+    UnsupportedSighashMode,
+    Secp256k1,
+    WrongPubkey,
+    LoadPrefilledData,
+    RecoverPubkey,
+    InvalidArgs,
+    NoPair,
+    DuplicatedInputs,
+    DuplicatedOutputs,
+    OutputAmountNotEnough,
+    OutputDataChanged,
+    Overflow,
+    InvalidOrderCell,
+    InvalidRemainder,
+    PriceNotMet,
+    OutputAlreadyClaimed,
+    SignatureExpired,
+    HeaderDepMissing,
+    InvalidSince,
+    InvalidOtx,
+    SealMissing,
+    OtxNotCovered,
+    DuplicatedMessage,
+    InvalidOtxScope,
+    ModeNotDelegated,
+    DelegatedCapacityExceeded,
+    IdentityMissing,
+    InvalidIdentity,
+    RecoveryNotEnabled,
+    RecoveryDelayNotMet,
+    RevocationMissing,
+    SignatureRevoked,
+    FeeLimitExceeded,
+    HighS,
+    InvalidRecoveryId,
+    ZeroSignatureScalar,
+    AnyoneCanPayDisabled,
+}
+
+impl Error {
+    /// The exit code of the contract.
+    pub fn code(&self) -> i8 {
+        *self as i8
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} (error code {})", self, self.code())
+    }
+}
@@ -0,0 +1,252 @@
+use crate::args::{LockArgs, REVOCATION_PREFIX_SIZE};
+use crate::error::Error;
+use crate::molecule::{read_bytes, witness_args, witness_args_fields};
+use crate::prefix::new_blake2b;
+use crate::script::{ScriptAccess, Source};
+use crate::signature::check_signature_encoding;
+use crate::since::{enforces_delay, is_expired};
+use crate::types::{BLAKE160_SIZE, SIGHASH_ALL_SIGNATURE_SIZE, UDT_AMOUNT_SIZE};
+
+use alloc::vec::Vec;
+
+/// Loads the lock field of the witness at `index`, returning `None` when the
+/// witness is missing, empty, or carries no (or an empty) lock.
+pub(crate) fn load_witness_lock<S: ScriptAccess>(
+    script: &S,
+    index: usize,
+    source: Source,
+) -> Result<Option<Vec<u8>>, Error> {
+    let witness = witness_args(match script.witness(index, source) {
+        Ok(witness) => Some(witness),
+        Err(Error::IndexOutOfBound) => None,
+        Err(err) => return Err(err),
+    });
+    let lock = witness_args_fields(&witness)?[0];
+    if lock.is_empty() {
+        return Ok(None);
+    }
+    let lock = read_bytes(lock)?;
+    Ok(Some(lock.to_vec()).filter(|lock| !lock.is_empty()))
+}
+
+/// Rejects the signature once any header dep goes beyond `expiry`, an absolute `since`
+/// value in block number or epoch number.
+pub(crate) fn check_expiry<S: ScriptAccess>(script: &S, expiry: u64) -> Result<(), Error> {
+    let headers = script.headers()?;
+    for header in headers.iter() {
+        if is_expired(expiry, header.number, header.epoch)? {
+            return Err(Error::SignatureExpired);
+        }
+    }
+    if headers.is_empty() {
+        return Err(Error::HeaderDepMissing);
+    }
+    Ok(())
+}
+
+/// Requires the `since` field of the input at `index` to enforce at least `delay`, a relative
+/// `since` value of the same metric.
+pub(crate) fn check_recovery_delay<S: ScriptAccess>(
+    script: &S,
+    index: usize,
+    delay: u64,
+) -> Result<(), Error> {
+    let since = script.input_since(index)?;
+    if !enforces_delay(since, delay)? {
+        return Err(Error::RecoveryDelayNotMet);
+    }
+    Ok(())
+}
+
+/// Reads the UDT amount stored in the first 16 bytes of the cell data.
+pub(crate) fn load_udt_amount<S: ScriptAccess>(
+    script: &S,
+    index: usize,
+    source: Source,
+) -> Result<u128, Error> {
+    let data = script.cell_data(index, source)?;
+    if data.len() < UDT_AMOUNT_SIZE {
+        return Err(Error::Encoding);
+    }
+    let mut buf = [0u8; UDT_AMOUNT_SIZE];
+    buf.copy_from_slice(&data[..UDT_AMOUNT_SIZE]);
+    Ok(u128::from_le_bytes(buf))
+}
+
+pub(crate) fn verify_pubkey_hash<S: ScriptAccess>(
+    script: &S,
+    message: &[u8; 32],
+    signature: &[u8; SIGHASH_ALL_SIGNATURE_SIZE],
+    expected_pubkey_hash: &[u8],
+) -> Result<(), Error> {
+    let pubkey_hash = recover_pubkey_hash(script, message, signature)?;
+    if !is_authorized(&pubkey_hash, expected_pubkey_hash) {
+        return Err(Error::WrongPubkey);
+    }
+    Ok(())
+}
+
+fn recover_pubkey_hash<S: ScriptAccess>(
+    script: &S,
+    message: &[u8; 32],
+    signature: &[u8; SIGHASH_ALL_SIGNATURE_SIZE],
+) -> Result<[u8; BLAKE160_SIZE], Error> {
+    // signatures the secp256k1 library accepts but that are not canonical
+    check_signature_encoding(signature)?;
+    let pubkey = script.recover_pubkey(message, signature)?;
+    let mut buf = [0u8; 32];
+    let mut hasher = new_blake2b();
+    hasher.update(&pubkey);
+    hasher.finalize(&mut buf);
+    let mut pubkey_hash = [0u8; BLAKE160_SIZE];
+    pubkey_hash.copy_from_slice(&buf[..BLAKE160_SIZE]);
+    Ok(pubkey_hash)
+}
+
+// `expected_pubkey_hash` may list several authorized pubkey hashes
+fn is_authorized(pubkey_hash: &[u8], expected_pubkey_hash: &[u8]) -> bool {
+    expected_pubkey_hash
+        .chunks(BLAKE160_SIZE)
+        .any(|expected| expected == pubkey_hash)
+}
+
+/// Loads the authorized pubkey hashes, `n * pubkey_hash (20 bytes)`, from the identity cell
+/// with the given type hash in the cell deps.
+fn load_identity<S: ScriptAccess>(
+    script: &S,
+    identity_type_hash: &[u8; 32],
+) -> Result<Vec<u8>, Error> {
+    let data = load_cell_dep_data(script, identity_type_hash)?.ok_or(Error::IdentityMissing)?;
+    if data.is_empty() || data.len() % BLAKE160_SIZE != 0 {
+        return Err(Error::InvalidIdentity);
+    }
+    Ok(data)
+}
+
+/// Who may sign for a lock group: the owner, whose keys and revoked signatures are loaded by
+/// the first signature checked against them, or the recovery key once the signed inputs have
+/// aged. Payments to anyone-can-pay inputs and the recovery key do without the identity and
+/// revocation cells.
+pub(crate) struct Signers<'a> {
+    args: &'a LockArgs,
+    // the authorized pubkey hashes and the revoked signatures
+    keys: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+impl<'a> Signers<'a> {
+    pub fn new(args: &'a LockArgs) -> Self {
+        Signers { args, keys: None }
+    }
+
+    /// The pubkey hashes of the owner, those of the identity cell when the args name one,
+    /// once `signature` is known not to be revoked.
+    pub fn pubkey_hashes<S: ScriptAccess>(
+        &mut self,
+        script: &S,
+        signature: &[u8],
+    ) -> Result<&[u8], Error> {
+        let keys = match self.keys.take() {
+            Some(keys) => keys,
+            None => {
+                let pubkey_hashes = match &self.args.identity_type_hash {
+                    Some(identity_type_hash) => load_identity(script, identity_type_hash)?,
+                    None => self.args.pubkey_hash.to_vec(),
+                };
+                let revocation = match &self.args.revocation_type_hash {
+                    Some(revocation_type_hash) => load_revocation(script, revocation_type_hash)?,
+                    None => Vec::new(),
+                };
+                (pubkey_hashes, revocation)
+            }
+        };
+        let (pubkey_hashes, revocation) = self.keys.insert(keys);
+        check_revocation(signature, revocation)?;
+        Ok(pubkey_hashes)
+    }
+
+    /// Accepts `signature` of `message` for the inputs at `indices`, see `check`.
+    pub fn verify<S: ScriptAccess>(
+        &mut self,
+        script: &S,
+        message: &[u8; 32],
+        signature: &[u8; SIGHASH_ALL_SIGNATURE_SIZE],
+        indices: &[usize],
+    ) -> Result<(), Error> {
+        let pubkey_hash = recover_pubkey_hash(script, message, signature)?;
+        self.check(script, &pubkey_hash, signature, indices)
+    }
+
+    /// Accepts `pubkey_hash`, recovered from `signature` for the inputs at `indices`, if it is
+    /// the recovery key and every input has aged past its delay, or one of the owner keys.
+    pub fn check<S: ScriptAccess>(
+        &mut self,
+        script: &S,
+        pubkey_hash: &[u8],
+        signature: &[u8],
+        indices: &[usize],
+    ) -> Result<(), Error> {
+        if let Some(recovery) = &self.args.recovery {
+            if pubkey_hash == recovery.pubkey_hash {
+                for i in indices {
+                    check_recovery_delay(script, *i, recovery.delay)?;
+                }
+                return Ok(());
+            }
+        }
+        if !is_authorized(pubkey_hash, self.pubkey_hashes(script, signature)?) {
+            return Err(Error::WrongPubkey);
+        }
+        Ok(())
+    }
+}
+
+/// Loads the revoked signatures, `n * r_prefix (8 bytes)`, from the revocation cell with the
+/// given type hash in the cell deps.
+fn load_revocation<S: ScriptAccess>(
+    script: &S,
+    revocation_type_hash: &[u8; 32],
+) -> Result<Vec<u8>, Error> {
+    let data = load_cell_dep_data(script, revocation_type_hash)?.ok_or(Error::RevocationMissing)?;
+    if data.len() % REVOCATION_PREFIX_SIZE != 0 {
+        return Err(Error::Encoding);
+    }
+    Ok(data)
+}
+
+/// Rejects a signature whose `r` starts with one of the revoked prefixes. Unlike the `s`
+/// value, `r` cannot be changed without the private key.
+fn check_revocation(signature: &[u8], revocation: &[u8]) -> Result<(), Error> {
+    if revocation
+        .chunks(REVOCATION_PREFIX_SIZE)
+        .any(|prefix| prefix == &signature[..REVOCATION_PREFIX_SIZE])
+    {
+        return Err(Error::SignatureRevoked);
+    }
+    Ok(())
+}
+
+fn load_cell_dep_data<S: ScriptAccess>(
+    script: &S,
+    type_hash: &[u8; 32],
+) -> Result<Option<Vec<u8>>, Error> {
+    for index in 0..script.cells_len(Source::CellDep)? {
+        if script.cell_type_hash(index, Source::CellDep)?.as_ref() == Some(type_hash) {
+            return Ok(Some(script.cell_data(index, Source::CellDep)?));
+        }
+    }
+    Ok(None)
+}
+
+/// Sums the capacity of the cells at `indices` in `source`.
+pub(crate) fn sum_capacity<S: ScriptAccess>(
+    script: &S,
+    indices: impl Iterator<Item = usize>,
+    source: Source,
+) -> Result<u64, Error> {
+    let mut total: u64 = 0;
+    for i in indices {
+        let capacity = script.cell_capacity(i, source)?;
+        total = total.checked_add(capacity).ok_or(Error::Overflow)?;
+    }
+    Ok(total)
+}
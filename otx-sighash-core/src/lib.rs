@@ -0,0 +1,47 @@
+//! Signing messages and checks of the otx-sighash-lock, shared by the contract, the native
+//! verifier and off-chain signers.
+//!
+//! Every mode builds its preimage from a `TxAccess`, which the contract implements with
+//! syscalls and signers with the transaction they are about to sign, so that both hash
+//! exactly the same bytes. The checks that do not depend on the transaction, such as
+//! parsing the lock args, the CoBuild witness layout or the canonical form of signatures,
+//! are shared as well.
+//!
+//! `validate_lock_group` runs the whole lock on a `ScriptAccess`, implemented with syscalls
+//! by the contract and with a resolved transaction by the native verifier, so that both
+//! take the same decisions and fail with the same `Error`.
+
+#![no_std]
+
+extern crate alloc;
+
+mod args;
+pub mod cobuild;
+mod digest;
+mod error;
+mod helper;
+mod lock;
+mod molecule;
+mod prefix;
+mod script;
+mod signature;
+pub mod since;
+mod types;
+mod validate;
+
+pub use args::*;
+
+pub use digest::{
+    delegation_message, limit_order_intent_hash, limit_order_message, sighash_all_acp_message,
+    sighash_single_acp_message, sighash_single_udt_acp_message, signing_message, signing_preimage,
+    Preimage, PreimagePart, TxAccess,
+};
+pub use error::Error;
+pub use lock::{validate_lock_group, validate_open_lock_group};
+pub use prefix::{
+    add_prefix, add_readable_prefix, new_blake2b, prefix_preimage, push_hex_line,
+    readable_prefix_preimage,
+};
+pub use script::{Header, ScriptAccess, Source};
+pub use signature::*;
+pub use types::*;
@@ -0,0 +1,208 @@
+use crate::args::LockArgs;
+use crate::error::Error;
+use crate::helper::{check_expiry, check_recovery_delay, load_witness_lock, Signers};
+use crate::script::{ScriptAccess, Source};
+use crate::types::{SighashMode, WitnessLock, SIGHASH_ALL_SIGNATURE_SIZE};
+use crate::validate::{
+    check_limit_order_fill, check_udt_requirements, collect_claims, validate_anyone_can_pay,
+    validate_cobuild_otx, validate_cobuild_sighash_all, validate_delegation, validate_fee_limit,
+    validate_limit_order, validate_sighash_all_anyonecanpay, validate_sighash_single_anyonecanpay,
+    validate_sighash_single_udt_anyonecanpay,
+};
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// Verifies the lock group of the running script, the whole of the contract. The error is
+/// the exit code of the script.
+pub fn validate_lock_group<S: ScriptAccess>(script: &S) -> Result<(), Error> {
+    validate(script, true)
+}
+
+/// Verifies the lock group of an otx still waiting for its counterparty, as a pool does
+/// before holding it: the payments SINGLE|ANYONECANPAY|UDT signatures require and the fills
+/// of limit orders are left to the transaction completing the otx.
+pub fn validate_open_lock_group<S: ScriptAccess>(script: &S) -> Result<(), Error> {
+    validate(script, false)
+}
+
+fn validate<S: ScriptAccess>(script: &S, complete: bool) -> Result<(), Error> {
+    let args = script.script_args()?;
+
+    // return an error if args is invalid
+    if args.is_empty() {
+        return Err(Error::ItemMissing);
+    }
+    let lock_args = LockArgs::from_slice(&args)?;
+
+    // the authorized pubkey hashes, kept in an identity cell for keys to be rotated, and the
+    // signatures revoked by the owner, whatever the signing mode, are only loaded to check a
+    // signature of the owner
+    let mut signers = Signers::new(&lock_args);
+
+    let current_script_hash = script.script_hash()?;
+    let mut group_inputs_absolute_indices: Vec<usize> = Vec::new();
+    for i in 0..script.inputs_len()? {
+        if script.cell_lock_hash(i, Source::Input)? == current_script_hash {
+            group_inputs_absolute_indices.push(i);
+        }
+    }
+
+    // Inputs in CoBuild open transactions are sealed by the otx they belong to,
+    // their witnesses are not aligned with the inputs.
+    if validate_cobuild_otx(script, &group_inputs_absolute_indices, &mut signers)? {
+        return Ok(());
+    }
+
+    // Fully sealed transactions may use the CoBuild layout, showing structured actions to signers.
+    if validate_cobuild_sighash_all(script, &group_inputs_absolute_indices, &mut signers)? {
+        return Ok(());
+    }
+
+    // This lock script is fully compatible with the secp256k1_blake2b_sighash_all signature algorithm.
+    // In this compatible mode, other witnesses in the same lock script group do not need to be verified.
+    let witness_lock = load_witness_lock(script, 0, Source::GroupInput)?;
+    if let Some(witness_lock) = witness_lock {
+        if witness_lock.len() == SIGHASH_ALL_SIGNATURE_SIZE {
+            let pubkey_hash = script.sighash_all_pubkey_hash()?;
+            return signers.check(
+                script,
+                &pubkey_hash,
+                &witness_lock,
+                &group_inputs_absolute_indices,
+            );
+        }
+    }
+
+    // This is a lock script that is compatible with various sighash modes,
+    // so we need to verify each witness in the same lock script group
+    // inputs without a witness lock are unlocked by receiving payments
+    let mut acp_inputs_absolute_indices: Vec<usize> = Vec::new();
+    // outputs taken by the inputs of this lock code, in any lock group
+    let claims = collect_claims(script)?;
+    // segments of the scoped ALL|ANYONECANPAY signatures
+    let mut otx_segments: Vec<(Range<usize>, Range<usize>)> = Vec::new();
+    // whether SINGLE|ANYONECANPAY|UDT signatures require payments
+    let mut requires_udt_payment = false;
+    for i in group_inputs_absolute_indices.iter() {
+        let witness_lock = match load_witness_lock(script, *i, Source::Input)? {
+            Some(witness_lock) => witness_lock,
+            None => {
+                acp_inputs_absolute_indices.push(*i);
+                continue;
+            }
+        };
+        let witness_lock = WitnessLock::from_slice(&witness_lock)?;
+        if let Some(expiry) = witness_lock.extension.expiry {
+            check_expiry(script, expiry)?;
+        }
+        // only ALL-style modes are scoped to an otx segment
+        if witness_lock.extension.otx_scope.is_some()
+            && witness_lock.mode != SighashMode::AllAnyoneCanPay
+        {
+            return Err(Error::InvalidOtxScope);
+        }
+        // scoped signers share their segment, being the same otx, or do not overlap at all,
+        // otherwise one output would pay for two otxs
+        if let Some(scope) = &witness_lock.extension.otx_scope {
+            let segment = (
+                scope.input_start..scope.input_start.saturating_add(scope.input_count),
+                scope.output_start..scope.output_start.saturating_add(scope.output_count),
+            );
+            if otx_segments.iter().any(|other| {
+                *other != segment
+                    && (overlaps(&other.0, &segment.0) || overlaps(&other.1, &segment.1))
+            }) {
+                return Err(Error::InvalidOtxScope);
+            }
+            otx_segments.push(segment);
+        }
+        if let Some(max_fee) = witness_lock.extension.max_fee {
+            validate_fee_limit(script, *i, &witness_lock, max_fee)?;
+        }
+        // the recovery key signs in place of the owner once the input has aged
+        let owner_pubkey_hash = if witness_lock.extension.recovery {
+            let recovery = lock_args
+                .recovery
+                .as_ref()
+                .ok_or(Error::RecoveryNotEnabled)?;
+            check_recovery_delay(script, *i, recovery.delay)?;
+            &recovery.pubkey_hash[..]
+        } else {
+            signers.pubkey_hashes(script, &witness_lock.signature)?
+        };
+        // readable preimages summarize the committed output, only SINGLE modes have one
+        if witness_lock.extension.readable
+            && witness_lock.mode != SighashMode::SingleAnyoneCanPay
+            && witness_lock.mode != SighashMode::SingleUdtAnyoneCanPay
+        {
+            return Err(Error::UnsupportedSighashMode);
+        }
+        // a delegated session key signs in place of the owner
+        let expected_pubkey_hash = match &witness_lock.extension.delegation {
+            Some(delegation) => {
+                validate_delegation(script, delegation, witness_lock.mode, owner_pubkey_hash)?;
+                &delegation.session_pubkey_hash[..]
+            }
+            None => owner_pubkey_hash,
+        };
+        match witness_lock.mode {
+            SighashMode::All => return Err(Error::UnsupportedSighashMode),
+            SighashMode::None => return Err(Error::UnsupportedSighashMode),
+            SighashMode::Single => return Err(Error::UnsupportedSighashMode),
+            SighashMode::AllAnyoneCanPay => {
+                validate_sighash_all_anyonecanpay(
+                    script,
+                    *i,
+                    &group_inputs_absolute_indices,
+                    &witness_lock,
+                    expected_pubkey_hash,
+                )?;
+            }
+            SighashMode::NoneAnyoneCanPay => return Err(Error::UnsupportedSighashMode),
+            SighashMode::SingleAnyoneCanPay => {
+                validate_sighash_single_anyonecanpay(
+                    script,
+                    *i,
+                    &witness_lock,
+                    expected_pubkey_hash,
+                )?;
+            }
+            SighashMode::SingleUdtAnyoneCanPay => {
+                validate_sighash_single_udt_anyonecanpay(
+                    script,
+                    *i,
+                    &witness_lock,
+                    expected_pubkey_hash,
+                )?;
+                requires_udt_payment = true;
+            }
+            SighashMode::LimitOrder => {
+                let order_cell =
+                    validate_limit_order(script, *i, &witness_lock, expected_pubkey_hash)?;
+                if complete {
+                    check_limit_order_fill(script, *i, &witness_lock, &order_cell)?;
+                }
+            }
+        };
+    }
+
+    if requires_udt_payment && complete {
+        check_udt_requirements(script, &claims)?;
+    }
+
+    if !acp_inputs_absolute_indices.is_empty() {
+        validate_anyone_can_pay(
+            script,
+            &acp_inputs_absolute_indices,
+            &lock_args,
+            &claims.outputs,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn overlaps(a: &Range<usize>, b: &Range<usize>) -> bool {
+    a.start < b.end && b.start < a.end
+}
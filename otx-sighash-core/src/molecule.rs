@@ -0,0 +1,137 @@
+//! The few molecule layouts the digests are made of, without depending on a particular
+//! version of the generated CKB types.
+
+use crate::error::Error;
+
+use alloc::vec::Vec;
+
+const NUMBER_SIZE: usize = 4;
+
+fn read_number(data: &[u8]) -> usize {
+    let mut buf = [0u8; NUMBER_SIZE];
+    buf.copy_from_slice(&data[..NUMBER_SIZE]);
+    u32::from_le_bytes(buf) as usize
+}
+
+/// Encodes a table, or a dynvec which shares the layout:
+/// `total_size (u32) | offsets (u32 each) | fields`.
+pub(crate) fn table<T: AsRef<[u8]>>(fields: &[T]) -> Vec<u8> {
+    let header_size = NUMBER_SIZE * (fields.len() + 1);
+    let total_size = header_size + fields.iter().map(|f| f.as_ref().len()).sum::<usize>();
+
+    let mut data = Vec::with_capacity(total_size);
+    data.extend_from_slice(&(total_size as u32).to_le_bytes());
+    let mut offset = header_size;
+    for field in fields {
+        data.extend_from_slice(&(offset as u32).to_le_bytes());
+        offset += field.as_ref().len();
+    }
+    for field in fields {
+        data.extend_from_slice(field.as_ref());
+    }
+    data
+}
+
+/// Encodes `Bytes`: `len (u32) | data`.
+pub(crate) fn bytes(data: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(NUMBER_SIZE + data.len());
+    encoded.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    encoded.extend_from_slice(data);
+    encoded
+}
+
+/// Number of items in a fixvec, such as `CellDepVec` or `Byte32Vec`.
+pub(crate) fn fixvec_len(data: &[u8]) -> Result<usize, Error> {
+    if data.len() < NUMBER_SIZE {
+        return Err(Error::Encoding);
+    }
+    Ok(read_number(data))
+}
+
+/// The `WitnessArgs` of an input: a missing or empty witness, as anyone-can-pay inputs
+/// leave it, reads as one without any field.
+pub(crate) fn witness_args(witness: Option<Vec<u8>>) -> Vec<u8> {
+    match witness {
+        Some(witness) if !witness.is_empty() => witness,
+        _ => table(&[[0u8; 0]; 3]),
+    }
+}
+
+/// Splits a `WitnessArgs` into its `lock`, `input_type` and `output_type` fields, each an
+/// encoded `BytesOpt`: empty for none, `Bytes` otherwise.
+pub(crate) fn witness_args_fields(data: &[u8]) -> Result<[&[u8]; 3], Error> {
+    if data.len() < NUMBER_SIZE * 4 || read_number(data) != data.len() {
+        return Err(Error::Encoding);
+    }
+    if read_number(&data[NUMBER_SIZE..]) != NUMBER_SIZE * 4 {
+        return Err(Error::Encoding);
+    }
+    let offset = |i: usize| match i {
+        3 => data.len(),
+        _ => read_number(&data[NUMBER_SIZE * (i + 1)..]),
+    };
+
+    let mut fields: [&[u8]; 3] = [&[]; 3];
+    for (i, field) in fields.iter_mut().enumerate() {
+        let (start, end) = (offset(i), offset(i + 1));
+        if start > end || end > data.len() {
+            return Err(Error::Encoding);
+        }
+        *field = &data[start..end];
+        if !field.is_empty()
+            && (field.len() < NUMBER_SIZE || read_number(field) != field.len() - NUMBER_SIZE)
+        {
+            return Err(Error::Encoding);
+        }
+    }
+    Ok(fields)
+}
+
+/// Splits a table, such as a `CellOutput` or a `Script`, into its fields.
+pub(crate) fn table_fields(data: &[u8]) -> Result<Vec<&[u8]>, Error> {
+    if data.len() < NUMBER_SIZE || read_number(data) != data.len() {
+        return Err(Error::Encoding);
+    }
+    if data.len() == NUMBER_SIZE {
+        return Ok(Vec::new());
+    }
+    if data.len() < NUMBER_SIZE * 2 {
+        return Err(Error::Encoding);
+    }
+    let header_size = read_number(&data[NUMBER_SIZE..]);
+    if header_size % NUMBER_SIZE != 0 || header_size < NUMBER_SIZE * 2 || header_size > data.len() {
+        return Err(Error::Encoding);
+    }
+    let count = header_size / NUMBER_SIZE - 1;
+    let offset = |i: usize| match i {
+        i if i == count => data.len(),
+        _ => read_number(&data[NUMBER_SIZE * (i + 1)..]),
+    };
+
+    let mut fields = Vec::with_capacity(count);
+    for i in 0..count {
+        let (start, end) = (offset(i), offset(i + 1));
+        if start < header_size || start > end || end > data.len() {
+            return Err(Error::Encoding);
+        }
+        fields.push(&data[start..end]);
+    }
+    Ok(fields)
+}
+
+/// Reads `Bytes`: `len (u32) | data`.
+pub(crate) fn read_bytes(data: &[u8]) -> Result<&[u8], Error> {
+    if data.len() < NUMBER_SIZE || read_number(data) != data.len() - NUMBER_SIZE {
+        return Err(Error::Encoding);
+    }
+    Ok(&data[NUMBER_SIZE..])
+}
+
+/// The item at `index` of a fixvec whose items are `item_size` bytes long.
+pub(crate) fn fixvec_item(data: &[u8], item_size: usize, index: usize) -> Result<&[u8], Error> {
+    if index >= fixvec_len(data)? {
+        return Err(Error::IndexOutOfBound);
+    }
+    let start = NUMBER_SIZE + item_size * index;
+    data.get(start..start + item_size).ok_or(Error::Encoding)
+}
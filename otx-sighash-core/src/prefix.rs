@@ -0,0 +1,55 @@
+use crate::types::MAGIC_CODE;
+
+use alloc::{format, string::String, vec::Vec};
+
+use blake2b_ref::{Blake2b, Blake2bBuilder};
+
+pub fn new_blake2b() -> Blake2b {
+    Blake2bBuilder::new(32)
+        .personal(b"ckb-default-hash")
+        .build()
+}
+
+/// Hashes `"COTX <mode>:\n<len><message>"` back into `message`, so that a signature of
+/// one mode cannot be replayed as another.
+pub fn add_prefix(sighash: u8, message: &mut [u8]) {
+    let preimage = prefix_preimage(sighash, message);
+    let mut blake2b = new_blake2b();
+    blake2b.update(&preimage);
+    blake2b.finalize(message);
+}
+
+/// Like `add_prefix`, with a preimage that a generic secp256k1 wallet can display:
+/// `"COTX <mode>:\n<len><summary>digest: 0x<message in hex>\n"`.
+///
+/// `summary` is rebuilt from the transaction, one `name: value` line per field.
+pub fn add_readable_prefix(sighash: u8, summary: &str, message: &mut [u8]) {
+    let preimage = readable_prefix_preimage(sighash, summary, message);
+    let mut blake2b = new_blake2b();
+    blake2b.update(&preimage);
+    blake2b.finalize(message);
+}
+
+/// The bytes `add_prefix` hashes.
+pub fn prefix_preimage(sighash: u8, message: &[u8]) -> Vec<u8> {
+    let mut preimage = format!("{} {}:\n{}", MAGIC_CODE, sighash, message.len()).into_bytes();
+    preimage.extend_from_slice(message);
+    preimage
+}
+
+/// The text `add_readable_prefix` hashes.
+pub fn readable_prefix_preimage(sighash: u8, summary: &str, message: &[u8]) -> Vec<u8> {
+    let mut text = String::from(summary);
+    push_hex_line(&mut text, "digest", message);
+    format!("{} {}:\n{}{}", MAGIC_CODE, sighash, text.len(), text).into_bytes()
+}
+
+/// Writes `name: 0x<data in hex>` as a line of a readable summary.
+pub fn push_hex_line(summary: &mut String, name: &str, data: &[u8]) {
+    summary.push_str(name);
+    summary.push_str(": 0x");
+    for byte in data {
+        summary.push_str(&format!("{:02x}", byte));
+    }
+    summary.push('\n');
+}
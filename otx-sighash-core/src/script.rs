@@ -0,0 +1,57 @@
+use crate::digest::TxAccess;
+use crate::error::Error;
+use crate::types::{BLAKE160_SIZE, SIGHASH_ALL_SIGNATURE_SIZE};
+
+use alloc::vec::Vec;
+
+/// Where cells and witnesses are loaded from, as with the syscalls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Input,
+    Output,
+    CellDep,
+    GroupInput,
+}
+
+/// The fields of a header dep the lock reads.
+pub struct Header {
+    pub number: u64,
+    pub epoch: u64,
+}
+
+/// Everything the lock script reads while verifying its group, beyond the transaction
+/// fields of `TxAccess`. The contract implements it with syscalls, the native verifier with
+/// a resolved transaction.
+///
+/// Loads past the last item fail with `IndexOutOfBound`, as the syscalls do.
+pub trait ScriptAccess: TxAccess<Error = Error> {
+    /// The args of the running lock script.
+    fn script_args(&self) -> Result<Vec<u8>, Error>;
+    fn script_hash(&self) -> Result<[u8; 32], Error>;
+    fn tx_hash(&self) -> Result<[u8; 32], Error>;
+
+    fn cells_len(&self, source: Source) -> Result<usize, Error>;
+    /// The `CellOutput` at `index`.
+    fn cell(&self, index: usize, source: Source) -> Result<Vec<u8>, Error>;
+    fn cell_capacity(&self, index: usize, source: Source) -> Result<u64, Error>;
+    fn cell_data(&self, index: usize, source: Source) -> Result<Vec<u8>, Error>;
+    fn cell_lock_hash(&self, index: usize, source: Source) -> Result<[u8; 32], Error>;
+    fn cell_type_hash(&self, index: usize, source: Source) -> Result<Option<[u8; 32]>, Error>;
+    fn input_since(&self, index: usize) -> Result<u64, Error>;
+
+    fn witnesses_len(&self) -> Result<usize, Error>;
+    /// The raw witness at `index`, from `Input` or `GroupInput`.
+    fn witness(&self, index: usize, source: Source) -> Result<Vec<u8>, Error>;
+    /// The headers of the header deps.
+    fn headers(&self) -> Result<Vec<Header>, Error>;
+
+    /// The serialized public key that made `signature` over `message`.
+    fn recover_pubkey(
+        &self,
+        message: &[u8; 32],
+        signature: &[u8; SIGHASH_ALL_SIGNATURE_SIZE],
+    ) -> Result<Vec<u8>, Error>;
+    /// The pubkey hash recovered by `validate_blake2b_sighash_all` of the secp256k1 library
+    /// from the first witness of the group, failing with `Secp256k1`.
+    fn sighash_all_pubkey_hash(&self) -> Result<[u8; BLAKE160_SIZE], Error>;
+}
@@ -0,0 +1,31 @@
+use crate::error::Error;
+use crate::types::SIGHASH_ALL_SIGNATURE_SIZE;
+
+// compact recoverable signature: `r (32) | s (32) | recovery id (1)`
+pub const SIGNATURE_SCALAR_SIZE: usize = 32;
+// half of the secp256k1 group order, the greatest `s` of a canonical signature
+pub const SECP256K1_HALF_ORDER: [u8; SIGNATURE_SCALAR_SIZE] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// Rejects signatures the secp256k1 library would accept but that are not in
+/// canonical form, so that nobody but the signer can produce another valid
+/// witness for the same message.
+pub fn check_signature_encoding(signature: &[u8; SIGHASH_ALL_SIGNATURE_SIZE]) -> Result<(), Error> {
+    let (r, rest) = signature.split_at(SIGNATURE_SCALAR_SIZE);
+    let (s, recovery_id) = rest.split_at(SIGNATURE_SCALAR_SIZE);
+    if r.iter().all(|&byte| byte == 0) || s.iter().all(|&byte| byte == 0) {
+        return Err(Error::ZeroSignatureScalar);
+    }
+    // both are big-endian, so they compare lexicographically
+    if s > &SECP256K1_HALF_ORDER[..] {
+        return Err(Error::HighS);
+    }
+    // ids 2 and 3 only arise when the x coordinate of `R` exceeds the group
+    // order, which no honest signer hits in practice
+    if recovery_id[0] > 1 {
+        return Err(Error::InvalidRecoveryId);
+    }
+    Ok(())
+}
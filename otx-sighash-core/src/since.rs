@@ -0,0 +1,52 @@
+//! The `since` values of expiries and recovery delays.
+
+use crate::error::Error;
+
+// `since` flags in the highest byte and masks
+pub const SINCE_ABSOLUTE_BLOCK_NUMBER: u64 = 0x00;
+pub const SINCE_ABSOLUTE_EPOCH: u64 = 0x20;
+pub const SINCE_VALUE_MASK: u64 = 0x00ff_ffff_ffff_ffff;
+pub const EPOCH_NUMBER_MASK: u64 = 0x00ff_ffff;
+pub const SINCE_RELATIVE_FLAG: u64 = 0x80;
+pub const SINCE_METRIC_MASK: u64 = 0x60;
+pub const SINCE_METRIC_EPOCH: u64 = 0x20;
+
+/// Whether a header with `number` and `epoch` goes beyond `expiry`, an absolute `since`
+/// value in block number or epoch number.
+pub fn is_expired(expiry: u64, number: u64, epoch: u64) -> Result<bool, Error> {
+    match expiry >> 56 {
+        SINCE_ABSOLUTE_BLOCK_NUMBER => Ok(number > expiry & SINCE_VALUE_MASK),
+        SINCE_ABSOLUTE_EPOCH => Ok(epoch & EPOCH_NUMBER_MASK > expiry & EPOCH_NUMBER_MASK),
+        _ => Err(Error::InvalidSince),
+    }
+}
+
+/// Whether the `since` of an input enforces at least `delay`, a relative `since` value of
+/// the same metric.
+pub fn enforces_delay(since: u64, delay: u64) -> Result<bool, Error> {
+    let flags = since >> 56;
+    if flags & SINCE_RELATIVE_FLAG == 0
+        || flags & SINCE_METRIC_MASK != (delay >> 56) & SINCE_METRIC_MASK
+    {
+        return Ok(false);
+    }
+    if flags & SINCE_METRIC_MASK == SINCE_METRIC_EPOCH {
+        let (since_numerator, since_length) = epoch_fraction(since)?;
+        let (delay_numerator, delay_length) = epoch_fraction(delay)?;
+        Ok(since_numerator * delay_length >= delay_numerator * since_length)
+    } else {
+        Ok(since & SINCE_VALUE_MASK >= delay & SINCE_VALUE_MASK)
+    }
+}
+
+// `number + index / length` of an epoch `since` value as a numerator over the length
+fn epoch_fraction(since: u64) -> Result<(u128, u128), Error> {
+    let number = (since & EPOCH_NUMBER_MASK) as u128;
+    let index = ((since >> 24) & 0xffff) as u128;
+    let length = ((since >> 40) & 0xffff) as u128;
+    match length {
+        0 if index == 0 => Ok((number, 1)),
+        _ if index < length => Ok((number * length + index, length)),
+        _ => Err(Error::InvalidSince),
+    }
+}
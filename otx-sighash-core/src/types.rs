@@ -0,0 +1,321 @@
+use crate::error::Error;
+
+pub const SIGHASH_ALL_SIGNATURE_SIZE: usize = 65;
+pub const MAGIC_CODE: &str = "COTX";
+pub const BLAKE160_SIZE: usize = 20;
+pub const UDT_AMOUNT_SIZE: usize = 16;
+pub const LIMIT_ORDER_INTENT_SIZE: usize = 32 + 32 + 8 + 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SighashMode {
+    All = 0x01,
+    None = 0x02,
+    Single = 0x03,
+    AllAnyoneCanPay = 0x81,
+    NoneAnyoneCanPay = 0x82,
+    SingleAnyoneCanPay = 0x83,
+    SingleUdtAnyoneCanPay = 0x84,
+    LimitOrder = 0x85,
+}
+
+impl SighashMode {
+    pub fn from_byte(value: u8) -> Result<SighashMode, Error> {
+        match value {
+            0x01 => Ok(SighashMode::All),
+            0x02 => Ok(SighashMode::None),
+            0x03 => Ok(SighashMode::Single),
+            0x81 => Ok(SighashMode::AllAnyoneCanPay),
+            0x82 => Ok(SighashMode::NoneAnyoneCanPay),
+            0x83 => Ok(SighashMode::SingleAnyoneCanPay),
+            0x84 => Ok(SighashMode::SingleUdtAnyoneCanPay),
+            0x85 => Ok(SighashMode::LimitOrder),
+            _ => Err(Error::Encoding),
+        }
+    }
+
+    /// Size of the mode specific payload following the signature in the witness lock.
+    pub fn payload_size(&self) -> usize {
+        match self {
+            SighashMode::SingleUdtAnyoneCanPay => UDT_REQUIREMENT_SIZE,
+            SighashMode::LimitOrder => LIMIT_ORDER_INTENT_SIZE + 4,
+            _ => 0,
+        }
+    }
+}
+
+pub const UDT_REQUIREMENT_SIZE: usize = 32 + 8 + 32 + UDT_AMOUNT_SIZE;
+
+/// Payload of `SingleUdtAnyoneCanPay`:
+/// `lock_hash (32 bytes) | capacity (u64) | type_hash (32 bytes) | udt_amount (u128)`.
+///
+/// Outputs locked by `lock_hash` must carry at least `capacity` shannons in total, and
+/// those of them whose type hash is `type_hash` at least `udt_amount` UDT units. An all
+/// zero `type_hash` means no UDT is required.
+pub struct UdtRequirement {
+    pub lock_hash: [u8; 32],
+    pub capacity: u64,
+    pub type_hash: Option<[u8; 32]>,
+    pub udt_amount: u128,
+}
+
+impl UdtRequirement {
+    pub fn from_slice(payload: &[u8]) -> Result<UdtRequirement, Error> {
+        if payload.len() != UDT_REQUIREMENT_SIZE {
+            return Err(Error::Encoding);
+        }
+        let mut lock_hash = [0u8; 32];
+        lock_hash.copy_from_slice(&payload[..32]);
+        let mut capacity = [0u8; 8];
+        capacity.copy_from_slice(&payload[32..40]);
+        let mut type_hash = [0u8; 32];
+        type_hash.copy_from_slice(&payload[40..72]);
+        let mut udt_amount = [0u8; UDT_AMOUNT_SIZE];
+        udt_amount.copy_from_slice(&payload[72..]);
+
+        Ok(UdtRequirement {
+            lock_hash,
+            capacity: u64::from_le_bytes(capacity),
+            type_hash: if type_hash == [0u8; 32] {
+                None
+            } else {
+                Some(type_hash)
+            },
+            udt_amount: u128::from_le_bytes(udt_amount),
+        })
+    }
+}
+
+pub const EXTENSION_EXPIRY: u8 = 0x01;
+pub const EXTENSION_CELL_DEPS: u8 = 0x02;
+pub const EXTENSION_HEADER_DEPS: u8 = 0x04;
+pub const EXTENSION_OTX_SCOPE: u8 = 0x08;
+pub const EXTENSION_DELEGATION: u8 = 0x10;
+pub const EXTENSION_RECOVERY: u8 = 0x20;
+pub const EXTENSION_FEE_LIMIT: u8 = 0x40;
+pub const EXTENSION_READABLE: u8 = 0x80;
+
+/// Optional part of the witness lock following the mode specific payload:
+/// `flags (1 byte) | fields`, where the fields appear in the order of their flag bits.
+///
+/// - `EXTENSION_EXPIRY`: `expiry (u64)`, an absolute `since` value that the header deps
+///   must not exceed
+/// - `EXTENSION_CELL_DEPS`: no field, the digest also covers all cell deps
+/// - `EXTENSION_HEADER_DEPS`: no field, the digest also covers all header deps
+/// - `EXTENSION_OTX_SCOPE`: `input_start (u32) | input_count (u32) | output_start (u32) |
+///   output_count (u32)`, see `OtxScope`
+/// - `EXTENSION_DELEGATION`: a certificate of the session key that made the signature, see
+///   `Delegation`
+/// - `EXTENSION_RECOVERY`: no field, the signature is made by the recovery key in the args
+/// - `EXTENSION_FEE_LIMIT`: `max_fee (u64)`, the most shannons the otx may lose to the fee,
+///   ALL|ANYONECANPAY and SINGLE|ANYONECANPAY only
+/// - `EXTENSION_READABLE`: no field, the signed preimage spells out what the user approves,
+///   see `add_readable_prefix`, SINGLE modes only
+pub struct WitnessExtension {
+    pub expiry: Option<u64>,
+    pub commit_cell_deps: bool,
+    pub commit_header_deps: bool,
+    pub otx_scope: Option<OtxScope>,
+    pub delegation: Option<Delegation>,
+    pub recovery: bool,
+    pub max_fee: Option<u64>,
+    pub readable: bool,
+}
+
+/// The segment of a merged transaction taken by an ALL-style otx.
+///
+/// The counts are signed while the starts are zeroed in the digest, so a batcher can place
+/// the otx at any offset.
+pub struct OtxScope {
+    pub input_start: usize,
+    pub input_count: usize,
+    pub output_start: usize,
+    pub output_count: usize,
+    // position of the field in the extension
+    pub offset: usize,
+}
+
+// sighash byte in the prefix of certificates, not a valid mode
+pub const DELEGATION_CERTIFICATE: u8 = 0x00;
+pub const DELEGATION_UNLIMITED_CAPACITY: u64 = u64::MAX;
+
+/// Delegation certificate: `session_pubkey_hash (20 bytes) | allowed_modes (u8) |
+/// expiry (u64) | max_capacity_out (u64) | owner_signature (65 bytes)`.
+///
+/// The owner signs the fields before the signature, allowing the session key to sign with
+/// the modes `0x81 + n` for every bit `n` set in `allowed_modes`. `max_capacity_out` bounds
+/// the capacity the lock group may lose in the transaction, `DELEGATION_UNLIMITED_CAPACITY`
+/// for no limit.
+///
+/// `expiry`, an absolute `since` value, is checked against the header deps like a signature
+/// expiry.
+pub struct Delegation {
+    pub session_pubkey_hash: [u8; BLAKE160_SIZE],
+    pub allowed_modes: u8,
+    pub expiry: u64,
+    pub max_capacity_out: u64,
+    pub owner_signature: [u8; SIGHASH_ALL_SIGNATURE_SIZE],
+}
+
+impl Delegation {
+    pub fn allows(&self, mode: SighashMode) -> bool {
+        let mode = mode as u8;
+        mode >= 0x81 && mode - 0x81 < 8 && self.allowed_modes & (1 << (mode - 0x81)) != 0
+    }
+}
+
+impl WitnessExtension {
+    pub fn from_slice(extension: &[u8]) -> Result<WitnessExtension, Error> {
+        let mut ext = WitnessExtension {
+            expiry: None,
+            commit_cell_deps: false,
+            commit_header_deps: false,
+            otx_scope: None,
+            delegation: None,
+            recovery: false,
+            max_fee: None,
+            readable: false,
+        };
+        if extension.is_empty() {
+            return Ok(ext);
+        }
+        let flags = extension[0];
+        if flags
+            & !(EXTENSION_EXPIRY
+                | EXTENSION_CELL_DEPS
+                | EXTENSION_HEADER_DEPS
+                | EXTENSION_OTX_SCOPE
+                | EXTENSION_DELEGATION
+                | EXTENSION_RECOVERY
+                | EXTENSION_FEE_LIMIT
+                | EXTENSION_READABLE)
+            != 0
+        {
+            return Err(Error::Encoding);
+        }
+        ext.commit_cell_deps = flags & EXTENSION_CELL_DEPS != 0;
+        ext.commit_header_deps = flags & EXTENSION_HEADER_DEPS != 0;
+        ext.recovery = flags & EXTENSION_RECOVERY != 0;
+        ext.readable = flags & EXTENSION_READABLE != 0;
+        let mut fields = &extension[1..];
+        if flags & EXTENSION_EXPIRY != 0 {
+            ext.expiry = Some(u64::from_le_bytes(take(&mut fields)?));
+        }
+        if flags & EXTENSION_OTX_SCOPE != 0 {
+            let offset = extension.len() - fields.len();
+            ext.otx_scope = Some(OtxScope {
+                input_start: u32::from_le_bytes(take(&mut fields)?) as usize,
+                input_count: u32::from_le_bytes(take(&mut fields)?) as usize,
+                output_start: u32::from_le_bytes(take(&mut fields)?) as usize,
+                output_count: u32::from_le_bytes(take(&mut fields)?) as usize,
+                offset,
+            });
+        }
+        if flags & EXTENSION_DELEGATION != 0 {
+            ext.delegation = Some(Delegation {
+                session_pubkey_hash: take(&mut fields)?,
+                allowed_modes: take::<1>(&mut fields)?[0],
+                expiry: u64::from_le_bytes(take(&mut fields)?),
+                max_capacity_out: u64::from_le_bytes(take(&mut fields)?),
+                owner_signature: take(&mut fields)?,
+            });
+        }
+        if flags & EXTENSION_FEE_LIMIT != 0 {
+            ext.max_fee = Some(u64::from_le_bytes(take(&mut fields)?));
+        }
+        if !fields.is_empty() {
+            return Err(Error::Encoding);
+        }
+        Ok(ext)
+    }
+}
+
+/// Parsed witness lock: `sighash_mode (1 byte) | signature (65 bytes) | payload | [extension]`.
+pub struct WitnessLock<'a> {
+    pub mode: SighashMode,
+    pub signature: [u8; SIGHASH_ALL_SIGNATURE_SIZE],
+    pub payload: &'a [u8],
+    // raw extension bytes, committed by every mode
+    pub extension_data: &'a [u8],
+    pub extension: WitnessExtension,
+}
+
+impl<'a> WitnessLock<'a> {
+    pub fn from_slice(witness_lock: &'a [u8]) -> Result<WitnessLock<'a>, Error> {
+        if witness_lock.len() < SIGHASH_ALL_SIGNATURE_SIZE + 1 {
+            return Err(Error::Encoding);
+        }
+        let mode = SighashMode::from_byte(witness_lock[0])?;
+        let mut signature = [0u8; SIGHASH_ALL_SIGNATURE_SIZE];
+        signature.copy_from_slice(&witness_lock[1..1 + SIGHASH_ALL_SIGNATURE_SIZE]);
+
+        // the mode specific payload follows the signature, then the optional extension
+        let rest = &witness_lock[1 + SIGHASH_ALL_SIGNATURE_SIZE..];
+        if rest.len() < mode.payload_size() {
+            return Err(Error::Encoding);
+        }
+        let (payload, extension_data) = rest.split_at(mode.payload_size());
+
+        Ok(WitnessLock {
+            mode,
+            signature,
+            payload,
+            extension_data,
+            extension: WitnessExtension::from_slice(extension_data)?,
+        })
+    }
+}
+
+pub(crate) fn take<const N: usize>(data: &mut &[u8]) -> Result<[u8; N], Error> {
+    if data.len() < N {
+        return Err(Error::Encoding);
+    }
+    let mut buf = [0u8; N];
+    buf.copy_from_slice(&data[..N]);
+    *data = &data[N..];
+    Ok(buf)
+}
+
+/// Signed part of the `LimitOrder` payload:
+/// `sell_type_hash (32 bytes) | buy_type_hash (32 bytes) | price_numerator (u64) | price_denominator (u64)`.
+///
+/// An all zero type hash stands for CKB. For every sold unit the maker receives
+/// `price_numerator / price_denominator` units of the bought asset. The payload ends
+/// with the unsigned `payment_output_index (u32)` chosen by whoever fills the order.
+pub struct LimitOrderIntent {
+    pub sell_type_hash: Option<[u8; 32]>,
+    pub buy_type_hash: Option<[u8; 32]>,
+    pub price_numerator: u64,
+    pub price_denominator: u64,
+}
+
+impl LimitOrderIntent {
+    pub fn from_slice(intent: &[u8]) -> Result<LimitOrderIntent, Error> {
+        if intent.len() != LIMIT_ORDER_INTENT_SIZE {
+            return Err(Error::Encoding);
+        }
+        let type_hash = |data: &[u8]| {
+            let mut hash = [0u8; 32];
+            hash.copy_from_slice(data);
+            if hash == [0u8; 32] {
+                None
+            } else {
+                Some(hash)
+            }
+        };
+        let mut price_numerator = [0u8; 8];
+        price_numerator.copy_from_slice(&intent[64..72]);
+        let mut price_denominator = [0u8; 8];
+        price_denominator.copy_from_slice(&intent[72..80]);
+        let price_denominator = u64::from_le_bytes(price_denominator);
+        if price_denominator == 0 {
+            return Err(Error::Encoding);
+        }
+
+        Ok(LimitOrderIntent {
+            sell_type_hash: type_hash(&intent[..32]),
+            buy_type_hash: type_hash(&intent[32..64]),
+            price_numerator: u64::from_le_bytes(price_numerator),
+            price_denominator,
+        })
+    }
+}
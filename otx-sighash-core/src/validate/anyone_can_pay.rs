@@ -0,0 +1,123 @@
+use crate::args::LockArgs;
+use crate::error::Error;
+use crate::script::{ScriptAccess, Source};
+use crate::types::UDT_AMOUNT_SIZE;
+
+use alloc::vec::Vec;
+
+struct PaymentCell {
+    type_hash: Option<[u8; 32]>,
+    capacity: u64,
+    udt_amount: Option<u128>,
+    // cell data after the UDT amount, or the whole data of a cell without type
+    extra_data: Vec<u8>,
+}
+
+impl PaymentCell {
+    fn load<S: ScriptAccess>(
+        script: &S,
+        index: usize,
+        source: Source,
+    ) -> Result<PaymentCell, Error> {
+        let type_hash = script.cell_type_hash(index, source)?;
+        let capacity = script.cell_capacity(index, source)?;
+        let mut data = script.cell_data(index, source)?;
+        let udt_amount = match type_hash {
+            Some(_) => {
+                if data.len() < UDT_AMOUNT_SIZE {
+                    return Err(Error::Encoding);
+                }
+                let mut buf = [0u8; UDT_AMOUNT_SIZE];
+                buf.copy_from_slice(&data[..UDT_AMOUNT_SIZE]);
+                data.drain(..UDT_AMOUNT_SIZE);
+                Some(u128::from_le_bytes(buf))
+            }
+            None => None,
+        };
+        Ok(PaymentCell {
+            type_hash,
+            capacity,
+            udt_amount,
+            extra_data: data,
+        })
+    }
+}
+
+/// Unlocks inputs without a signature, following the anyone-can-pay lock.
+///
+/// Each input must be paired with exactly one output that has the same lock and type,
+/// leaving out the `claimed_outputs` committed by signed inputs of this lock, such as limit
+/// order fills.
+/// The output keeps the cell data beyond the UDT amount, neither the capacity nor the
+/// UDT amount may decrease, and the payment must reach `10^min_ckb` shannons or
+/// `10^min_udt` UDT units.
+pub(crate) fn validate_anyone_can_pay<S: ScriptAccess>(
+    script: &S,
+    group_inputs_absolute_indices: &[usize],
+    args: &LockArgs,
+    claimed_outputs: &[usize],
+) -> Result<(), Error> {
+    // receiving payments is only enabled when args carry the minimums
+    let min_ckb = match args.min_ckb {
+        Some(x) => 10u64.pow(x as u32),
+        None => return Err(Error::AnyoneCanPayDisabled),
+    };
+    let min_udt = args.min_udt.map(|x| 10u128.pow(x as u32));
+
+    let mut inputs: Vec<PaymentCell> = Vec::new();
+    for i in group_inputs_absolute_indices {
+        let cell = PaymentCell::load(script, *i, Source::Input)?;
+        if inputs.iter().any(|input| input.type_hash == cell.type_hash) {
+            return Err(Error::DuplicatedInputs);
+        }
+        inputs.push(cell);
+    }
+
+    // pair every input with the output of the same lock and type
+    let current_script_hash = script.script_hash()?;
+    let mut outputs: Vec<Option<PaymentCell>> = inputs.iter().map(|_| None).collect();
+    for i in 0..script.outputs_len()? {
+        if claimed_outputs.contains(&i)
+            || script.cell_lock_hash(i, Source::Output)? != current_script_hash
+        {
+            continue;
+        }
+        let type_hash = script.cell_type_hash(i, Source::Output)?;
+        if let Some(pos) = inputs.iter().position(|input| input.type_hash == type_hash) {
+            if outputs[pos].is_some() {
+                return Err(Error::DuplicatedOutputs);
+            }
+            outputs[pos] = Some(PaymentCell::load(script, i, Source::Output)?);
+        }
+    }
+
+    for (input, output) in inputs.iter().zip(outputs.iter()) {
+        let output = output.as_ref().ok_or(Error::NoPair)?;
+        if output.extra_data != input.extra_data {
+            return Err(Error::OutputDataChanged);
+        }
+        let paid_ckb = output
+            .capacity
+            .checked_sub(input.capacity)
+            .ok_or(Error::OutputAmountNotEnough)?;
+        let paid_udt = match (input.udt_amount, output.udt_amount) {
+            (Some(input_amount), Some(output_amount)) => Some(
+                output_amount
+                    .checked_sub(input_amount)
+                    .ok_or(Error::OutputAmountNotEnough)?,
+            ),
+            _ => None,
+        };
+
+        // either minimum is enough when both are set
+        let udt_satisfied = match (min_udt, paid_udt) {
+            (Some(min_udt), Some(paid_udt)) => paid_udt >= min_udt,
+            _ => false,
+        };
+        if paid_ckb < min_ckb && !udt_satisfied {
+            return Err(Error::OutputAmountNotEnough);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use crate::error::Error;
+use crate::helper::load_witness_lock;
+use crate::molecule::table_fields;
+use crate::script::{ScriptAccess, Source};
+use crate::types::{SighashMode, UdtRequirement, WitnessLock};
+use crate::validate::limit_order::payment_index;
+
+use alloc::vec::Vec;
+
+/// What the inputs locked by this lock code, in any lock group, take from the outputs of the
+/// transaction, collected before any input is validated. Every group collects the same
+/// claims, so that an output pays for one input of the whole transaction.
+pub(crate) struct Claims {
+    /// Outputs committed by signed inputs:
+    ///
+    /// - SINGLE|ANYONECANPAY: the output at the index of the input
+    /// - limit orders: the remainder at the index of the input and the payment
+    pub outputs: Vec<usize>,
+    /// Lock and type hashes of the inputs without a witness lock, paid by the output of the
+    /// same lock and type.
+    pub payments: Vec<([u8; 32], Option<[u8; 32]>)>,
+    /// SINGLE|ANYONECANPAY|UDT requirements, summed per lock and type.
+    pub requirements: Vec<UdtRequirement>,
+}
+
+impl Claims {
+    /// Whether the output at `index` is spoken for, paying an anyone-can-pay input or committed
+    /// by a signature, so that it cannot count toward a UDT requirement.
+    pub fn is_claimed<S: ScriptAccess>(&self, script: &S, index: usize) -> Result<bool, Error> {
+        if self.outputs.contains(&index) {
+            return Ok(true);
+        }
+        if self.payments.is_empty() {
+            return Ok(false);
+        }
+        let payment = (
+            script.cell_lock_hash(index, Source::Output)?,
+            script.cell_type_hash(index, Source::Output)?,
+        );
+        Ok(self.payments.contains(&payment))
+    }
+}
+
+/// Collects the claims of every input whose lock has the code hash and hash type of the
+/// running script.
+///
+/// Witnesses of other groups are only read here, their own group verifies them: a witness
+/// lock that does not parse, such as a CoBuild seal or a 65-byte signature, claims nothing.
+pub(crate) fn collect_claims<S: ScriptAccess>(script: &S) -> Result<Claims, Error> {
+    let mut claims = Claims {
+        outputs: Vec::new(),
+        payments: Vec::new(),
+        requirements: Vec::new(),
+    };
+    let code = lock_code(&script.cell(0, Source::GroupInput)?)?;
+    for i in 0..script.inputs_len()? {
+        if lock_code(&script.cell(i, Source::Input)?)? != code {
+            continue;
+        }
+        let witness_lock = match load_witness_lock(script, i, Source::Input) {
+            Ok(Some(witness_lock)) => witness_lock,
+            Ok(None) => {
+                claims.payments.push((
+                    script.cell_lock_hash(i, Source::Input)?,
+                    script.cell_type_hash(i, Source::Input)?,
+                ));
+                continue;
+            }
+            Err(_) => continue,
+        };
+        let witness_lock = match WitnessLock::from_slice(&witness_lock) {
+            Ok(witness_lock) => witness_lock,
+            Err(_) => continue,
+        };
+        match witness_lock.mode {
+            SighashMode::SingleAnyoneCanPay => claim(&mut claims.outputs, i)?,
+            SighashMode::LimitOrder => {
+                claim(&mut claims.outputs, i)?;
+                claim(&mut claims.outputs, payment_index(&witness_lock))?;
+            }
+            SighashMode::SingleUdtAnyoneCanPay => {
+                add_requirement(
+                    &mut claims.requirements,
+                    UdtRequirement::from_slice(witness_lock.payload)?,
+                )?;
+            }
+            _ => {}
+        }
+    }
+    Ok(claims)
+}
+
+// adds `index` to the claimed outputs, failing if another input claimed it first
+fn claim(claimed_outputs: &mut Vec<usize>, index: usize) -> Result<(), Error> {
+    if claimed_outputs.contains(&index) {
+        return Err(Error::OutputAlreadyClaimed);
+    }
+    claimed_outputs.push(index);
+    Ok(())
+}
+
+// requirements of the same lock and type are summed up
+fn add_requirement(
+    requirements: &mut Vec<UdtRequirement>,
+    requirement: UdtRequirement,
+) -> Result<(), Error> {
+    match requirements
+        .iter_mut()
+        .find(|r| r.lock_hash == requirement.lock_hash && r.type_hash == requirement.type_hash)
+    {
+        Some(r) => {
+            r.capacity = r
+                .capacity
+                .checked_add(requirement.capacity)
+                .ok_or(Error::Overflow)?;
+            r.udt_amount = r
+                .udt_amount
+                .checked_add(requirement.udt_amount)
+                .ok_or(Error::Overflow)?;
+        }
+        None => requirements.push(requirement),
+    }
+    Ok(())
+}
+
+// `code_hash | hash_type` of the lock of a `CellOutput`
+fn lock_code(cell_output: &[u8]) -> Result<Vec<u8>, Error> {
+    let lock = *table_fields(cell_output)?.get(1).ok_or(Error::Encoding)?;
+    let fields = table_fields(lock)?;
+    if fields.len() < 2 {
+        return Err(Error::Encoding);
+    }
+    Ok([fields[0], fields[1]].concat())
+}
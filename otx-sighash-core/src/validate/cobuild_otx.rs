@@ -0,0 +1,165 @@
+use crate::cobuild::{new_otx_blake2b, Otx, WitnessLayout};
+use crate::error::Error;
+use crate::helper::Signers;
+use crate::molecule::{fixvec_item, fixvec_len};
+use crate::script::{ScriptAccess, Source};
+use crate::types::SIGHASH_ALL_SIGNATURE_SIZE;
+
+use alloc::vec::Vec;
+
+// `OutPoint | dep_type`
+const CELL_DEP_SIZE: usize = 32 + 4 + 1;
+
+// start of the otx in each of the transaction vectors
+#[derive(Clone, Copy)]
+struct OtxCursor {
+    input_cell: usize,
+    output_cell: usize,
+    cell_deps: usize,
+    header_deps: usize,
+}
+
+/// Verifies the group inputs that belong to CoBuild open transactions.
+///
+/// The `OtxStart` witness is followed by consecutive `Otx` witnesses, each of them taking the
+/// next `input_cells`, `output_cells`, `cell_deps` and `header_deps` of the transaction. Every
+/// otx containing group inputs must carry a seal for the current script, a signature over the
+/// otx signing message.
+///
+/// Returns `false` when no group input belongs to an otx, the group is then left to the other
+/// modes. Otherwise all of the group inputs must be covered by otxs.
+pub(crate) fn validate_cobuild_otx<S: ScriptAccess>(
+    script: &S,
+    group_inputs_absolute_indices: &[usize],
+    signers: &mut Signers,
+) -> Result<bool, Error> {
+    let witnesses = (0..script.witnesses_len()?)
+        .map(|i| script.witness(i, Source::Input))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // locate the only OtxStart
+    let mut otx_start = None;
+    for (i, witness) in witnesses.iter().enumerate() {
+        if let Some(WitnessLayout::OtxStart(start)) = WitnessLayout::from_slice(witness)? {
+            if otx_start.is_some() {
+                return Err(Error::InvalidOtx);
+            }
+            otx_start = Some((i, start));
+        }
+    }
+    let (start_index, start) = match otx_start {
+        Some(otx_start) => otx_start,
+        None => return Ok(false),
+    };
+
+    let cell_deps = script.cell_deps()?;
+    let header_deps = script.header_deps()?;
+    let current_script_hash = script.script_hash()?;
+    let mut cursor = OtxCursor {
+        input_cell: start.start_input_cell as usize,
+        output_cell: start.start_output_cell as usize,
+        cell_deps: start.start_cell_deps as usize,
+        header_deps: start.start_header_deps as usize,
+    };
+    let mut covered_inputs = 0;
+    for witness in witnesses[start_index + 1..].iter() {
+        let otx = match WitnessLayout::from_slice(witness)? {
+            Some(WitnessLayout::Otx(otx)) => otx,
+            _ => break,
+        };
+        let next = OtxCursor {
+            input_cell: checked_end(cursor.input_cell, otx.input_cells, script.inputs_len()?)?,
+            output_cell: checked_end(cursor.output_cell, otx.output_cells, script.outputs_len()?)?,
+            cell_deps: checked_end(cursor.cell_deps, otx.cell_deps, fixvec_len(&cell_deps)?)?,
+            header_deps: checked_end(
+                cursor.header_deps,
+                otx.header_deps,
+                fixvec_len(&header_deps)?,
+            )?,
+        };
+
+        let otx_group_inputs: Vec<usize> = group_inputs_absolute_indices
+            .iter()
+            .copied()
+            .filter(|i| (cursor.input_cell..next.input_cell).contains(i))
+            .collect();
+        if !otx_group_inputs.is_empty() {
+            let seal = otx
+                .seals
+                .iter()
+                .find(|seal_pair| seal_pair.script_hash == &current_script_hash[..])
+                .ok_or(Error::SealMissing)?
+                .seal;
+            if seal.len() != SIGHASH_ALL_SIGNATURE_SIZE {
+                return Err(Error::Encoding);
+            }
+            let mut signature = [0u8; SIGHASH_ALL_SIGNATURE_SIZE];
+            signature.copy_from_slice(seal);
+
+            let message = otx_signing_message(script, &cell_deps, &header_deps, &otx, cursor)?;
+            signers.verify(script, &message, &signature, &otx_group_inputs)?;
+            covered_inputs += otx_group_inputs.len();
+        }
+        cursor = next;
+    }
+
+    if covered_inputs == 0 {
+        return Ok(false);
+    }
+    if covered_inputs != group_inputs_absolute_indices.len() {
+        return Err(Error::OtxNotCovered);
+    }
+    Ok(true)
+}
+
+fn checked_end(start: usize, count: u32, total: usize) -> Result<usize, Error> {
+    match start.checked_add(count as usize) {
+        Some(end) if end <= total => Ok(end),
+        _ => Err(Error::InvalidOtx),
+    }
+}
+
+fn otx_signing_message<S: ScriptAccess>(
+    script: &S,
+    cell_deps: &[u8],
+    header_deps: &[u8],
+    otx: &Otx,
+    start: OtxCursor,
+) -> Result<[u8; 32], Error> {
+    let mut blake2b = new_otx_blake2b();
+    blake2b.update(otx.message);
+
+    blake2b.update(&otx.input_cells.to_le_bytes());
+    for i in start.input_cell..start.input_cell + otx.input_cells as usize {
+        let input = script.input(i)?;
+        let input_cell = script.cell(i, Source::Input)?;
+        let input_cell_data = script.cell_data(i, Source::Input)?;
+        blake2b.update(&input);
+        blake2b.update(&input_cell);
+        blake2b.update(&(input_cell_data.len() as u32).to_le_bytes());
+        blake2b.update(&input_cell_data);
+    }
+
+    blake2b.update(&otx.output_cells.to_le_bytes());
+    for i in start.output_cell..start.output_cell + otx.output_cells as usize {
+        let output_cell = script.cell(i, Source::Output)?;
+        let output_cell_data = script.cell_data(i, Source::Output)?;
+        blake2b.update(&output_cell);
+        blake2b.update(&(output_cell_data.len() as u32).to_le_bytes());
+        blake2b.update(&output_cell_data);
+    }
+
+    blake2b.update(&otx.cell_deps.to_le_bytes());
+    for i in start.cell_deps..start.cell_deps + otx.cell_deps as usize {
+        blake2b.update(fixvec_item(cell_deps, CELL_DEP_SIZE, i)?);
+    }
+
+    blake2b.update(&otx.header_deps.to_le_bytes());
+    for i in start.header_deps..start.header_deps + otx.header_deps as usize {
+        blake2b.update(fixvec_item(header_deps, 32, i)?);
+    }
+
+    let mut message = [0u8; 32];
+    blake2b.finalize(&mut message);
+    Ok(message)
+}
@@ -0,0 +1,85 @@
+use crate::cobuild::{new_sighash_all_blake2b, new_sighash_all_only_blake2b, WitnessLayout};
+use crate::error::Error;
+use crate::helper::Signers;
+use crate::script::{ScriptAccess, Source};
+use crate::types::SIGHASH_ALL_SIGNATURE_SIZE;
+
+use alloc::vec::Vec;
+
+/// Verifies a group sealed by a CoBuild `SighashAll` or `SighashAllOnly` witness.
+///
+/// The seal is carried by the first witness of the group. The signing message covers the
+/// CoBuild `Message` of the transaction, if any, the transaction hash, all input cells with
+/// their data, and the witnesses beyond the inputs.
+///
+/// Returns `false` when the first witness of the group does not use the CoBuild layout.
+pub(crate) fn validate_cobuild_sighash_all<S: ScriptAccess>(
+    script: &S,
+    group_inputs_absolute_indices: &[usize],
+    signers: &mut Signers,
+) -> Result<bool, Error> {
+    let witness = match script.witness(0, Source::GroupInput) {
+        Ok(witness) => witness,
+        Err(Error::IndexOutOfBound) => return Ok(false),
+        Err(err) => return Err(err),
+    };
+    let seal = match WitnessLayout::from_slice(&witness)? {
+        Some(WitnessLayout::SighashAll { seal, .. }) => seal,
+        Some(WitnessLayout::SighashAllOnly { seal }) => seal,
+        _ => return Ok(false),
+    };
+    if seal.len() != SIGHASH_ALL_SIGNATURE_SIZE {
+        return Err(Error::Encoding);
+    }
+    let mut signature = [0u8; SIGHASH_ALL_SIGNATURE_SIZE];
+    signature.copy_from_slice(seal);
+
+    let witnesses = (0..script.witnesses_len()?)
+        .map(|i| script.witness(i, Source::Input))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut message = None;
+    for witness in witnesses.iter() {
+        if let Some(WitnessLayout::SighashAll { message: m, .. }) =
+            WitnessLayout::from_slice(witness)?
+        {
+            if message.is_some() {
+                return Err(Error::DuplicatedMessage);
+            }
+            message = Some(m);
+        }
+    }
+
+    let mut blake2b = match message {
+        Some(message) => {
+            let mut blake2b = new_sighash_all_blake2b();
+            blake2b.update(message);
+            blake2b
+        }
+        None => new_sighash_all_only_blake2b(),
+    };
+    blake2b.update(&script.tx_hash()?);
+
+    let inputs_len = script.inputs_len()?;
+    for i in 0..inputs_len {
+        let input_cell = script.cell(i, Source::Input)?;
+        let input_cell_data = script.cell_data(i, Source::Input)?;
+        blake2b.update(&input_cell);
+        blake2b.update(&(input_cell_data.len() as u32).to_le_bytes());
+        blake2b.update(&input_cell_data);
+    }
+
+    for witness in witnesses.iter().skip(inputs_len) {
+        blake2b.update(&(witness.len() as u32).to_le_bytes());
+        blake2b.update(witness);
+    }
+
+    let mut signing_message = [0u8; 32];
+    blake2b.finalize(&mut signing_message);
+    signers.verify(
+        script,
+        &signing_message,
+        &signature,
+        group_inputs_absolute_indices,
+    )?;
+    Ok(true)
+}
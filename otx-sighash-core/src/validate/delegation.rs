@@ -0,0 +1,50 @@
+use crate::digest::delegation_message;
+use crate::error::Error;
+use crate::helper::{check_expiry, sum_capacity, verify_pubkey_hash};
+use crate::script::{ScriptAccess, Source};
+use crate::types::{Delegation, SighashMode, DELEGATION_UNLIMITED_CAPACITY};
+
+use alloc::vec::Vec;
+
+/// Verifies that the owner delegated signing with `mode` to the session key of the
+/// certificate, and that the transaction stays within the certificate constraints.
+///
+/// The otx signature itself is then verified against the session pubkey hash.
+pub(crate) fn validate_delegation<S: ScriptAccess>(
+    script: &S,
+    delegation: &Delegation,
+    mode: SighashMode,
+    owner_pubkey_hash: &[u8],
+) -> Result<(), Error> {
+    verify_pubkey_hash(
+        script,
+        &delegation_message(delegation),
+        &delegation.owner_signature,
+        owner_pubkey_hash,
+    )?;
+
+    if !delegation.allows(mode) {
+        return Err(Error::ModeNotDelegated);
+    }
+    check_expiry(script, delegation.expiry)?;
+
+    if delegation.max_capacity_out != DELEGATION_UNLIMITED_CAPACITY {
+        let current_script_hash = script.script_hash()?;
+        let capacity_in = sum_capacity(
+            script,
+            0..script.cells_len(Source::GroupInput)?,
+            Source::GroupInput,
+        )?;
+        let mut kept_outputs = Vec::new();
+        for i in 0..script.outputs_len()? {
+            if script.cell_lock_hash(i, Source::Output)? == current_script_hash {
+                kept_outputs.push(i);
+            }
+        }
+        let capacity_kept = sum_capacity(script, kept_outputs.into_iter(), Source::Output)?;
+        if capacity_in.saturating_sub(capacity_kept) > delegation.max_capacity_out {
+            return Err(Error::DelegatedCapacityExceeded);
+        }
+    }
+    Ok(())
+}
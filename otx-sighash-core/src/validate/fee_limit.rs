@@ -0,0 +1,47 @@
+use crate::error::Error;
+use crate::helper::sum_capacity;
+use crate::script::{ScriptAccess, Source};
+use crate::types::{SighashMode, WitnessLock};
+
+/// Bounds the capacity the otx of the input at `index` loses to the transaction fee: the
+/// capacity of its inputs minus the capacity of the outputs committed by the signature.
+///
+/// - SINGLE|ANYONECANPAY: the input and the output at the same index
+/// - ALL|ANYONECANPAY: the inputs and the outputs in the otx scope, or of the whole
+///   transaction without a scope
+pub(crate) fn validate_fee_limit<S: ScriptAccess>(
+    script: &S,
+    index: usize,
+    witness_lock: &WitnessLock,
+    max_fee: u64,
+) -> Result<(), Error> {
+    let (input_capacity, output_capacity) = match witness_lock.mode {
+        SighashMode::SingleAnyoneCanPay => (
+            script.cell_capacity(index, Source::Input)?,
+            script.cell_capacity(index, Source::Output)?,
+        ),
+        SighashMode::AllAnyoneCanPay => match &witness_lock.extension.otx_scope {
+            Some(scope) => (
+                sum_capacity(
+                    script,
+                    scope.input_start..scope.input_start + scope.input_count,
+                    Source::Input,
+                )?,
+                sum_capacity(
+                    script,
+                    scope.output_start..scope.output_start + scope.output_count,
+                    Source::Output,
+                )?,
+            ),
+            None => (
+                sum_capacity(script, 0..script.inputs_len()?, Source::Input)?,
+                sum_capacity(script, 0..script.outputs_len()?, Source::Output)?,
+            ),
+        },
+        _ => return Err(Error::UnsupportedSighashMode),
+    };
+    if input_capacity.saturating_sub(output_capacity) > max_fee {
+        return Err(Error::FeeLimitExceeded);
+    }
+    Ok(())
+}
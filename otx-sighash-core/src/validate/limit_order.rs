@@ -0,0 +1,174 @@
+use crate::digest::{limit_order_intent_hash, limit_order_message};
+use crate::error::Error;
+use crate::helper::{load_udt_amount, verify_pubkey_hash};
+use crate::script::{ScriptAccess, Source};
+use crate::types::{LimitOrderIntent, WitnessLock, LIMIT_ORDER_INTENT_SIZE, UDT_AMOUNT_SIZE};
+
+/// The order cell of a limit order, bound to its intent.
+pub(crate) struct OrderCell {
+    intent: LimitOrderIntent,
+    intent_hash: [u8; 32],
+    capacity: u64,
+    /// The UDT amount, or the capacity of a CKB order.
+    amount: u128,
+}
+
+/// Verifies the signature of a limit order and the order cell at `index`, which carries
+/// `[udt_amount (u128)] | intent_hash (32 bytes)` as its data, where the UDT amount is
+/// present when it sells a UDT.
+///
+/// The signature only commits to the intent and the witness extension, so it stays valid for
+/// every remainder. The fill is checked by `check_limit_order_fill`.
+pub(crate) fn validate_limit_order<S: ScriptAccess>(
+    script: &S,
+    index: usize,
+    witness_lock: &WitnessLock,
+    expected_pubkey_hash: &[u8],
+) -> Result<OrderCell, Error> {
+    let message = limit_order_message(script, witness_lock)?;
+    verify_pubkey_hash(
+        script,
+        &message,
+        &witness_lock.signature,
+        expected_pubkey_hash,
+    )?;
+
+    load_order_cell(script, index, witness_lock)
+}
+
+/// Fills a limit order partially or fully.
+///
+/// The output at `index` is the remainder: it keeps the lock, the type and the intent hash,
+/// with the amount decreased by the sold amount. The output at the payment index pays the
+/// maker, under the same lock, at least `sold * price_numerator / price_denominator` of the
+/// bought asset.
+///
+/// A UDT order is filled completely with a remainder of no UDT, which keeps the capacity of
+/// the order cell. A CKB order sells its capacity, so it is filled completely when the output
+/// at `index` is missing or under another lock.
+///
+/// Both outputs are claimed by `collect_claims` beforehand, so that no other input counts
+/// them.
+pub(crate) fn check_limit_order_fill<S: ScriptAccess>(
+    script: &S,
+    index: usize,
+    witness_lock: &WitnessLock,
+    order_cell: &OrderCell,
+) -> Result<(), Error> {
+    let payment_index = payment_index(witness_lock);
+    let intent = &order_cell.intent;
+
+    // remainder
+    let current_script_hash = script.script_hash()?;
+    let remainder_lock_hash = match script.cell_lock_hash(index, Source::Output) {
+        Ok(lock_hash) => Some(lock_hash),
+        Err(Error::IndexOutOfBound) => None,
+        Err(err) => return Err(err),
+    };
+    let remainder_amount =
+        if intent.sell_type_hash.is_none() && remainder_lock_hash != Some(current_script_hash) {
+            0
+        } else {
+            if remainder_lock_hash != Some(current_script_hash)
+                || script.cell_type_hash(index, Source::Output)? != intent.sell_type_hash
+            {
+                return Err(Error::InvalidRemainder);
+            }
+            let remainder_capacity = script.cell_capacity(index, Source::Output)?;
+            if intent.sell_type_hash.is_some() && remainder_capacity < order_cell.capacity {
+                return Err(Error::InvalidRemainder);
+            }
+            load_order_amount(
+                script,
+                index,
+                Source::Output,
+                intent,
+                &order_cell.intent_hash,
+            )?
+            .ok_or(Error::InvalidRemainder)?
+        };
+    let sold = order_cell
+        .amount
+        .checked_sub(remainder_amount)
+        .ok_or(Error::InvalidRemainder)?;
+
+    // payment
+    if script.cell_lock_hash(payment_index, Source::Output)? != current_script_hash
+        || script.cell_type_hash(payment_index, Source::Output)? != intent.buy_type_hash
+    {
+        return Err(Error::PriceNotMet);
+    }
+    let received = match intent.buy_type_hash {
+        Some(_) => load_udt_amount(script, payment_index, Source::Output)?,
+        None => script.cell_capacity(payment_index, Source::Output)? as u128,
+    };
+
+    // round the price in favor of the maker
+    let expected = sold
+        .checked_mul(intent.price_numerator as u128)
+        .and_then(|amount| amount.checked_add(intent.price_denominator as u128 - 1))
+        .ok_or(Error::Overflow)?
+        / intent.price_denominator as u128;
+    if received < expected {
+        return Err(Error::PriceNotMet);
+    }
+
+    Ok(())
+}
+
+/// The index of the output paying the maker, following the intent in the payload.
+pub(crate) fn payment_index(witness_lock: &WitnessLock) -> usize {
+    let mut buf = [0u8; 4];
+    buf.copy_from_slice(&witness_lock.payload[LIMIT_ORDER_INTENT_SIZE..]);
+    u32::from_le_bytes(buf) as usize
+}
+
+fn load_order_cell<S: ScriptAccess>(
+    script: &S,
+    index: usize,
+    witness_lock: &WitnessLock,
+) -> Result<OrderCell, Error> {
+    let intent = &witness_lock.payload[..LIMIT_ORDER_INTENT_SIZE];
+    let intent_hash = limit_order_intent_hash(intent);
+    let intent = LimitOrderIntent::from_slice(intent)?;
+
+    if script.cell_type_hash(index, Source::Input)? != intent.sell_type_hash {
+        return Err(Error::InvalidOrderCell);
+    }
+    let capacity = script.cell_capacity(index, Source::Input)?;
+    let amount = load_order_amount(script, index, Source::Input, &intent, &intent_hash)?
+        .ok_or(Error::InvalidOrderCell)?;
+    Ok(OrderCell {
+        intent,
+        intent_hash,
+        capacity,
+        amount,
+    })
+}
+
+// returns the amount on sale if the cell data is bound to the intent
+fn load_order_amount<S: ScriptAccess>(
+    script: &S,
+    index: usize,
+    source: Source,
+    intent: &LimitOrderIntent,
+    intent_hash: &[u8; 32],
+) -> Result<Option<u128>, Error> {
+    let data = script.cell_data(index, source)?;
+    match intent.sell_type_hash {
+        Some(_) => {
+            if data.len() != UDT_AMOUNT_SIZE + 32 || &data[UDT_AMOUNT_SIZE..] != intent_hash {
+                return Ok(None);
+            }
+            let mut buf = [0u8; UDT_AMOUNT_SIZE];
+            buf.copy_from_slice(&data[..UDT_AMOUNT_SIZE]);
+            Ok(Some(u128::from_le_bytes(buf)))
+        }
+        None => {
+            if &data[..] != intent_hash {
+                return Ok(None);
+            }
+            Ok(Some(script.cell_capacity(index, source)?.into()))
+        }
+    }
+}
@@ -0,0 +1,23 @@
+mod anyone_can_pay;
+mod claims;
+mod cobuild_otx;
+mod cobuild_sighash_all;
+mod delegation;
+mod fee_limit;
+mod limit_order;
+mod sighash_all_acp;
+mod sighash_single_acp;
+mod sighash_single_udt_acp;
+
+pub(crate) use anyone_can_pay::validate_anyone_can_pay;
+pub(crate) use claims::collect_claims;
+pub(crate) use cobuild_otx::validate_cobuild_otx;
+pub(crate) use cobuild_sighash_all::validate_cobuild_sighash_all;
+pub(crate) use delegation::validate_delegation;
+pub(crate) use fee_limit::validate_fee_limit;
+pub(crate) use limit_order::{check_limit_order_fill, validate_limit_order};
+pub(crate) use sighash_all_acp::validate_sighash_all_anyonecanpay;
+pub(crate) use sighash_single_acp::validate_sighash_single_anyonecanpay;
+pub(crate) use sighash_single_udt_acp::{
+    check_udt_requirements, validate_sighash_single_udt_anyonecanpay,
+};
@@ -0,0 +1,22 @@
+use crate::digest::sighash_all_acp_message;
+use crate::error::Error;
+use crate::helper::verify_pubkey_hash;
+use crate::script::ScriptAccess;
+use crate::types::WitnessLock;
+
+pub(crate) fn validate_sighash_all_anyonecanpay<S: ScriptAccess>(
+    script: &S,
+    index: usize,
+    group_inputs_absolute_indices: &[usize],
+    witness_lock: &WitnessLock,
+    expected_pubkey_hash: &[u8],
+) -> Result<(), Error> {
+    let message =
+        sighash_all_acp_message(script, index, group_inputs_absolute_indices, witness_lock)?;
+    verify_pubkey_hash(
+        script,
+        &message,
+        &witness_lock.signature,
+        expected_pubkey_hash,
+    )
+}
@@ -0,0 +1,73 @@
+use crate::digest::sighash_single_udt_acp_message;
+use crate::error::Error;
+use crate::helper::{load_udt_amount, verify_pubkey_hash};
+use crate::script::{ScriptAccess, Source};
+use crate::types::WitnessLock;
+use crate::validate::claims::Claims;
+
+/// Unlike `SingleAnyoneCanPay`, the signature does not commit to any output. It commits to
+/// the input and to the amounts in the witness payload, which are checked against the sum of
+/// all qualifying outputs, so that an aggregator is free to merge or split them.
+///
+/// The requirement is summed up with the others of the transaction by `collect_claims` and
+/// checked by `check_udt_requirements` once every input of the group is verified.
+pub(crate) fn validate_sighash_single_udt_anyonecanpay<S: ScriptAccess>(
+    script: &S,
+    index: usize,
+    witness_lock: &WitnessLock,
+    expected_pubkey_hash: &[u8],
+) -> Result<(), Error> {
+    let message = sighash_single_udt_acp_message(script, index, witness_lock)?;
+    verify_pubkey_hash(
+        script,
+        &message,
+        &witness_lock.signature,
+        expected_pubkey_hash,
+    )
+}
+
+/// Checks the summed requirements of the transaction against the outputs. The capacity is
+/// required per lock, whatever the UDT, the UDT amount per lock and type.
+///
+/// Only outputs dedicated to the requirements count: outputs paying an anyone-can-pay input
+/// or committed by another signature are left out.
+pub(crate) fn check_udt_requirements<S: ScriptAccess>(
+    script: &S,
+    claims: &Claims,
+) -> Result<(), Error> {
+    let requirements = &claims.requirements;
+    for requirement in requirements {
+        let required_capacity = requirements
+            .iter()
+            .filter(|r| r.lock_hash == requirement.lock_hash)
+            .try_fold(0u64, |sum, r| sum.checked_add(r.capacity))
+            .ok_or(Error::Overflow)?;
+
+        let mut capacity: u64 = 0;
+        let mut udt_amount: u128 = 0;
+        for i in 0..script.outputs_len()? {
+            if script.cell_lock_hash(i, Source::Output)? != requirement.lock_hash
+                || claims.is_claimed(script, i)?
+            {
+                continue;
+            }
+            capacity = capacity
+                .checked_add(script.cell_capacity(i, Source::Output)?)
+                .ok_or(Error::Overflow)?;
+
+            if requirement.type_hash.is_none()
+                || script.cell_type_hash(i, Source::Output)? != requirement.type_hash
+            {
+                continue;
+            }
+            udt_amount = udt_amount
+                .checked_add(load_udt_amount(script, i, Source::Output)?)
+                .ok_or(Error::Overflow)?;
+        }
+
+        if capacity < required_capacity || udt_amount < requirement.udt_amount {
+            return Err(Error::OutputAmountNotEnough);
+        }
+    }
+    Ok(())
+}
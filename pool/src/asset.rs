@@ -0,0 +1,135 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use ckb_types::{
+    bytes::Bytes,
+    packed::{CellOutput, WitnessArgs},
+    prelude::*,
+    H256,
+};
+use otx_sighash_core::{
+    LimitOrderIntent, SighashMode, UdtRequirement, WitnessLock, LIMIT_ORDER_INTENT_SIZE,
+    UDT_AMOUNT_SIZE,
+};
+use otx_sighash_lock_sdk::Psot;
+use serde::{Deserialize, Serialize};
+
+/// CKB, or the UDT with the given type hash.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Asset {
+    Ckb,
+    Udt(H256),
+}
+
+/// What an otx gives away and what it asks for: the assets its inputs hold more of than its
+/// outputs, and the other way round.
+///
+/// Open otxs declare the rest in their payloads: the requirement of a SINGLE|ANYONECANPAY|UDT
+/// signature counts as outputs, and a limit order offers the asset it sells for the asset it
+/// buys, leaving its order cell and remainder out of the balance.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Assets {
+    pub offered: Vec<Asset>,
+    pub wanted: Vec<Asset>,
+}
+
+impl Assets {
+    pub fn of(psot: &Psot) -> Assets {
+        let mut assets = Assets::default();
+        // amounts in the inputs and in the outputs
+        let mut balances: BTreeMap<Asset, (u128, u128)> = BTreeMap::new();
+        let mut remainders = Vec::new();
+        for (i, input) in psot.inputs.iter().enumerate() {
+            let witness_lock = signed_witness_lock(psot, i);
+            match witness_lock.as_deref().map(WitnessLock::from_slice) {
+                Some(Ok(witness_lock)) if witness_lock.mode == SighashMode::LimitOrder => {
+                    let intent = witness_lock
+                        .payload
+                        .get(..LIMIT_ORDER_INTENT_SIZE)
+                        .and_then(|intent| LimitOrderIntent::from_slice(intent).ok());
+                    if let Some(intent) = intent {
+                        assets.offered.push(asset(intent.sell_type_hash));
+                        assets.wanted.push(asset(intent.buy_type_hash));
+                        remainders.push(i);
+                        continue;
+                    }
+                }
+                Some(Ok(witness_lock))
+                    if witness_lock.mode == SighashMode::SingleUdtAnyoneCanPay =>
+                {
+                    if let Ok(requirement) = UdtRequirement::from_slice(witness_lock.payload) {
+                        let mut required = vec![(Asset::Ckb, requirement.capacity as u128)];
+                        required.extend(
+                            requirement
+                                .type_hash
+                                .map(|type_hash| (asset(Some(type_hash)), requirement.udt_amount)),
+                        );
+                        for (asset, amount) in required {
+                            let balance = balances.entry(asset).or_default();
+                            balance.1 = balance.1.saturating_add(amount);
+                        }
+                    }
+                }
+                _ => {}
+            }
+            for (asset, amount) in cell_assets(&input.cell_output, &input.data) {
+                let balance = balances.entry(asset).or_default();
+                balance.0 = balance.0.saturating_add(amount);
+            }
+        }
+        for (i, (output, data)) in psot.tx.outputs_with_data_iter().enumerate() {
+            if remainders.contains(&i) {
+                continue;
+            }
+            for (asset, amount) in cell_assets(&output, &data) {
+                let balance = balances.entry(asset).or_default();
+                balance.1 = balance.1.saturating_add(amount);
+            }
+        }
+
+        for (asset, (input, output)) in balances {
+            match input.cmp(&output) {
+                Ordering::Greater => assets.offered.push(asset),
+                Ordering::Less => assets.wanted.push(asset),
+                Ordering::Equal => {}
+            }
+        }
+        assets.offered.sort();
+        assets.offered.dedup();
+        assets.wanted.sort();
+        assets.wanted.dedup();
+        assets
+    }
+}
+
+// the capacity, and the UDT amount of a typed cell with one
+fn cell_assets(cell_output: &CellOutput, data: &Bytes) -> Vec<(Asset, u128)> {
+    let capacity: u64 = cell_output.capacity().unpack();
+    let mut assets = vec![(Asset::Ckb, capacity as u128)];
+    if let Some(type_script) = cell_output.type_().to_opt() {
+        if data.len() >= UDT_AMOUNT_SIZE {
+            let mut buf = [0u8; UDT_AMOUNT_SIZE];
+            buf.copy_from_slice(&data[..UDT_AMOUNT_SIZE]);
+            let type_hash: H256 = type_script.calc_script_hash().unpack();
+            assets.push((Asset::Udt(type_hash), u128::from_le_bytes(buf)));
+        }
+    }
+    assets
+}
+
+// CKB for an all zero type hash, the UDT otherwise
+fn asset(type_hash: Option<[u8; 32]>) -> Asset {
+    match type_hash {
+        Some(type_hash) => Asset::Udt(H256(type_hash)),
+        None => Asset::Ckb,
+    }
+}
+
+/// The witness lock of the input at `index` when it is signed in an otx mode.
+pub(crate) fn signed_witness_lock(psot: &Psot, index: usize) -> Option<Bytes> {
+    psot.inputs.get(index)?.mode?;
+    let witness = psot.tx.witnesses().get(index)?;
+    let witness_args = WitnessArgs::from_slice(&witness.raw_data()).ok()?;
+    Some(witness_args.lock().to_opt()?.raw_data())
+}
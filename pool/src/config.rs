@@ -0,0 +1,61 @@
+use crate::pool::FeePayer;
+
+use std::path::PathBuf;
+
+use ckb_jsonrpc_types as json;
+use ckb_types::{core::ScriptHashType, packed, prelude::*, H256};
+use otx_sighash_lock_verifier::OtxVerifier;
+use serde::{Deserialize, Serialize};
+
+/// Config file of the `otx-sighash-lock-pool` binary.
+#[derive(Serialize, Deserialize)]
+pub struct Config {
+    /// Address the JSON-RPC server listens on, such as `127.0.0.1:8118`.
+    pub listen: String,
+    /// JSON file of the cell store, created when missing.
+    pub store: PathBuf,
+    pub lock: LockConfig,
+    pub fee: FeeConfig,
+}
+
+/// The deployment of the otx-sighash-lock whose otxs the pool accepts.
+#[derive(Serialize, Deserialize)]
+pub struct LockConfig {
+    pub code_hash: H256,
+    pub hash_type: json::ScriptHashType,
+}
+
+/// The cell of the pool paying the fee of aggregated transactions.
+#[derive(Serialize, Deserialize)]
+pub struct FeeConfig {
+    pub out_point: json::OutPoint,
+    pub cell_deps: Vec<json::CellDep>,
+    /// The witness of the fee input as it will be signed, the signature zeroed.
+    pub witness: json::JsonBytes,
+    /// Shannons per 1000 bytes.
+    pub fee_rate: json::Uint64,
+}
+
+impl Config {
+    pub fn verifier(&self) -> OtxVerifier {
+        OtxVerifier::new(
+            self.lock.code_hash.pack(),
+            ScriptHashType::from(self.lock.hash_type.clone()),
+        )
+    }
+
+    pub fn fee_payer(&self) -> FeePayer {
+        FeePayer {
+            out_point: self.fee.out_point.clone().into(),
+            cell_deps: self
+                .fee
+                .cell_deps
+                .iter()
+                .cloned()
+                .map(packed::CellDep::from)
+                .collect(),
+            witness: self.fee.witness.clone().into_bytes().pack(),
+            fee_rate: self.fee.fee_rate.into(),
+        }
+    }
+}
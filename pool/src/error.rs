@@ -0,0 +1,34 @@
+use otx_sighash_lock_verifier::GroupError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("psot: {0}")]
+    Psot(#[from] otx_sighash_lock_sdk::Error),
+    #[error("aggregator: {0}")]
+    Aggregator(#[from] otx_sighash_lock_aggregator::Error),
+    #[error("otx is not completely signed")]
+    Incomplete,
+    #[error("input {0} is spent or unknown")]
+    UnknownCell(usize),
+    #[error("input {0} does not consume the cell listed in the psot")]
+    CellMismatch(usize),
+    #[error("cell dep {0} is unknown")]
+    UnknownCellDep(usize),
+    #[error("header dep {0} is unknown")]
+    UnknownHeader(usize),
+    #[error("spends an input of otx {0}")]
+    DoubleSpend(u64),
+    #[error("rejected by the lock: {0}")]
+    Rejected(#[from] GroupError),
+    #[error("signature expired at the tip")]
+    Expired,
+    #[error("otx {0} is not in the pool")]
+    OtxNotFound(u64),
+    #[error("the fee cell is spent or unknown")]
+    FeeCellMissing,
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("json: {0}")]
+    Json(#[from] serde_json::Error),
+}
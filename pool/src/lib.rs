@@ -0,0 +1,20 @@
+//! Local pool of otxs of the otx-sighash-lock.
+//!
+//! `OtxPool` accepts complete `Psot`s, verifies them with the rules of the lock against a
+//! `CellStore` standing in for the chain, indexes them by the assets they offer and want,
+//! evicts those whose inputs are spent or whose signatures expired, and lays the selected
+//! ones out in one transaction with the aggregator. `rpc::build_handler` serves the pool
+//! over JSON-RPC, the `otx-sighash-lock-pool` binary over HTTP.
+
+mod asset;
+mod config;
+mod error;
+mod pool;
+pub mod rpc;
+mod store;
+
+pub use asset::{Asset, Assets};
+pub use config::{Config, LockConfig};
+pub use error::Error;
+pub use pool::{FeePayer, OtxPool, PoolAggregate};
+pub use store::CellStore;
@@ -0,0 +1,22 @@
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::sync::{Arc, Mutex};
+
+use jsonrpc_http_server::ServerBuilder;
+use otx_sighash_lock_pool::{rpc, CellStore, Config, OtxPool};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let config_path = env::args()
+        .nth(1)
+        .ok_or("usage: otx-sighash-lock-pool <config.json>")?;
+    let config: Config = serde_json::from_str(&fs::read_to_string(config_path)?)?;
+
+    let store = CellStore::open(&config.store)?;
+    let pool = OtxPool::new(store, config.verifier(), config.fee_payer());
+    let server = ServerBuilder::new(rpc::build_handler(Arc::new(Mutex::new(pool))))
+        .start_http(&config.listen.parse()?)?;
+    println!("otx pool listening on {}", config.listen);
+    server.wait();
+    Ok(())
+}
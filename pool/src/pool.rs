@@ -0,0 +1,334 @@
+use crate::asset::{signed_witness_lock, Asset, Assets};
+use crate::error::Error;
+use crate::store::CellStore;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use ckb_types::{
+    core::{DepType, HeaderView, TransactionView},
+    packed::{self, CellDep, CellInput, OutPoint, OutPointVec},
+    prelude::*,
+};
+use otx_sighash_core::{since::is_expired, WitnessLock};
+use otx_sighash_lock_aggregator::{Aggregator, ConflictReason, FeeCell};
+use otx_sighash_lock_sdk::Psot;
+use otx_sighash_lock_verifier::{OtxVerifier, ResolvedTx};
+
+/// The cell of the pool paying the fee of aggregated transactions.
+pub struct FeePayer {
+    pub out_point: OutPoint,
+    /// Deps unlocking the fee cell.
+    pub cell_deps: Vec<CellDep>,
+    /// Witness of the fee input as it will be signed, the signature zeroed.
+    pub witness: packed::Bytes,
+    /// Shannons per 1000 bytes.
+    pub fee_rate: u64,
+}
+
+/// Transaction laid out from otxs of the pool.
+pub struct PoolAggregate {
+    /// The otxs followed by the fee input, still to be signed, and the change if any.
+    pub psot: Psot,
+    /// Ids of the otxs in the transaction, in their order.
+    pub otxs: Vec<u64>,
+    /// The otxs left out.
+    pub conflicts: Vec<(u64, ConflictReason)>,
+    pub fee: u64,
+}
+
+struct PooledOtx {
+    psot: Psot,
+    assets: Assets,
+    // expiries of the signatures, absolute `since` values
+    expiries: Vec<u64>,
+}
+
+/// Verified otxs waiting to be aggregated, indexed by the assets they offer and want.
+pub struct OtxPool {
+    store: CellStore,
+    verifier: OtxVerifier,
+    fee_payer: FeePayer,
+    otxs: BTreeMap<u64, PooledOtx>,
+    offered: BTreeMap<Asset, BTreeSet<u64>>,
+    wanted: BTreeMap<Asset, BTreeSet<u64>>,
+    next_id: u64,
+}
+
+impl OtxPool {
+    pub fn new(store: CellStore, verifier: OtxVerifier, fee_payer: FeePayer) -> Self {
+        OtxPool {
+            store,
+            verifier,
+            fee_payer,
+            otxs: BTreeMap::new(),
+            offered: BTreeMap::new(),
+            wanted: BTreeMap::new(),
+            next_id: 0,
+        }
+    }
+
+    pub fn store(&self) -> &CellStore {
+        &self.store
+    }
+
+    pub fn store_mut(&mut self) -> &mut CellStore {
+        &mut self.store
+    }
+
+    /// Adds a complete otx, returning its id.
+    ///
+    /// The inputs must consume live cells of the store, none of them spent by another otx
+    /// of the pool, and the lock groups must pass the checks of the lock that do not wait
+    /// for a counterparty: the payments of SINGLE|ANYONECANPAY|UDT signatures and the fills
+    /// of limit orders are left to the aggregation.
+    pub fn submit(&mut self, psot: Psot) -> Result<u64, Error> {
+        psot.validate()?;
+        if !psot.is_complete() {
+            return Err(Error::Incomplete);
+        }
+        for (i, (input, psot_input)) in psot.tx.inputs().into_iter().zip(&psot.inputs).enumerate() {
+            let out_point = input.previous_output();
+            let (cell_output, data) = self
+                .store
+                .get_cell(&out_point)
+                .ok_or(Error::UnknownCell(i))?;
+            if cell_output.as_slice() != psot_input.cell_output.as_slice()
+                || data != &psot_input.data
+            {
+                return Err(Error::CellMismatch(i));
+            }
+            if let Some(id) = self.spender(&out_point) {
+                return Err(Error::DoubleSpend(id));
+            }
+        }
+        self.verifier.verify_open(&self.resolve(&psot.tx)?)?;
+
+        let expiries = expiries(&psot);
+        if self.is_expired(&expiries) {
+            return Err(Error::Expired);
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        let assets = Assets::of(&psot);
+        for asset in assets.offered.iter() {
+            self.offered.entry(asset.clone()).or_default().insert(id);
+        }
+        for asset in assets.wanted.iter() {
+            self.wanted.entry(asset.clone()).or_default().insert(id);
+        }
+        self.otxs.insert(
+            id,
+            PooledOtx {
+                psot,
+                assets,
+                expiries,
+            },
+        );
+        Ok(id)
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Psot> {
+        self.otxs.get(&id).map(|otx| &otx.psot)
+    }
+
+    /// Ids of the otxs offering `offered` and wanting `wanted`, either left out to match
+    /// any otx.
+    pub fn find(&self, offered: Option<&Asset>, wanted: Option<&Asset>) -> Vec<u64> {
+        let lookup = |index: &BTreeMap<Asset, BTreeSet<u64>>, asset: &Asset| {
+            index.get(asset).cloned().unwrap_or_default()
+        };
+        let mut ids: BTreeSet<u64> = self.otxs.keys().copied().collect();
+        if let Some(offered) = offered {
+            ids = &ids & &lookup(&self.offered, offered);
+        }
+        if let Some(wanted) = wanted {
+            ids = &ids & &lookup(&self.wanted, wanted);
+        }
+        ids.into_iter().collect()
+    }
+
+    pub fn remove(&mut self, id: u64) -> Result<Psot, Error> {
+        let otx = self.otxs.remove(&id).ok_or(Error::OtxNotFound(id))?;
+        for asset in otx.assets.offered.iter() {
+            remove_from_index(&mut self.offered, asset, id);
+        }
+        for asset in otx.assets.wanted.iter() {
+            remove_from_index(&mut self.wanted, asset, id);
+        }
+        Ok(otx.psot)
+    }
+
+    /// Applies a transaction committed to the chain, returning the ids of the otxs evicted.
+    pub fn commit_transaction(&mut self, tx: &TransactionView) -> Result<Vec<u64>, Error> {
+        self.store.apply(tx);
+        self.store.save()?;
+        self.evict()
+    }
+
+    /// Moves the tip to `header`, returning the ids of the otxs evicted.
+    pub fn set_tip(&mut self, header: HeaderView) -> Result<Vec<u64>, Error> {
+        self.store.set_tip(header);
+        self.store.save()?;
+        self.evict()
+    }
+
+    /// Lays the otxs with the given ids out in one transaction paid by the fee cell, all of
+    /// the otxs of the pool when `ids` is empty.
+    pub fn aggregate(&self, ids: &[u64]) -> Result<PoolAggregate, Error> {
+        let ids: Vec<u64> = if ids.is_empty() {
+            self.otxs.keys().copied().collect()
+        } else {
+            ids.to_vec()
+        };
+
+        let (cell_output, data) = self
+            .store
+            .get_cell(&self.fee_payer.out_point)
+            .cloned()
+            .ok_or(Error::FeeCellMissing)?;
+        let fee_cell = FeeCell {
+            input: CellInput::new(self.fee_payer.out_point.clone(), 0),
+            cell_output,
+            data,
+            cell_deps: self.fee_payer.cell_deps.clone(),
+            witness: self.fee_payer.witness.clone(),
+        };
+        let mut aggregator = Aggregator::new(fee_cell, self.fee_payer.fee_rate);
+        for id in ids.iter() {
+            let otx = self.otxs.get(id).ok_or(Error::OtxNotFound(*id))?;
+            aggregator.add_otx(otx.psot.clone());
+        }
+
+        let aggregate = aggregator.aggregate()?;
+        Ok(PoolAggregate {
+            psot: aggregate.psot,
+            otxs: aggregate.otxs.iter().map(|i| ids[*i]).collect(),
+            conflicts: aggregate
+                .conflicts
+                .into_iter()
+                .map(|conflict| (ids[conflict.otx], conflict.reason))
+                .collect(),
+            fee: aggregate.fee,
+        })
+    }
+
+    // drops the otxs spending cells no longer live or whose signatures expired
+    fn evict(&mut self) -> Result<Vec<u64>, Error> {
+        let evicted: Vec<u64> = self
+            .otxs
+            .iter()
+            .filter(|(_, otx)| {
+                otx.psot
+                    .tx
+                    .inputs()
+                    .into_iter()
+                    .any(|input| !self.store.is_live(&input.previous_output()))
+                    || self.is_expired(&otx.expiries)
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        for id in evicted.iter() {
+            self.remove(*id)?;
+        }
+        Ok(evicted)
+    }
+
+    fn is_expired(&self, expiries: &[u64]) -> bool {
+        let tip = match self.store.tip() {
+            Some(tip) => tip,
+            None => return false,
+        };
+        let number: u64 = tip.data().raw().number().unpack();
+        let epoch: u64 = tip.data().raw().epoch().unpack();
+        expiries
+            .iter()
+            .any(|expiry| is_expired(*expiry, number, epoch).unwrap_or(true))
+    }
+
+    fn spender(&self, out_point: &OutPoint) -> Option<u64> {
+        self.otxs
+            .iter()
+            .find(|(_, otx)| {
+                otx.psot
+                    .tx
+                    .inputs()
+                    .into_iter()
+                    .any(|input| input.previous_output().as_slice() == out_point.as_slice())
+            })
+            .map(|(id, _)| *id)
+    }
+
+    fn resolve(&self, tx: &TransactionView) -> Result<ResolvedTx, Error> {
+        let mut inputs = Vec::new();
+        for (i, input) in tx.inputs().into_iter().enumerate() {
+            let cell = self.store.get_cell(&input.previous_output());
+            inputs.push(cell.cloned().ok_or(Error::UnknownCell(i))?);
+        }
+
+        let dep_group: packed::Byte = DepType::DepGroup.into();
+        let mut cell_deps = Vec::new();
+        for (i, cell_dep) in tx.cell_deps().into_iter().enumerate() {
+            let (cell_output, data) = self
+                .store
+                .get_cell(&cell_dep.out_point())
+                .cloned()
+                .ok_or(Error::UnknownCellDep(i))?;
+            if cell_dep.dep_type().as_slice() != dep_group.as_slice() {
+                cell_deps.push((cell_output, data));
+                continue;
+            }
+            // the members of a dep group take its place
+            let out_points =
+                OutPointVec::from_slice(&data).map_err(|_| Error::UnknownCellDep(i))?;
+            for out_point in out_points {
+                let cell = self.store.get_cell(&out_point);
+                cell_deps.push(cell.cloned().ok_or(Error::UnknownCellDep(i))?);
+            }
+        }
+
+        let mut header_deps = Vec::new();
+        for (i, hash) in tx.header_deps().into_iter().enumerate() {
+            let header = self.store.get_header(&hash);
+            header_deps.push(header.cloned().ok_or(Error::UnknownHeader(i))?);
+        }
+
+        Ok(ResolvedTx {
+            tx: tx.clone(),
+            inputs,
+            cell_deps,
+            header_deps,
+        })
+    }
+}
+
+// the expiries of the signed witness locks, and of their delegation certificates
+fn expiries(psot: &Psot) -> Vec<u64> {
+    let mut expiries = Vec::new();
+    for i in 0..psot.inputs.len() {
+        let witness_lock = match signed_witness_lock(psot, i) {
+            Some(witness_lock) => witness_lock,
+            None => continue,
+        };
+        if let Ok(witness_lock) = WitnessLock::from_slice(&witness_lock) {
+            expiries.extend(witness_lock.extension.expiry);
+            expiries.extend(
+                witness_lock
+                    .extension
+                    .delegation
+                    .as_ref()
+                    .map(|delegation| delegation.expiry),
+            );
+        }
+    }
+    expiries
+}
+
+fn remove_from_index(index: &mut BTreeMap<Asset, BTreeSet<u64>>, asset: &Asset, id: u64) {
+    if let Some(ids) = index.get_mut(asset) {
+        ids.remove(&id);
+        if ids.is_empty() {
+            index.remove(asset);
+        }
+    }
+}
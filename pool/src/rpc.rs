@@ -0,0 +1,124 @@
+//! JSON-RPC methods of the pool.
+//!
+//! - `submit_otx(psot)`: adds a complete PSOT in its JSON form, returns its id
+//! - `get_otx(id)`: the PSOT with the given id, or null
+//! - `find_otxs(offered, wanted)`: ids of the otxs offering and wanting the given assets,
+//!   `"ckb"` or `{"udt": type_hash}`, null to match any
+//! - `remove_otx(id)`
+//! - `aggregate(ids)`: lays the given otxs out in one transaction, all of them when `ids` is
+//!   empty, returning `{psot, otxs, conflicts, fee}`
+//! - `commit_transaction(tx)`: applies a committed transaction to the cell store, returns
+//!   the ids of the otxs evicted
+//! - `set_tip(header)`: moves the tip of the cell store, returns the ids of the otxs evicted
+//!
+//! Otxs rejected by the lock fail with `data` carrying the lock script hash and the error
+//! code of the contract.
+
+use crate::asset::Asset;
+use crate::error::Error;
+use crate::pool::OtxPool;
+
+use std::sync::{Arc, Mutex};
+
+use ckb_jsonrpc_types as json;
+use ckb_types::{packed, prelude::*, H256};
+use jsonrpc_core::{ErrorCode, IoHandler, Params, Value};
+use otx_sighash_lock_sdk::Psot;
+use serde_json::json;
+
+pub fn build_handler(pool: Arc<Mutex<OtxPool>>) -> IoHandler {
+    let mut io = IoHandler::new();
+
+    let p = Arc::clone(&pool);
+    io.add_sync_method("submit_otx", move |params: Params| {
+        let (psot,): (Value,) = params.parse()?;
+        let psot = Psot::from_json(&psot.to_string()).map_err(|err| rpc_error(err.into()))?;
+        let id = p.lock().unwrap().submit(psot).map_err(rpc_error)?;
+        Ok(json!(json::Uint64::from(id)))
+    });
+
+    let p = Arc::clone(&pool);
+    io.add_sync_method("get_otx", move |params: Params| {
+        let (id,): (json::Uint64,) = params.parse()?;
+        match p.lock().unwrap().get(id.into()) {
+            Some(psot) => psot_value(psot),
+            None => Ok(Value::Null),
+        }
+    });
+
+    let p = Arc::clone(&pool);
+    io.add_sync_method("find_otxs", move |params: Params| {
+        let (offered, wanted): (Option<Asset>, Option<Asset>) = params.parse()?;
+        let ids = p.lock().unwrap().find(offered.as_ref(), wanted.as_ref());
+        Ok(json!(ids_value(&ids)))
+    });
+
+    let p = Arc::clone(&pool);
+    io.add_sync_method("remove_otx", move |params: Params| {
+        let (id,): (json::Uint64,) = params.parse()?;
+        p.lock().unwrap().remove(id.into()).map_err(rpc_error)?;
+        Ok(Value::Null)
+    });
+
+    let p = Arc::clone(&pool);
+    io.add_sync_method("aggregate", move |params: Params| {
+        let (ids,): (Vec<json::Uint64>,) = params.parse()?;
+        let ids: Vec<u64> = ids.into_iter().map(Into::into).collect();
+        let aggregate = p.lock().unwrap().aggregate(&ids).map_err(rpc_error)?;
+        let conflicts: Vec<Value> = aggregate
+            .conflicts
+            .iter()
+            .map(|(id, reason)| json!({"otx": json::Uint64::from(*id), "reason": reason.to_string()}))
+            .collect();
+        Ok(json!({
+            "psot": psot_value(&aggregate.psot)?,
+            "otxs": ids_value(&aggregate.otxs),
+            "conflicts": conflicts,
+            "fee": json::Uint64::from(aggregate.fee),
+        }))
+    });
+
+    let p = Arc::clone(&pool);
+    io.add_sync_method("commit_transaction", move |params: Params| {
+        let (tx,): (json::Transaction,) = params.parse()?;
+        let tx = packed::Transaction::from(tx).into_view();
+        let evicted = p
+            .lock()
+            .unwrap()
+            .commit_transaction(&tx)
+            .map_err(rpc_error)?;
+        Ok(json!(ids_value(&evicted)))
+    });
+
+    io.add_sync_method("set_tip", move |params: Params| {
+        let (header,): (json::Header,) = params.parse()?;
+        let header = packed::Header::from(header).into_view();
+        let evicted = pool.lock().unwrap().set_tip(header).map_err(rpc_error)?;
+        Ok(json!(ids_value(&evicted)))
+    });
+
+    io
+}
+
+fn psot_value(psot: &Psot) -> Result<Value, jsonrpc_core::Error> {
+    serde_json::from_str(&psot.to_json()).map_err(|err| rpc_error(err.into()))
+}
+
+fn ids_value(ids: &[u64]) -> Vec<json::Uint64> {
+    ids.iter().copied().map(json::Uint64::from).collect()
+}
+
+fn rpc_error(err: Error) -> jsonrpc_core::Error {
+    let data = match &err {
+        Error::Rejected(group) => {
+            let script_hash: H256 = group.script_hash.unpack();
+            Some(json!({"script_hash": script_hash, "error_code": group.error.code()}))
+        }
+        _ => None,
+    };
+    jsonrpc_core::Error {
+        code: ErrorCode::ServerError(-1),
+        message: err.to_string(),
+        data,
+    }
+}
@@ -0,0 +1,139 @@
+use crate::error::Error;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ckb_jsonrpc_types as json;
+use ckb_types::{
+    bytes::Bytes,
+    core::{HeaderView, TransactionView},
+    packed::{self, CellOutput, OutPoint},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+/// Live cells and known headers standing in for the chain, kept in memory or in a JSON
+/// file.
+#[derive(Default)]
+pub struct CellStore {
+    // keyed by the encoded out point
+    cells: BTreeMap<Bytes, (CellOutput, Bytes)>,
+    headers: BTreeMap<Bytes, HeaderView>,
+    tip: Option<HeaderView>,
+    path: Option<PathBuf>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct StoreFile {
+    cells: Vec<LiveCell>,
+    headers: Vec<json::Header>,
+    tip: Option<json::Header>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LiveCell {
+    out_point: json::OutPoint,
+    output: json::CellOutput,
+    data: json::JsonBytes,
+}
+
+impl CellStore {
+    /// Opens the store saved at `path`, or an empty one if there is no file yet. The store
+    /// is saved back to `path` by `save`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<CellStore, Error> {
+        let path = path.as_ref();
+        let file: StoreFile = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(path)?)?
+        } else {
+            StoreFile::default()
+        };
+
+        let mut store = CellStore {
+            path: Some(path.to_path_buf()),
+            ..Default::default()
+        };
+        for cell in file.cells {
+            store.insert_cell(
+                cell.out_point.into(),
+                cell.output.into(),
+                cell.data.into_bytes(),
+            );
+        }
+        for header in file.headers {
+            store.insert_header(packed::Header::from(header).into_view());
+        }
+        store.tip = file
+            .tip
+            .map(|header| packed::Header::from(header).into_view());
+        Ok(store)
+    }
+
+    /// Writes the store to the file it was opened from, if any.
+    pub fn save(&self) -> Result<(), Error> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let file = StoreFile {
+            cells: self
+                .cells
+                .iter()
+                .map(|(out_point, (output, data))| LiveCell {
+                    out_point: OutPoint::new_unchecked(out_point.clone()).into(),
+                    output: output.clone().into(),
+                    data: json::JsonBytes::from_bytes(data.clone()),
+                })
+                .collect(),
+            headers: self
+                .headers
+                .values()
+                .map(|header| header.data().into())
+                .collect(),
+            tip: self.tip.as_ref().map(|header| header.data().into()),
+        };
+        fs::write(path, serde_json::to_string_pretty(&file)?)?;
+        Ok(())
+    }
+
+    pub fn insert_cell(&mut self, out_point: OutPoint, cell_output: CellOutput, data: Bytes) {
+        self.cells.insert(out_point.as_bytes(), (cell_output, data));
+    }
+
+    pub fn get_cell(&self, out_point: &OutPoint) -> Option<&(CellOutput, Bytes)> {
+        self.cells.get(&out_point.as_bytes())
+    }
+
+    pub fn is_live(&self, out_point: &OutPoint) -> bool {
+        self.cells.contains_key(&out_point.as_bytes())
+    }
+
+    pub fn insert_header(&mut self, header: HeaderView) {
+        self.headers.insert(header.hash().as_bytes(), header);
+    }
+
+    pub fn get_header(&self, hash: &packed::Byte32) -> Option<&HeaderView> {
+        self.headers.get(&hash.as_bytes())
+    }
+
+    /// The latest header, which signature expiries are checked against.
+    pub fn tip(&self) -> Option<&HeaderView> {
+        self.tip.as_ref()
+    }
+
+    pub fn set_tip(&mut self, header: HeaderView) {
+        self.insert_header(header.clone());
+        self.tip = Some(header);
+    }
+
+    /// Spends the inputs of `tx` and adds its outputs.
+    pub fn apply(&mut self, tx: &TransactionView) {
+        for input in tx.inputs() {
+            self.cells.remove(&input.previous_output().as_bytes());
+        }
+        for (i, (output, data)) in tx.outputs_with_data_iter().enumerate() {
+            let out_point = OutPoint::new(tx.hash(), i as u32);
+            self.insert_cell(out_point, output, data);
+        }
+    }
+}
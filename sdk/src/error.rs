@@ -0,0 +1,54 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("input {0} is out of bound")]
+    InputOutOfBound(usize),
+    #[error("output {0} is out of bound")]
+    OutputOutOfBound(usize),
+    #[error("witness {0} is not a WitnessArgs")]
+    InvalidWitness(usize),
+    #[error("witness lock: {0:?}")]
+    WitnessLock(otx_sighash_core::Error),
+    #[error("key backend: {0}")]
+    Key(Box<dyn std::error::Error + Send + Sync>),
+    #[error("ALL|ANYONECANPAY signs the witnesses of the lock group, whose inputs are not set")]
+    NoGroupInputs,
+    #[error("input {0} is not signed in an otx mode")]
+    NoSighashMode(usize),
+    #[error("unsupported psot version {0}")]
+    UnsupportedPsotVersion(u8),
+    #[error("invalid psot encoding")]
+    InvalidPsot,
+    #[error("psot lists {0} inputs, the transaction has {1}")]
+    InputCountMismatch(usize, usize),
+    #[error("witness lock {0} is not in the mode of its input")]
+    ModeMismatch(usize),
+    #[error("signature status of input {0} does not match its witness lock")]
+    SignatureStatus(usize),
+    #[error("signature of input {0} does not hold in the merged transaction: {1}")]
+    MergeConflict(usize, MergeConflictKind),
+    #[error("json: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Why a signature would not hold in a merged transaction.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeConflictKind {
+    #[error("ALL|ANYONECANPAY without an otx scope signs all outputs")]
+    AllOutputs,
+    #[error("the output at the index of the input would not be the signed one")]
+    Unaligned,
+    #[error("the witnesses of the first otx go beyond its inputs")]
+    ExtraWitnesses,
+    #[error("the committed cell deps would change")]
+    CellDepsChanged,
+    #[error("the committed header deps would change")]
+    HeaderDepsChanged,
+}
+
+impl From<otx_sighash_core::Error> for Error {
+    fn from(err: otx_sighash_core::Error) -> Self {
+        Error::WitnessLock(err)
+    }
+}
@@ -0,0 +1,101 @@
+use otx_sighash_core::{
+    Delegation, EXTENSION_CELL_DEPS, EXTENSION_DELEGATION, EXTENSION_EXPIRY, EXTENSION_FEE_LIMIT,
+    EXTENSION_HEADER_DEPS, EXTENSION_OTX_SCOPE, EXTENSION_READABLE, EXTENSION_RECOVERY,
+};
+
+/// Builds the witness extension `flags (1 byte) | fields`, writing the fields in the order
+/// of their flag bits. Without any flag the extension is empty.
+#[derive(Default)]
+pub struct ExtensionBuilder {
+    flags: u8,
+    expiry: Option<u64>,
+    otx_scope: Option<[u32; 4]>,
+    delegation: Option<Vec<u8>>,
+    max_fee: Option<u64>,
+}
+
+impl ExtensionBuilder {
+    /// Rejects the signature once a header dep goes beyond `expiry`, an absolute `since`.
+    pub fn expiry(mut self, expiry: u64) -> Self {
+        self.flags |= EXTENSION_EXPIRY;
+        self.expiry = Some(expiry);
+        self
+    }
+
+    pub fn commit_cell_deps(mut self) -> Self {
+        self.flags |= EXTENSION_CELL_DEPS;
+        self
+    }
+
+    pub fn commit_header_deps(mut self) -> Self {
+        self.flags |= EXTENSION_HEADER_DEPS;
+        self
+    }
+
+    /// Scopes an ALL|ANYONECANPAY otx to a segment of the merged transaction. The starts
+    /// are not signed, the batcher may move them.
+    pub fn otx_scope(
+        mut self,
+        input_start: u32,
+        input_count: u32,
+        output_start: u32,
+        output_count: u32,
+    ) -> Self {
+        self.flags |= EXTENSION_OTX_SCOPE;
+        self.otx_scope = Some([input_start, input_count, output_start, output_count]);
+        self
+    }
+
+    /// Signs with a session key, see `OtxSigner::sign_delegation`.
+    pub fn delegation(mut self, delegation: &Delegation) -> Self {
+        let mut certificate = delegation.session_pubkey_hash.to_vec();
+        certificate.push(delegation.allowed_modes);
+        certificate.extend_from_slice(&delegation.expiry.to_le_bytes());
+        certificate.extend_from_slice(&delegation.max_capacity_out.to_le_bytes());
+        certificate.extend_from_slice(&delegation.owner_signature);
+        self.flags |= EXTENSION_DELEGATION;
+        self.delegation = Some(certificate);
+        self
+    }
+
+    /// Signs with the recovery key of the lock args.
+    pub fn recovery(mut self) -> Self {
+        self.flags |= EXTENSION_RECOVERY;
+        self
+    }
+
+    /// Bounds the shannons the otx may lose to the fee.
+    pub fn max_fee(mut self, max_fee: u64) -> Self {
+        self.flags |= EXTENSION_FEE_LIMIT;
+        self.max_fee = Some(max_fee);
+        self
+    }
+
+    /// Signs a preimage a generic wallet can display, SINGLE modes only.
+    pub fn readable(mut self) -> Self {
+        self.flags |= EXTENSION_READABLE;
+        self
+    }
+
+    pub fn build(self) -> Vec<u8> {
+        if self.flags == 0 {
+            return Vec::new();
+        }
+        let mut extension = vec![self.flags];
+        if let Some(expiry) = self.expiry {
+            extension.extend_from_slice(&expiry.to_le_bytes());
+        }
+        if let Some(scope) = self.otx_scope {
+            for field in scope {
+                extension.extend_from_slice(&field.to_le_bytes());
+            }
+        }
+        if let Some(certificate) = self.delegation {
+            extension.extend_from_slice(&certificate);
+        }
+        if let Some(max_fee) = self.max_fee {
+            extension.extend_from_slice(&max_fee.to_le_bytes());
+        }
+        extension
+    }
+}
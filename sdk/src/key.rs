@@ -0,0 +1,41 @@
+use ckb_crypto::secp::Privkey;
+use ckb_types::H256;
+use otx_sighash_core::SIGHASH_ALL_SIGNATURE_SIZE;
+
+/// A key that signs messages, kept in memory, in a hardware wallet or behind a remote
+/// service.
+pub trait KeyBackend {
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Signs the 32-byte message, returning the compact recoverable signature
+    /// `r (32 bytes) | s (32 bytes) | recovery id (1 byte)` with a low `s`.
+    fn sign_recoverable(
+        &self,
+        message: &[u8; 32],
+    ) -> Result<[u8; SIGHASH_ALL_SIGNATURE_SIZE], Self::Error>;
+}
+
+impl KeyBackend for Privkey {
+    type Error = ckb_crypto::secp::Error;
+
+    fn sign_recoverable(
+        &self,
+        message: &[u8; 32],
+    ) -> Result<[u8; SIGHASH_ALL_SIGNATURE_SIZE], Self::Error> {
+        let signature = Privkey::sign_recoverable(self, &H256::from(*message))?;
+        let mut serialized = [0u8; SIGHASH_ALL_SIGNATURE_SIZE];
+        serialized.copy_from_slice(&signature.serialize());
+        Ok(serialized)
+    }
+}
+
+impl<K: KeyBackend + ?Sized> KeyBackend for &K {
+    type Error = K::Error;
+
+    fn sign_recoverable(
+        &self,
+        message: &[u8; 32],
+    ) -> Result<[u8; SIGHASH_ALL_SIGNATURE_SIZE], Self::Error> {
+        (**self).sign_recoverable(message)
+    }
+}
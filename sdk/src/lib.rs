@@ -0,0 +1,33 @@
+//! Off-chain signing for the otx-sighash-lock.
+//!
+//! `OtxSigner` signs the witness lock of one input in a given mode, building the message
+//! with `otx-sighash-core` exactly as the contract does, and keeps the other fields of the
+//! witness. Keys are provided by a `KeyBackend`, implemented for in-memory `Privkey`s.
+//!
+//! `signing_preimage` keeps the fields a signature covers, `explain_preimage` decodes them
+//! and `diff_preimages` tells which of them changed between two transactions.
+//!
+//! A `Psot` carries a partially signed otx between the maker, the aggregator and the
+//! co-signers, with the cells its inputs consume and the signing status of each of them.
+
+mod error;
+mod extension;
+mod key;
+mod preimage;
+mod psot;
+mod reader;
+mod signer;
+
+pub use error::{Error, MergeConflictKind};
+pub use extension::ExtensionBuilder;
+pub use key::KeyBackend;
+pub use preimage::{
+    diff_preimages, explain_preimage, signing_preimage, FieldDiff, PreimageComponent,
+};
+pub use psot::{Psot, PsotInput, PSOT_VERSION};
+pub use reader::TxReader;
+pub use signer::OtxSigner;
+
+pub use otx_sighash_core::{
+    Delegation, LockArgs, Preimage, SighashMode, SIGHASH_ALL_SIGNATURE_SIZE,
+};
@@ -0,0 +1,265 @@
+use crate::error::Error;
+use crate::reader::TxReader;
+use crate::signer::{set_witness_lock, witness_lock};
+
+use ckb_types::{
+    core::TransactionView,
+    packed::{self, Byte32Vec, BytesVec, CellDepVec, CellInput, CellOutput, CellOutputVec, Script},
+    prelude::*,
+};
+use otx_sighash_core::{Preimage, SighashMode, WitnessLock, SIGHASH_ALL_SIGNATURE_SIZE};
+
+/// A part of a signing preimage decoded into `name: value` fields, such as the
+/// `previous_output` of a `CellInput`. Parts holding a single value, such as lengths, have
+/// one field with an empty name.
+pub struct PreimageComponent {
+    pub name: String,
+    pub fields: Vec<(String, String)>,
+}
+
+/// A field that differs between two preimages, `None` on the side lacking it.
+#[derive(Debug, PartialEq, Eq)]
+pub struct FieldDiff {
+    pub field: String,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+/// The preimage signed by the witness lock of the input at `input_index`, with its mode
+/// replaced by `mode` when given. Without a witness lock, the input is taken as unsigned in
+/// `mode`, with neither payload nor extension.
+pub fn signing_preimage(
+    tx: &TransactionView,
+    input_index: usize,
+    group_inputs: &[usize],
+    mode: Option<SighashMode>,
+) -> Result<Preimage, Error> {
+    if input_index >= tx.inputs().len() {
+        return Err(Error::InputOutOfBound(input_index));
+    }
+    let mut lock = match (witness_lock(tx, input_index)?, mode) {
+        (Some(lock), _) => lock.to_vec(),
+        (None, Some(_)) => vec![0u8; 1 + SIGHASH_ALL_SIGNATURE_SIZE],
+        (None, None) => return Err(Error::NoSighashMode(input_index)),
+    };
+    if let (Some(mode), Some(byte)) = (mode, lock.first_mut()) {
+        *byte = mode as u8;
+    }
+    let tx = set_witness_lock(tx, input_index, &lock)?;
+
+    let witness_lock = WitnessLock::from_slice(&lock)?;
+    otx_sighash_core::signing_preimage(&TxReader(&tx), input_index, group_inputs, &witness_lock)
+}
+
+/// Decodes each part of `preimage` in the order it is hashed, followed by the digest, the
+/// prefixed digest and the signed message.
+pub fn explain_preimage(preimage: &Preimage) -> Vec<PreimageComponent> {
+    let mut components: Vec<PreimageComponent> = preimage
+        .parts
+        .iter()
+        .map(|part| PreimageComponent {
+            name: part.name.clone(),
+            fields: decode_part(&part.name, &part.data),
+        })
+        .collect();
+
+    // the prefix is text, followed by the raw digest unless it is readable
+    let prefixed = match preimage.prefixed.strip_suffix(&preimage.digest[..]) {
+        Some(prefix) => format!(
+            "{:?} | {}",
+            String::from_utf8_lossy(prefix),
+            hex(&preimage.digest)
+        ),
+        None => format!("{:?}", String::from_utf8_lossy(&preimage.prefixed)),
+    };
+    for (name, value) in [
+        ("digest", hex(&preimage.digest)),
+        ("prefixed", prefixed),
+        ("message", hex(&preimage.message)),
+    ] {
+        components.push(PreimageComponent {
+            name: name.to_string(),
+            fields: vec![(String::new(), value)],
+        });
+    }
+    components
+}
+
+/// Lists the fields of `left` that differ in `right`, in the order they are hashed, then
+/// the fields only `right` has.
+pub fn diff_preimages(left: &Preimage, right: &Preimage) -> Vec<FieldDiff> {
+    let left = flatten(explain_preimage(left));
+    let right = flatten(explain_preimage(right));
+    let find = |fields: &[(String, String)], name: &str| {
+        fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.clone())
+    };
+
+    let mut diffs = Vec::new();
+    for (field, value) in &left {
+        let other = find(&right, field);
+        if other.as_ref() != Some(value) {
+            diffs.push(FieldDiff {
+                field: field.clone(),
+                left: Some(value.clone()),
+                right: other,
+            });
+        }
+    }
+    for (field, value) in &right {
+        if find(&left, field).is_none() {
+            diffs.push(FieldDiff {
+                field: field.clone(),
+                left: None,
+                right: Some(value.clone()),
+            });
+        }
+    }
+    diffs
+}
+
+// `component.field` for every field
+fn flatten(components: Vec<PreimageComponent>) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+    for component in components {
+        for (name, value) in component.fields {
+            let field = if name.is_empty() {
+                component.name.clone()
+            } else {
+                format!("{}.{}", component.name, name)
+            };
+            fields.push((field, value));
+        }
+    }
+    fields
+}
+
+// the parts are named after the transaction fields they hold, see `Preimage`, parts that do
+// not decode are shown in hex
+fn decode_part(name: &str, data: &[u8]) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+    let decoded = if name.ends_with(" length") || name.ends_with(" count") {
+        let mut number = [0u8; 8];
+        let decoded = data.len() == number.len();
+        if decoded {
+            number.copy_from_slice(data);
+            fields.push((String::new(), u64::from_le_bytes(number).to_string()));
+        }
+        decoded
+    } else if name == "witness" || name.starts_with("witness ") {
+        packed::WitnessArgs::from_slice(data)
+            .map(|witness| {
+                let field = |bytes: packed::BytesOpt| match bytes.to_opt() {
+                    Some(bytes) => hex(&bytes.raw_data()),
+                    None => "none".to_string(),
+                };
+                fields.push(("lock".to_string(), field(witness.lock())));
+                fields.push(("input_type".to_string(), field(witness.input_type())));
+                fields.push(("output_type".to_string(), field(witness.output_type())));
+            })
+            .is_ok()
+    } else {
+        match name {
+            "input" => CellInput::from_slice(data)
+                .map(|input| {
+                    let since: u64 = input.since().unpack();
+                    fields.push(("since".to_string(), format!("{:#x}", since)));
+                    push_out_point(&mut fields, "previous_output", &input.previous_output());
+                })
+                .is_ok(),
+            "output" => CellOutput::from_slice(data)
+                .map(|output| push_output(&mut fields, "", &output))
+                .is_ok(),
+            "outputs" => CellOutputVec::from_slice(data)
+                .map(|outputs| {
+                    for (i, output) in outputs.into_iter().enumerate() {
+                        push_output(&mut fields, &format!("{}.", i), &output);
+                    }
+                })
+                .is_ok(),
+            "output data" => packed::Bytes::from_slice(data)
+                .map(|data| fields.push((String::new(), hex(&data.raw_data()))))
+                .is_ok(),
+            "outputs data" => BytesVec::from_slice(data)
+                .map(|outputs_data| {
+                    for (i, data) in outputs_data.into_iter().enumerate() {
+                        fields.push((i.to_string(), hex(&data.raw_data())));
+                    }
+                })
+                .is_ok(),
+            "cell deps" => CellDepVec::from_slice(data)
+                .map(|cell_deps| {
+                    for (i, cell_dep) in cell_deps.into_iter().enumerate() {
+                        push_out_point(
+                            &mut fields,
+                            &format!("{}.out_point", i),
+                            &cell_dep.out_point(),
+                        );
+                        let dep_type = match cell_dep.dep_type().as_slice()[0] {
+                            0 => "code".to_string(),
+                            1 => "dep_group".to_string(),
+                            dep_type => format!("{:#04x}", dep_type),
+                        };
+                        fields.push((format!("{}.dep_type", i), dep_type));
+                    }
+                })
+                .is_ok(),
+            "header deps" => Byte32Vec::from_slice(data)
+                .map(|header_deps| {
+                    for (i, hash) in header_deps.into_iter().enumerate() {
+                        fields.push((i.to_string(), hex(hash.as_slice())));
+                    }
+                })
+                .is_ok(),
+            _ => false,
+        }
+    };
+    if !decoded {
+        fields = vec![(String::new(), hex(data))];
+    }
+    fields
+}
+
+fn push_out_point(fields: &mut Vec<(String, String)>, name: &str, out_point: &packed::OutPoint) {
+    fields.push((
+        format!("{}.tx_hash", name),
+        hex(out_point.tx_hash().as_slice()),
+    ));
+    let index: u32 = out_point.index().unpack();
+    fields.push((format!("{}.index", name), index.to_string()));
+}
+
+fn push_output(fields: &mut Vec<(String, String)>, prefix: &str, output: &CellOutput) {
+    let capacity: u64 = output.capacity().unpack();
+    fields.push((format!("{}capacity", prefix), capacity.to_string()));
+    push_script(fields, &format!("{}lock", prefix), &output.lock());
+    match output.type_().to_opt() {
+        Some(type_script) => push_script(fields, &format!("{}type", prefix), &type_script),
+        None => fields.push((format!("{}type", prefix), "none".to_string())),
+    }
+}
+
+fn push_script(fields: &mut Vec<(String, String)>, name: &str, script: &Script) {
+    fields.push((
+        format!("{}.code_hash", name),
+        hex(script.code_hash().as_slice()),
+    ));
+    let hash_type = match script.hash_type().as_slice()[0] {
+        0 => "data".to_string(),
+        1 => "type".to_string(),
+        2 => "data1".to_string(),
+        hash_type => format!("{:#04x}", hash_type),
+    };
+    fields.push((format!("{}.hash_type", name), hash_type));
+    fields.push((format!("{}.args", name), hex(&script.args().raw_data())));
+}
+
+fn hex(data: &[u8]) -> String {
+    let mut hex = String::from("0x");
+    for byte in data {
+        hex.push_str(&format!("{:02x}", byte));
+    }
+    hex
+}
@@ -0,0 +1,549 @@
+use crate::error::{Error, MergeConflictKind};
+use crate::key::KeyBackend;
+use crate::signer::{set_witness_lock, witness_lock, OtxSigner};
+
+use std::collections::BTreeMap;
+
+use ckb_jsonrpc_types as json;
+use ckb_types::{bytes::Bytes, core::TransactionView, packed, prelude::*};
+use otx_sighash_core::{
+    SighashMode, WitnessLock, LIMIT_ORDER_INTENT_SIZE, SIGHASH_ALL_SIGNATURE_SIZE,
+};
+use serde::{Deserialize, Serialize};
+
+/// Version of the PSOT encoding written by this crate.
+pub const PSOT_VERSION: u8 = 1;
+
+const NUMBER_SIZE: usize = 4;
+// mode byte of the inputs not signed in an otx mode
+const NO_SIGHASH_MODE: u8 = 0x00;
+
+/// An input of a PSOT, with the cell it consumes.
+#[derive(Clone, Debug)]
+pub struct PsotInput {
+    pub cell_output: packed::CellOutput,
+    pub data: Bytes,
+    /// Mode the witness lock is signed with, `None` for the inputs unlocked otherwise, such
+    /// as anyone-can-pay payments or cells under other locks.
+    pub mode: Option<SighashMode>,
+    pub signed: bool,
+}
+
+impl PsotInput {
+    pub fn new(cell_output: packed::CellOutput, data: Bytes, mode: Option<SighashMode>) -> Self {
+        PsotInput {
+            cell_output,
+            data,
+            mode,
+            signed: false,
+        }
+    }
+}
+
+/// Partially signed otx, passed between the maker, the aggregator and the co-signers.
+///
+/// `to_bytes` encodes it with molecule as laid out in `schemas/psot.mol`, `to_json` as
+/// JSON with the transaction in the format of the CKB RPC.
+#[derive(Clone, Debug)]
+pub struct Psot {
+    pub tx: TransactionView,
+    /// The inputs of `tx`, in the same order.
+    pub inputs: Vec<PsotInput>,
+    pub metadata: BTreeMap<String, Bytes>,
+}
+
+impl Psot {
+    pub fn new(tx: TransactionView, inputs: Vec<PsotInput>) -> Self {
+        Psot {
+            tx,
+            inputs,
+            metadata: BTreeMap::new(),
+        }
+    }
+
+    /// Checks that the inputs match the transaction and that the witness locks agree with
+    /// the modes and signing status of the inputs.
+    pub fn validate(&self) -> Result<(), Error> {
+        let inputs_len = self.tx.inputs().len();
+        if self.inputs.len() != inputs_len {
+            return Err(Error::InputCountMismatch(self.inputs.len(), inputs_len));
+        }
+        for (index, input) in self.inputs.iter().enumerate() {
+            let mode = match input.mode {
+                Some(mode) => mode,
+                None => continue,
+            };
+            // the modes the lock verifies input by input
+            if !matches!(
+                mode,
+                SighashMode::AllAnyoneCanPay
+                    | SighashMode::SingleAnyoneCanPay
+                    | SighashMode::SingleUdtAnyoneCanPay
+                    | SighashMode::LimitOrder
+            ) {
+                return Err(otx_sighash_core::Error::UnsupportedSighashMode.into());
+            }
+            let witness_lock = witness_lock(&self.tx, index)?;
+            if !input.signed {
+                // a placeholder may be in place, its signature is zeroed
+                let placeholder = witness_lock.map_or(true, |witness_lock| {
+                    witness_lock
+                        .iter()
+                        .skip(1)
+                        .take(SIGHASH_ALL_SIGNATURE_SIZE)
+                        .all(|byte| *byte == 0)
+                });
+                if !placeholder {
+                    return Err(Error::SignatureStatus(index));
+                }
+                continue;
+            }
+            let witness_lock = witness_lock.ok_or(Error::SignatureStatus(index))?;
+            let witness_lock = WitnessLock::from_slice(&witness_lock)?;
+            if witness_lock.mode != mode {
+                return Err(Error::ModeMismatch(index));
+            }
+            if witness_lock.signature == [0u8; SIGHASH_ALL_SIGNATURE_SIZE] {
+                return Err(Error::SignatureStatus(index));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether every input signed in an otx mode carries its signature.
+    pub fn is_complete(&self) -> bool {
+        self.validate().is_ok()
+            && self
+                .inputs
+                .iter()
+                .all(|input| input.mode.is_none() || input.signed)
+    }
+
+    /// Signs the input at `index` in its mode, the witness lock carrying `payload` and
+    /// `extension` after the signature. The lock group is found from the consumed cells.
+    pub fn sign<K: KeyBackend>(
+        &mut self,
+        key: K,
+        index: usize,
+        payload: &[u8],
+        extension: &[u8],
+    ) -> Result<(), Error> {
+        let input = self
+            .inputs
+            .get(index)
+            .ok_or(Error::InputOutOfBound(index))?;
+        let mode = input.mode.ok_or(Error::NoSighashMode(index))?;
+        let lock = input.cell_output.lock();
+        let group_inputs = self
+            .inputs
+            .iter()
+            .enumerate()
+            .filter(|(_, input)| input.cell_output.lock().as_slice() == lock.as_slice())
+            .map(|(i, _)| i)
+            .collect();
+
+        self.tx = OtxSigner::new(key)
+            .group_inputs(group_inputs)
+            .sign(&self.tx, index, mode, payload, extension)?;
+        self.inputs[index].signed = true;
+        Ok(())
+    }
+
+    /// Appends the inputs, outputs and witnesses of `other` after those of this otx, and
+    /// the cell deps and header deps this otx misses. Witnesses are padded up to the inputs
+    /// of this otx first, the metadata of this otx wins over that of `other`.
+    ///
+    /// Fails with `MergeConflict` when a signature would not hold in the merged transaction,
+    /// with the kind of conflict:
+    ///
+    /// - ALL|ANYONECANPAY commits to all outputs unless it has an otx scope. The starts of
+    ///   the scopes in `other` are moved past this otx, they are not signed
+    /// - SINGLE modes and limit orders commit to the output at the index of their input, so
+    ///   the inputs and outputs of this otx must be aligned when `other` has such signatures.
+    ///   The payment indices of the limit orders in `other` are moved past this otx, they are
+    ///   not signed
+    /// - cell deps and header deps committed by an extension must not change
+    pub fn merge(&self, other: &Psot) -> Result<Psot, Error> {
+        self.validate()?;
+        other.validate()?;
+
+        let inputs_len = self.tx.inputs().len();
+        let outputs_len = self.tx.outputs().len();
+        let witnesses_len = self.tx.witnesses().len();
+        let cell_deps = packed::CellDepVec::new_builder()
+            .set(merge_deps(self.tx.cell_deps(), other.tx.cell_deps()))
+            .build();
+        let header_deps = packed::Byte32Vec::new_builder()
+            .set(merge_deps(self.tx.header_deps(), other.tx.header_deps()))
+            .build();
+
+        let mut witnesses: Vec<packed::Bytes> = self.tx.witnesses().into_iter().collect();
+        if !other.tx.witnesses().is_empty() && witnesses.len() < inputs_len {
+            witnesses.resize(inputs_len, packed::Bytes::default());
+        }
+        witnesses.extend(other.tx.witnesses());
+        let mut tx = self
+            .tx
+            .as_advanced_builder()
+            .inputs(other.tx.inputs())
+            .outputs(other.tx.outputs())
+            .outputs_data(other.tx.outputs_data())
+            .set_cell_deps(cell_deps.clone().into_iter().collect())
+            .set_header_deps(header_deps.clone().into_iter().collect())
+            .set_witnesses(witnesses)
+            .build();
+
+        let other_is_empty = other.tx.inputs().is_empty() && other.tx.outputs().is_empty();
+        for (index, witness_lock) in signed_witness_locks(self)? {
+            let witness_lock = WitnessLock::from_slice(&witness_lock)?;
+            let conflict = if witness_lock.mode == SighashMode::AllAnyoneCanPay
+                && witness_lock.extension.otx_scope.is_none()
+                && !other_is_empty
+            {
+                Some(MergeConflictKind::AllOutputs)
+            } else {
+                deps_conflict(&witness_lock, &cell_deps, &header_deps, &self.tx)
+            };
+            if let Some(conflict) = conflict {
+                return Err(Error::MergeConflict(index, conflict));
+            }
+        }
+
+        let self_is_empty = inputs_len == 0 && outputs_len == 0;
+        for (index, mut witness_lock) in signed_witness_locks(other)? {
+            let merged_index = inputs_len + index;
+            let parsed = WitnessLock::from_slice(&witness_lock)?;
+            let positional = matches!(
+                parsed.mode,
+                SighashMode::SingleAnyoneCanPay
+                    | SighashMode::SingleUdtAnyoneCanPay
+                    | SighashMode::LimitOrder
+            );
+            let conflict = if witnesses_len > inputs_len {
+                Some(MergeConflictKind::ExtraWitnesses)
+            } else if positional && inputs_len != outputs_len {
+                Some(MergeConflictKind::Unaligned)
+            } else if parsed.mode == SighashMode::AllAnyoneCanPay
+                && parsed.extension.otx_scope.is_none()
+                && !self_is_empty
+            {
+                Some(MergeConflictKind::AllOutputs)
+            } else {
+                deps_conflict(&parsed, &cell_deps, &header_deps, &other.tx)
+            };
+            if let Some(conflict) = conflict {
+                return Err(Error::MergeConflict(merged_index, conflict));
+            }
+
+            // the batcher places the scope, the starts are not signed
+            let scope = parsed.extension.otx_scope.as_ref().map(|scope| {
+                (
+                    1 + SIGHASH_ALL_SIGNATURE_SIZE + parsed.payload.len() + scope.offset,
+                    (scope.input_start + inputs_len) as u32,
+                    (scope.output_start + outputs_len) as u32,
+                )
+            });
+            let is_limit_order = parsed.mode == SighashMode::LimitOrder;
+            if let Some((position, input_start, output_start)) = scope {
+                witness_lock[position..position + NUMBER_SIZE]
+                    .copy_from_slice(&input_start.to_le_bytes());
+                witness_lock[position + NUMBER_SIZE * 2..position + NUMBER_SIZE * 3]
+                    .copy_from_slice(&output_start.to_le_bytes());
+                tx = set_witness_lock(&tx, merged_index, &witness_lock)?;
+            }
+
+            // the payment output moves along with the otx, its index is not signed either
+            if is_limit_order {
+                let position = 1 + SIGHASH_ALL_SIGNATURE_SIZE + LIMIT_ORDER_INTENT_SIZE;
+                let payment_index = (read_number(&witness_lock[position..]) + outputs_len) as u32;
+                witness_lock[position..position + NUMBER_SIZE]
+                    .copy_from_slice(&payment_index.to_le_bytes());
+                tx = set_witness_lock(&tx, merged_index, &witness_lock)?;
+            }
+        }
+
+        let mut metadata = other.metadata.clone();
+        metadata.extend(self.metadata.clone());
+        Ok(Psot {
+            tx,
+            inputs: self.inputs.iter().chain(&other.inputs).cloned().collect(),
+            metadata,
+        })
+    }
+
+    /// Molecule encoding, see `schemas/psot.mol`.
+    pub fn to_bytes(&self) -> Bytes {
+        let inputs: Vec<Vec<u8>> = self
+            .inputs
+            .iter()
+            .map(|input| {
+                table::<&[u8]>(&[
+                    input.cell_output.as_slice(),
+                    &bytes(&input.data),
+                    &[input.mode.map_or(NO_SIGHASH_MODE, |mode| mode as u8)],
+                    &[input.signed as u8],
+                ])
+            })
+            .collect();
+        let metadata: Vec<Vec<u8>> = self
+            .metadata
+            .iter()
+            .map(|(key, value)| table(&[bytes(key.as_bytes()), bytes(value)]))
+            .collect();
+
+        table::<&[u8]>(&[
+            &[PSOT_VERSION],
+            self.tx.data().as_slice(),
+            &table(&inputs),
+            &table(&metadata),
+        ])
+        .into()
+    }
+
+    pub fn from_slice(data: &[u8]) -> Result<Psot, Error> {
+        let fields = table_fields(data, Some(4))?;
+        let version = byte(fields[0])?;
+        if version != PSOT_VERSION {
+            return Err(Error::UnsupportedPsotVersion(version));
+        }
+        let tx = packed::Transaction::from_slice(fields[1])
+            .map_err(|_| Error::InvalidPsot)?
+            .into_view();
+
+        let mut inputs = Vec::new();
+        for input in table_fields(fields[2], None)? {
+            let fields = table_fields(input, Some(4))?;
+            inputs.push(PsotInput {
+                cell_output: packed::CellOutput::from_slice(fields[0])
+                    .map_err(|_| Error::InvalidPsot)?,
+                data: raw_bytes(fields[1])?,
+                mode: match byte(fields[2])? {
+                    NO_SIGHASH_MODE => None,
+                    mode => Some(SighashMode::from_byte(mode).map_err(|_| Error::InvalidPsot)?),
+                },
+                signed: match byte(fields[3])? {
+                    0 => false,
+                    1 => true,
+                    _ => return Err(Error::InvalidPsot),
+                },
+            });
+        }
+
+        let mut metadata = BTreeMap::new();
+        for entry in table_fields(fields[3], None)? {
+            let fields = table_fields(entry, Some(2))?;
+            let key = String::from_utf8(raw_bytes(fields[0])?.to_vec())
+                .map_err(|_| Error::InvalidPsot)?;
+            metadata.insert(key, raw_bytes(fields[1])?);
+        }
+
+        Ok(Psot {
+            tx,
+            inputs,
+            metadata,
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        let psot = JsonPsot {
+            version: PSOT_VERSION,
+            tx: self.tx.data().into(),
+            inputs: self
+                .inputs
+                .iter()
+                .map(|input| JsonPsotInput {
+                    cell_output: input.cell_output.clone().into(),
+                    data: json::JsonBytes::from_bytes(input.data.clone()),
+                    mode: input.mode.map(|mode| mode as u8),
+                    signed: input.signed,
+                })
+                .collect(),
+            metadata: self
+                .metadata
+                .iter()
+                .map(|(key, value)| (key.clone(), json::JsonBytes::from_bytes(value.clone())))
+                .collect(),
+        };
+        serde_json::to_string_pretty(&psot).expect("serialize psot")
+    }
+
+    pub fn from_json(data: &str) -> Result<Psot, Error> {
+        let psot: JsonPsot = serde_json::from_str(data)?;
+        if psot.version != PSOT_VERSION {
+            return Err(Error::UnsupportedPsotVersion(psot.version));
+        }
+
+        let mut inputs = Vec::new();
+        for input in psot.inputs {
+            inputs.push(PsotInput {
+                cell_output: input.cell_output.into(),
+                data: input.data.into_bytes(),
+                mode: match input.mode {
+                    Some(mode) => {
+                        Some(SighashMode::from_byte(mode).map_err(|_| Error::InvalidPsot)?)
+                    }
+                    None => None,
+                },
+                signed: input.signed,
+            });
+        }
+
+        Ok(Psot {
+            tx: packed::Transaction::from(psot.tx).into_view(),
+            inputs,
+            metadata: psot
+                .metadata
+                .into_iter()
+                .map(|(key, value)| (key, value.into_bytes()))
+                .collect(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonPsot {
+    version: u8,
+    tx: json::Transaction,
+    inputs: Vec<JsonPsotInput>,
+    metadata: BTreeMap<String, json::JsonBytes>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonPsotInput {
+    cell_output: json::CellOutput,
+    data: json::JsonBytes,
+    mode: Option<u8>,
+    signed: bool,
+}
+
+// the witness locks of the signed inputs of a validated psot
+fn signed_witness_locks(psot: &Psot) -> Result<Vec<(usize, Vec<u8>)>, Error> {
+    let mut witness_locks = Vec::new();
+    for (index, input) in psot.inputs.iter().enumerate() {
+        if input.mode.is_some() && input.signed {
+            let witness_lock =
+                witness_lock(&psot.tx, index)?.ok_or(Error::SignatureStatus(index))?;
+            witness_locks.push((index, witness_lock.to_vec()));
+        }
+    }
+    Ok(witness_locks)
+}
+
+// whether the merged deps change those committed by a signature of `tx`
+fn deps_conflict(
+    witness_lock: &WitnessLock,
+    cell_deps: &packed::CellDepVec,
+    header_deps: &packed::Byte32Vec,
+    tx: &TransactionView,
+) -> Option<MergeConflictKind> {
+    if witness_lock.extension.commit_cell_deps && cell_deps.as_slice() != tx.cell_deps().as_slice()
+    {
+        Some(MergeConflictKind::CellDepsChanged)
+    } else if witness_lock.extension.commit_header_deps
+        && header_deps.as_slice() != tx.header_deps().as_slice()
+    {
+        Some(MergeConflictKind::HeaderDepsChanged)
+    } else {
+        None
+    }
+}
+
+// `deps` followed by those of `other_deps` it misses
+fn merge_deps<T: Entity>(
+    deps: impl IntoIterator<Item = T>,
+    other_deps: impl IntoIterator<Item = T>,
+) -> Vec<T> {
+    let mut merged: Vec<T> = deps.into_iter().collect();
+    for dep in other_deps {
+        if !merged
+            .iter()
+            .any(|merged| merged.as_slice() == dep.as_slice())
+        {
+            merged.push(dep);
+        }
+    }
+    merged
+}
+
+fn read_number(data: &[u8]) -> usize {
+    let mut buf = [0u8; NUMBER_SIZE];
+    buf.copy_from_slice(&data[..NUMBER_SIZE]);
+    u32::from_le_bytes(buf) as usize
+}
+
+// encodes a table, or a dynvec which shares the layout:
+// `total_size (u32) | offsets (u32 each) | fields`
+fn table<T: AsRef<[u8]>>(fields: &[T]) -> Vec<u8> {
+    let header_size = NUMBER_SIZE * (fields.len() + 1);
+    let total_size = header_size + fields.iter().map(|f| f.as_ref().len()).sum::<usize>();
+
+    let mut data = Vec::with_capacity(total_size);
+    data.extend_from_slice(&(total_size as u32).to_le_bytes());
+    let mut offset = header_size;
+    for field in fields {
+        data.extend_from_slice(&(offset as u32).to_le_bytes());
+        offset += field.as_ref().len();
+    }
+    for field in fields {
+        data.extend_from_slice(field.as_ref());
+    }
+    data
+}
+
+// splits a table of `count` fields, or a dynvec when `count` is `None`
+fn table_fields(data: &[u8], count: Option<usize>) -> Result<Vec<&[u8]>, Error> {
+    if data.len() < NUMBER_SIZE || read_number(data) != data.len() {
+        return Err(Error::InvalidPsot);
+    }
+    if data.len() == NUMBER_SIZE {
+        return match count {
+            None | Some(0) => Ok(Vec::new()),
+            Some(_) => Err(Error::InvalidPsot),
+        };
+    }
+    if data.len() < NUMBER_SIZE * 2 {
+        return Err(Error::InvalidPsot);
+    }
+    let header_size = read_number(&data[NUMBER_SIZE..]);
+    if header_size % NUMBER_SIZE != 0 || header_size < NUMBER_SIZE * 2 || header_size > data.len() {
+        return Err(Error::InvalidPsot);
+    }
+    let len = header_size / NUMBER_SIZE - 1;
+    if count.map_or(false, |count| count != len) {
+        return Err(Error::InvalidPsot);
+    }
+
+    let mut offsets: Vec<usize> = (1..=len)
+        .map(|i| read_number(&data[NUMBER_SIZE * i..]))
+        .collect();
+    offsets.push(data.len());
+    let mut fields = Vec::with_capacity(len);
+    for window in offsets.windows(2) {
+        if window[0] > window[1] {
+            return Err(Error::InvalidPsot);
+        }
+        fields.push(&data[window[0]..window[1]]);
+    }
+    Ok(fields)
+}
+
+// encodes `Bytes`: `len (u32) | data`
+fn bytes(data: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(NUMBER_SIZE + data.len());
+    encoded.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    encoded.extend_from_slice(data);
+    encoded
+}
+
+fn raw_bytes(field: &[u8]) -> Result<Bytes, Error> {
+    Ok(packed::Bytes::from_slice(field)
+        .map_err(|_| Error::InvalidPsot)?
+        .raw_data())
+}
+
+fn byte(field: &[u8]) -> Result<u8, Error> {
+    match field {
+        [byte] => Ok(*byte),
+        _ => Err(Error::InvalidPsot),
+    }
+}
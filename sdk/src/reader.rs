@@ -0,0 +1,81 @@
+use crate::error::Error;
+
+use ckb_types::{core::TransactionView, prelude::*, H256};
+use otx_sighash_core::TxAccess;
+
+/// Gives the signing messages access to a transaction being signed.
+pub struct TxReader<'a>(pub &'a TransactionView);
+
+impl TxAccess for TxReader<'_> {
+    type Error = Error;
+
+    fn inputs_len(&self) -> Result<usize, Error> {
+        Ok(self.0.inputs().len())
+    }
+
+    fn input(&self, index: usize) -> Result<Vec<u8>, Error> {
+        let input = self.0.inputs().get(index);
+        Ok(input
+            .ok_or(Error::InputOutOfBound(index))?
+            .as_slice()
+            .to_vec())
+    }
+
+    fn outputs_len(&self) -> Result<usize, Error> {
+        Ok(self.0.outputs().len())
+    }
+
+    fn output(&self, index: usize) -> Result<Vec<u8>, Error> {
+        let output = self.0.outputs().get(index);
+        Ok(output
+            .ok_or(Error::OutputOutOfBound(index))?
+            .as_slice()
+            .to_vec())
+    }
+
+    fn output_data(&self, index: usize) -> Result<Vec<u8>, Error> {
+        let data = self.0.outputs_data().get(index);
+        Ok(data
+            .ok_or(Error::OutputOutOfBound(index))?
+            .raw_data()
+            .to_vec())
+    }
+
+    fn output_lock_hash(&self, index: usize) -> Result<[u8; 32], Error> {
+        let output = self.0.outputs().get(index);
+        let lock_hash: H256 = output
+            .ok_or(Error::OutputOutOfBound(index))?
+            .lock()
+            .calc_script_hash()
+            .unpack();
+        Ok(lock_hash.0)
+    }
+
+    fn output_type_hash(&self, index: usize) -> Result<Option<[u8; 32]>, Error> {
+        let output = self.0.outputs().get(index);
+        Ok(output
+            .ok_or(Error::OutputOutOfBound(index))?
+            .type_()
+            .to_opt()
+            .map(|type_script| {
+                let type_hash: H256 = type_script.calc_script_hash().unpack();
+                type_hash.0
+            }))
+    }
+
+    fn input_witness(&self, index: usize) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self
+            .0
+            .witnesses()
+            .get(index)
+            .map(|witness| witness.raw_data().to_vec()))
+    }
+
+    fn cell_deps(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.0.cell_deps().as_slice().to_vec())
+    }
+
+    fn header_deps(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.0.header_deps().as_slice().to_vec())
+    }
+}
@@ -0,0 +1,143 @@
+use crate::error::Error;
+use crate::key::KeyBackend;
+use crate::reader::TxReader;
+
+use ckb_types::{
+    bytes::Bytes,
+    core::TransactionView,
+    packed::{self, WitnessArgs},
+    prelude::*,
+};
+use otx_sighash_core::{
+    delegation_message, signing_message, Delegation, SighashMode, WitnessLock, BLAKE160_SIZE,
+    SIGHASH_ALL_SIGNATURE_SIZE,
+};
+
+/// Signs the witness locks of otx-sighash-lock inputs.
+pub struct OtxSigner<K> {
+    key: K,
+    group_inputs: Option<Vec<usize>>,
+}
+
+impl<K: KeyBackend> OtxSigner<K> {
+    pub fn new(key: K) -> Self {
+        OtxSigner {
+            key,
+            group_inputs: None,
+        }
+    }
+
+    /// Sets the inputs of the lock group, whose witnesses ALL|ANYONECANPAY partly commits
+    /// to. Signing in ALL|ANYONECANPAY fails without them.
+    pub fn group_inputs(mut self, group_inputs: Vec<usize>) -> Self {
+        self.group_inputs = Some(group_inputs);
+        self
+    }
+
+    /// Signs the input at `input_index` with `mode`, the witness lock carrying `payload` and
+    /// `extension` after the signature. The other fields of the witness are kept.
+    pub fn sign(
+        &self,
+        tx: &TransactionView,
+        input_index: usize,
+        mode: SighashMode,
+        payload: &[u8],
+        extension: &[u8],
+    ) -> Result<TransactionView, Error> {
+        if input_index >= tx.inputs().len() {
+            return Err(Error::InputOutOfBound(input_index));
+        }
+
+        // the signature is zeroed in the digest, sign with the final layout in place
+        let mut witness_lock = vec![mode as u8];
+        witness_lock.extend_from_slice(&[0u8; SIGHASH_ALL_SIGNATURE_SIZE]);
+        witness_lock.extend_from_slice(payload);
+        witness_lock.extend_from_slice(extension);
+        let tx = set_witness_lock(tx, input_index, &witness_lock)?;
+
+        let message = self.signing_message(&tx, input_index, &witness_lock)?;
+        let signature = self.sign_message(&message)?;
+        witness_lock[1..1 + SIGHASH_ALL_SIGNATURE_SIZE].copy_from_slice(&signature);
+
+        set_witness_lock(&tx, input_index, &witness_lock)
+    }
+
+    /// Message the witness lock of the input at `input_index` signs.
+    pub fn signing_message(
+        &self,
+        tx: &TransactionView,
+        input_index: usize,
+        witness_lock: &[u8],
+    ) -> Result<[u8; 32], Error> {
+        let witness_lock = WitnessLock::from_slice(witness_lock)?;
+        let group_inputs = match (&self.group_inputs, witness_lock.mode) {
+            (Some(group_inputs), _) => group_inputs.as_slice(),
+            (None, SighashMode::AllAnyoneCanPay) => return Err(Error::NoGroupInputs),
+            (None, _) => &[],
+        };
+        signing_message(&TxReader(tx), input_index, group_inputs, &witness_lock)
+    }
+
+    /// Certifies the session key with `session_pubkey_hash` to sign with the modes
+    /// `0x81 + n` for every bit `n` of `allowed_modes`, losing at most `max_capacity_out`
+    /// shannons, with header deps up to `expiry`.
+    pub fn sign_delegation(
+        &self,
+        session_pubkey_hash: [u8; BLAKE160_SIZE],
+        allowed_modes: u8,
+        expiry: u64,
+        max_capacity_out: u64,
+    ) -> Result<Delegation, Error> {
+        let mut delegation = Delegation {
+            session_pubkey_hash,
+            allowed_modes,
+            expiry,
+            max_capacity_out,
+            owner_signature: [0u8; SIGHASH_ALL_SIGNATURE_SIZE],
+        };
+        delegation.owner_signature = self.sign_message(&delegation_message(&delegation))?;
+        Ok(delegation)
+    }
+
+    fn sign_message(&self, message: &[u8; 32]) -> Result<[u8; SIGHASH_ALL_SIGNATURE_SIZE], Error> {
+        self.key
+            .sign_recoverable(message)
+            .map_err(|err| Error::Key(Box::new(err)))
+    }
+}
+
+// places `witness_lock` in the witness at `index`, adding empty witnesses before it as needed
+pub(crate) fn set_witness_lock(
+    tx: &TransactionView,
+    index: usize,
+    witness_lock: &[u8],
+) -> Result<TransactionView, Error> {
+    let mut witnesses: Vec<packed::Bytes> = tx.witnesses().into_iter().collect();
+    if witnesses.len() <= index {
+        witnesses.resize(index + 1, packed::Bytes::default());
+    }
+    let witness = witnesses[index].raw_data();
+    let witness_args = if witness.is_empty() {
+        WitnessArgs::default()
+    } else {
+        WitnessArgs::from_slice(&witness).map_err(|_| Error::InvalidWitness(index))?
+    };
+    witnesses[index] = witness_args
+        .as_builder()
+        .lock(Some(Bytes::from(witness_lock.to_vec())).pack())
+        .build()
+        .as_bytes()
+        .pack();
+    Ok(tx.as_advanced_builder().set_witnesses(witnesses).build())
+}
+
+// the witness lock of the input at `index`, `None` when it has none
+pub(crate) fn witness_lock(tx: &TransactionView, index: usize) -> Result<Option<Bytes>, Error> {
+    let witness = match tx.witnesses().get(index) {
+        Some(witness) if !witness.raw_data().is_empty() => witness.raw_data(),
+        _ => return Ok(None),
+    };
+    let witness_args =
+        WitnessArgs::from_slice(&witness).map_err(|_| Error::InvalidWitness(index))?;
+    Ok(witness_args.lock().to_opt().map(|lock| lock.raw_data()))
+}
@@ -1,25 +1,31 @@
-use ckb_testtool::ckb_crypto::secp::Privkey;
-use ckb_testtool::ckb_hash::{blake2b_256, new_blake2b};
+use crate::Loader;
+
+use ckb_system_scripts::BUNDLED_CELL;
+use ckb_testtool::builtin::ALWAYS_SUCCESS;
+use ckb_testtool::ckb_crypto::secp::{Generator, Privkey};
+use ckb_testtool::ckb_hash::{blake2b_256, new_blake2b, Blake2bBuilder};
 use ckb_testtool::ckb_types::{
     bytes::Bytes,
-    core::TransactionView,
+    core::{
+        EpochNumberWithFraction, HeaderBuilder, HeaderView, TransactionBuilder, TransactionView,
+    },
     packed::{self, *},
     prelude::*,
     H256,
 };
+use ckb_testtool::context::Context;
+
+use otx_sighash_lock_sdk::{ExtensionBuilder, OtxSigner, Psot, PsotInput};
+
+use std::fs;
+
+pub use otx_sighash_core::{
+    SighashMode, EXTENSION_CELL_DEPS, EXTENSION_DELEGATION, EXTENSION_EXPIRY, EXTENSION_FEE_LIMIT,
+    EXTENSION_HEADER_DEPS, EXTENSION_OTX_SCOPE, EXTENSION_READABLE, EXTENSION_RECOVERY,
+};
 
 pub const MAX_CYCLES: u64 = 10_000_000;
 pub const SIGNATURE_SIZE: usize = 65;
-pub const MAGIC_CODE: &str = "COTX";
-
-pub enum SighashMode {
-    All = 0x01,
-    None = 0x02,
-    Single = 0x03,
-    AllAnyoneCanPay = 0x81,
-    NoneAnyoneCanPay = 0x82,
-    SingleAnyoneCanPay = 0x83,
-}
 
 pub fn blake160(data: &[u8]) -> [u8; 20] {
     let mut buf = [0u8; 20];
@@ -28,6 +34,210 @@ pub fn blake160(data: &[u8]) -> [u8; 20] {
     buf
 }
 
+/// The lock deployed in a test context, with an always success script for the other
+/// parties and the UDTs.
+pub struct Deployment {
+    pub lock_out_point: OutPoint,
+    pub always_success_out_point: OutPoint,
+    /// The secp256k1 library and its data, loaded by the lock.
+    pub cell_deps: Vec<CellDep>,
+}
+
+impl Deployment {
+    pub fn lock_script(&self, context: &mut Context, args: &[u8]) -> Script {
+        context
+            .build_script(&self.lock_out_point, Bytes::copy_from_slice(args))
+            .expect("script")
+    }
+
+    pub fn always_success_script(&self, context: &mut Context, args: &[u8]) -> Script {
+        context
+            .build_script(&self.always_success_out_point, Bytes::copy_from_slice(args))
+            .expect("script")
+    }
+}
+
+pub fn deploy(context: &mut Context) -> Deployment {
+    // deploy contract
+    let contract_bin: Bytes = Loader::default().load_binary("otx-sighash-lock");
+    let lock_out_point = context.deploy_cell(contract_bin);
+    let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    // deploy secp256k1 lib
+    let secp256k1_bin: Bytes =
+        fs::read("../ckb-miscellaneous-scripts/build/secp256k1_blake2b_sighash_all_dual")
+            .expect("load secp256k1")
+            .into();
+    let secp256k1_out_point = context.deploy_cell(secp256k1_bin);
+
+    // deploy secp256k1 data
+    let secp256k1_data_bin = BUNDLED_CELL.get("specs/cells/secp256k1_data").unwrap();
+    let secp256k1_data_out_point = context.deploy_cell(secp256k1_data_bin.to_vec().into());
+
+    Deployment {
+        lock_out_point,
+        always_success_out_point,
+        cell_deps: [secp256k1_out_point, secp256k1_data_out_point]
+            .into_iter()
+            .map(|out_point| CellDep::new_builder().out_point(out_point).build())
+            .collect(),
+    }
+}
+
+pub fn cell_output(capacity: u64, lock: &Script, type_: Option<&Script>) -> CellOutput {
+    CellOutput::new_builder()
+        .capacity(capacity.pack())
+        .lock(lock.clone())
+        .type_(type_.cloned().pack())
+        .build()
+}
+
+/// The header of block `number`, in epochs of 1000 blocks.
+pub fn block_header(number: u64) -> HeaderView {
+    let epoch = EpochNumberWithFraction::new(number / 1000, number % 1000, 1000);
+    HeaderBuilder::default()
+        .number(number.pack())
+        .epoch(epoch.full_value().pack())
+        .build()
+}
+
+/// Creates the cells of `inputs` and builds a transaction spending them to `outputs`, with
+/// the cell deps of the deployment and of the scripts in use.
+pub fn build_tx(
+    context: &mut Context,
+    deployment: &Deployment,
+    inputs: Vec<(CellOutput, Bytes)>,
+    outputs: Vec<(CellOutput, Bytes)>,
+) -> TransactionView {
+    let tx = assemble_tx(context, deployment, inputs, outputs);
+    context.complete_tx(tx)
+}
+
+/// The otx most tests sign: a new key moves a 1000 shannon cell of its lock to an output of
+/// the same lock.
+pub struct Otx {
+    pub deployment: Deployment,
+    pub privkey: Privkey,
+    pub lock_script: Script,
+    pub input_cell: (CellOutput, Bytes),
+    pub tx: TransactionView,
+}
+
+impl Otx {
+    /// The lock args are the pubkey hash of the key followed by `args_fields`, such as the
+    /// minimums and the flags.
+    pub fn new(context: &mut Context, args_fields: &[u8]) -> Otx {
+        Otx::with_privkey(context, Generator::random_privkey(), args_fields)
+    }
+
+    /// `Otx::new` for a given key, such as one held by another signer.
+    pub fn with_privkey(context: &mut Context, privkey: Privkey, args_fields: &[u8]) -> Otx {
+        let deployment = deploy(context);
+        let pubkey_hash = blake160(&privkey.pubkey().expect("pubkey").serialize());
+
+        let lock_script = deployment.lock_script(context, &[&pubkey_hash, args_fields].concat());
+        let input_cell = (cell_output(1000, &lock_script, None), Bytes::new());
+        let tx = build_tx(
+            context,
+            &deployment,
+            vec![input_cell.clone()],
+            vec![(cell_output(1000, &lock_script, None), Bytes::new())],
+        );
+        Otx {
+            deployment,
+            privkey,
+            lock_script,
+            input_cell,
+            tx,
+        }
+    }
+
+    /// Replaces the output with the one built for the lock, adding the cell dep of its type
+    /// script.
+    pub fn output<F>(mut self, context: &mut Context, output: F) -> Otx
+    where
+        F: FnOnce(&Script) -> (CellOutput, Bytes),
+    {
+        let output = output(&self.lock_script);
+        let tx = self
+            .tx
+            .as_advanced_builder()
+            .set_outputs(vec![output.0])
+            .set_outputs_data(vec![output.1.pack()])
+            .build();
+        self.tx = context.complete_tx(tx);
+        self
+    }
+}
+
+// `build_tx` without the cell deps of the scripts, left to whoever completes the transaction
+fn assemble_tx(
+    context: &mut Context,
+    deployment: &Deployment,
+    inputs: Vec<(CellOutput, Bytes)>,
+    outputs: Vec<(CellOutput, Bytes)>,
+) -> TransactionView {
+    let mut tx = TransactionBuilder::default().cell_deps(deployment.cell_deps.clone());
+    for (cell_output, data) in inputs {
+        let out_point = context.create_cell(cell_output, data);
+        tx = tx.input(CellInput::new_builder().previous_output(out_point).build());
+    }
+    for (cell_output, data) in outputs {
+        tx = tx.output(cell_output).output_data(data.pack());
+    }
+    tx.build()
+}
+
+/// Wraps an unsigned otx spending `inputs` to `outputs` into a PSOT, every input to be signed
+/// in `mode`. Only the cell deps of the deployment are set, the aggregation completes them.
+pub fn build_psot(
+    context: &mut Context,
+    deployment: &Deployment,
+    inputs: Vec<(CellOutput, Bytes)>,
+    outputs: Vec<(CellOutput, Bytes)>,
+    mode: SighashMode,
+) -> Psot {
+    let tx = assemble_tx(context, deployment, inputs.clone(), outputs);
+    let inputs = inputs
+        .into_iter()
+        .map(|(cell_output, data)| PsotInput::new(cell_output, data, Some(mode)))
+        .collect();
+    Psot::new(tx, inputs)
+}
+
+/// An otx of a new maker spending a cell of 1000 shannons to `outputs`, or to an output of
+/// 1000 shannons of its own, signed in `mode` with `extension`.
+pub fn build_signed_psot(
+    context: &mut Context,
+    deployment: &Deployment,
+    mode: SighashMode,
+    extension: &[u8],
+    outputs: Option<Vec<CellOutput>>,
+) -> Psot {
+    let privkey = Generator::random_privkey();
+    let pubkey_hash = blake160(&privkey.pubkey().expect("pubkey").serialize());
+
+    let lock_script = deployment.lock_script(context, &pubkey_hash);
+    let outputs = outputs
+        .unwrap_or_else(|| vec![cell_output(1000, &lock_script, None)])
+        .into_iter()
+        .map(|output| (output, Bytes::new()))
+        .collect();
+    let mut psot = build_psot(
+        context,
+        deployment,
+        vec![(cell_output(1000, &lock_script, None), Bytes::new())],
+        outputs,
+        mode,
+    );
+    assert!(!psot.is_complete());
+
+    // sign
+    psot.sign(&privkey, 0, &[], extension).expect("sign");
+    assert!(psot.is_complete());
+    psot
+}
+
 pub fn sign_secp256k1_blake2b_sighash_all(tx: TransactionView, key: &Privkey) -> TransactionView {
     let witnesses_len = tx.witnesses().len();
     let tx_hash = tx.hash();
@@ -75,15 +285,72 @@ pub fn sign_secp256k1_blake2b_sighash_all(tx: TransactionView, key: &Privkey) ->
         .build()
 }
 
-fn add_prefix(sighash: u8, message: &mut [u8]) {
-    let prefix = format!("{} {}:\n{}", MAGIC_CODE, sighash, message.len())
-        .as_bytes()
-        .to_vec();
-    let new = [prefix, message.to_vec()].concat();
+/// Builds the witness extension `flags | max_fee (u64)`.
+pub fn fee_limit_extension(max_fee: u64) -> Vec<u8> {
+    let mut extension = vec![EXTENSION_FEE_LIMIT];
+    extension.extend_from_slice(&max_fee.to_le_bytes());
+    extension
+}
 
-    let mut blake2b = new_blake2b();
-    blake2b.update(&new);
-    blake2b.finalize(message);
+/// Builds the witness extension `flags | expiry (u64)` with an absolute `since` value.
+pub fn expiry_extension(expiry: u64) -> Vec<u8> {
+    let mut extension = vec![EXTENSION_EXPIRY];
+    extension.extend_from_slice(&expiry.to_le_bytes());
+    extension
+}
+
+/// Builds the witness extension `flags | input_start (u32) | input_count (u32) |
+/// output_start (u32) | output_count (u32)` scoping an ALL-style otx.
+pub fn otx_scope_extension(
+    input_start: u32,
+    input_count: u32,
+    output_start: u32,
+    output_count: u32,
+) -> Vec<u8> {
+    let mut extension = vec![EXTENSION_OTX_SCOPE];
+    for field in [input_start, input_count, output_start, output_count] {
+        extension.extend_from_slice(&field.to_le_bytes());
+    }
+    extension
+}
+
+/// Builds the witness extension `flags | certificate`, the owner delegating the modes
+/// `0x81 + n` for every bit `n` of `allowed_modes` to the session key until `expiry`.
+pub fn delegation_extension(
+    owner_key: &Privkey,
+    session_pubkey_hash: &[u8; 20],
+    allowed_modes: u8,
+    expiry: u64,
+    max_capacity_out: u64,
+) -> Vec<u8> {
+    let delegation = OtxSigner::new(owner_key)
+        .sign_delegation(
+            *session_pubkey_hash,
+            allowed_modes,
+            expiry,
+            max_capacity_out,
+        )
+        .expect("sign");
+    ExtensionBuilder::default().delegation(&delegation).build()
+}
+
+/// Signs the input at `input_index` with `mode`, the witness lock carrying `payload` and
+/// `extension` after the signature. All inputs are taken as the lock group.
+/// Signs the input at `input_index` in `mode`, ALL|ANYONECANPAY committing to the witnesses
+/// of the `group_inputs`.
+pub fn sign_witness_lock(
+    tx: TransactionView,
+    key: &Privkey,
+    input_index: usize,
+    group_inputs: &[usize],
+    mode: SighashMode,
+    payload: &[u8],
+    extension: &[u8],
+) -> TransactionView {
+    OtxSigner::new(key)
+        .group_inputs(group_inputs.to_vec())
+        .sign(&tx, input_index, mode, payload, extension)
+        .expect("sign")
 }
 
 pub fn sign_sighash_single_acp(
@@ -91,142 +358,331 @@ pub fn sign_sighash_single_acp(
     key: &Privkey,
     input_index: usize,
 ) -> TransactionView {
-    // input
-    let input = tx.inputs().get(input_index).unwrap();
-    let input_len = input.as_slice().len() as u64;
+    sign_sighash_single_acp_with_extension(tx, key, input_index, &[])
+}
 
-    // output
-    let output = tx.outputs().get(input_index).unwrap();
-    let output_len = output.as_slice().len() as u64;
+pub fn sign_sighash_single_acp_with_extension(
+    tx: TransactionView,
+    key: &Privkey,
+    input_index: usize,
+    extension: &[u8],
+) -> TransactionView {
+    sign_witness_lock(
+        tx,
+        key,
+        input_index,
+        &[],
+        SighashMode::SingleAnyoneCanPay,
+        &[],
+        extension,
+    )
+}
 
-    // outputs data
-    let output_data = tx.outputs_data().get(input_index).unwrap();
-    let output_data_len = output_data.as_slice().len() as u64;
+/// Builds the `SingleUdtAnyoneCanPay` payload of the witness lock.
+pub fn udt_requirement(
+    lock_hash: &[u8; 32],
+    capacity: u64,
+    type_hash: &[u8; 32],
+    udt_amount: u128,
+) -> Vec<u8> {
+    let mut payload = lock_hash.to_vec();
+    payload.extend_from_slice(&capacity.to_le_bytes());
+    payload.extend_from_slice(type_hash);
+    payload.extend_from_slice(&udt_amount.to_le_bytes());
+    payload
+}
 
-    // witness
-    let witness = WitnessArgs::default();
-    let zero_lock: Bytes = {
-        let mut buf = Vec::new();
-        buf.resize(1 + SIGNATURE_SIZE, 0);
-        buf.into()
-    };
-    let witness_for_digest = witness
-        .clone()
-        .as_builder()
-        .lock(Some(zero_lock).pack())
-        .build();
-    let witness_len = witness_for_digest.as_bytes().len() as u64;
+pub fn sign_sighash_single_udt_acp(
+    tx: TransactionView,
+    key: &Privkey,
+    input_index: usize,
+    payload: &[u8],
+) -> TransactionView {
+    sign_witness_lock(
+        tx,
+        key,
+        input_index,
+        &[],
+        SighashMode::SingleUdtAnyoneCanPay,
+        payload,
+        &[],
+    )
+}
 
-    // hash
-    let mut message = [0u8; 32];
-    let mut blake2b = new_blake2b();
-    blake2b.update(&input_len.to_le_bytes());
-    blake2b.update(input.as_slice());
-    blake2b.update(&output_len.to_le_bytes());
-    blake2b.update(output.as_slice());
-    blake2b.update(&output_data_len.to_le_bytes());
-    blake2b.update(output_data.as_slice());
-    blake2b.update(&witness_len.to_le_bytes());
-    blake2b.update(&witness_for_digest.as_bytes());
-    blake2b.finalize(&mut message);
+/// Builds the signed intent of a `LimitOrder`, an all zero type hash stands for CKB.
+pub fn limit_order_intent(
+    sell_type_hash: &[u8; 32],
+    buy_type_hash: &[u8; 32],
+    price_numerator: u64,
+    price_denominator: u64,
+) -> Vec<u8> {
+    let mut intent = sell_type_hash.to_vec();
+    intent.extend_from_slice(buy_type_hash);
+    intent.extend_from_slice(&price_numerator.to_le_bytes());
+    intent.extend_from_slice(&price_denominator.to_le_bytes());
+    intent
+}
 
-    // add prefix
-    add_prefix(SighashMode::SingleAnyoneCanPay as u8, &mut message);
+/// Signs the order cell at the next input without a witness.
+pub fn sign_limit_order(
+    tx: TransactionView,
+    key: &Privkey,
+    intent: &[u8],
+    payment_index: u32,
+    extension: &[u8],
+) -> TransactionView {
+    let mut payload = intent.to_vec();
+    payload.extend_from_slice(&payment_index.to_le_bytes());
+    let input_index = tx.witnesses().len();
+    sign_witness_lock(
+        tx,
+        key,
+        input_index,
+        &[],
+        SighashMode::LimitOrder,
+        &payload,
+        extension,
+    )
+}
 
-    // sign
-    let message = H256::from(message);
-    let sig = key.sign_recoverable(&message).expect("sign");
+/// A limit order of a new maker selling 1000 shannons for a UDT at 1:1, filled for 300
+/// shannons.
+pub fn build_limit_order_psot(context: &mut Context, deployment: &Deployment) -> Psot {
+    let privkey = Generator::random_privkey();
+    let pubkey_hash = blake160(&privkey.pubkey().expect("pubkey").serialize());
 
-    // witness
-    let mut witness_lock = vec![SighashMode::SingleAnyoneCanPay as u8];
-    witness_lock.extend_from_slice(&sig.serialize());
-    let witness = witness
-        .as_builder()
-        .lock(Some(Bytes::from(witness_lock)).pack())
-        .build()
-        .as_bytes()
-        .pack();
+    let lock_script = deployment.lock_script(context, &pubkey_hash);
+    let udt_type_script = deployment.always_success_script(context, &[42]);
+    let udt_type_hash: [u8; 32] = udt_type_script.calc_script_hash().unpack().into();
+    let intent = limit_order_intent(&[0u8; 32], &udt_type_hash, 1, 1);
+    let intent_hash = Bytes::from(blake2b_256(&intent).to_vec());
+
+    let mut psot = build_psot(
+        context,
+        deployment,
+        vec![(cell_output(1000, &lock_script, None), intent_hash.clone())],
+        vec![
+            (cell_output(700, &lock_script, None), intent_hash),
+            (
+                cell_output(200, &lock_script, Some(&udt_type_script)),
+                Bytes::from(300u128.to_le_bytes().to_vec()),
+            ),
+        ],
+        SighashMode::LimitOrder,
+    );
 
-    // set witness
-    tx.as_advanced_builder().witness(witness).build()
+    // sign, the payment follows the remainder
+    let mut payload = intent;
+    payload.extend_from_slice(&1u32.to_le_bytes());
+    psot.sign(&privkey, 0, &payload, &[]).expect("sign");
+    psot
 }
 
 pub fn sign_sighash_all_acp(
     tx: TransactionView,
     key: &Privkey,
     input_index: usize,
+    group_inputs: &[usize],
 ) -> TransactionView {
-    // input
-    let input = tx.inputs().get(input_index).unwrap();
-    let input_len = input.as_slice().len() as u64;
+    sign_sighash_all_acp_with_extension(tx, key, input_index, group_inputs, &[])
+}
 
-    // outputs
-    let outputs = tx.outputs();
-    let outputs_count = outputs.len();
-    let outputs_len = outputs.as_slice().len() as u64;
+pub fn sign_sighash_all_acp_with_extension(
+    tx: TransactionView,
+    key: &Privkey,
+    input_index: usize,
+    group_inputs: &[usize],
+    extension: &[u8],
+) -> TransactionView {
+    sign_witness_lock(
+        tx,
+        key,
+        input_index,
+        group_inputs,
+        SighashMode::AllAnyoneCanPay,
+        &[],
+        extension,
+    )
+}
 
-    // outputs data
-    let outputs_data = tx.outputs_data();
-    let outputs_data_count = outputs_data.unpack().len() as u64;
-    let outputs_data_len = outputs_data.as_slice().len() as u64;
+pub const WITNESS_LAYOUT_SIGHASH_ALL: u32 = 4278190081;
+pub const WITNESS_LAYOUT_SIGHASH_ALL_ONLY: u32 = 4278190082;
+pub const WITNESS_LAYOUT_OTX: u32 = 4278190083;
+pub const WITNESS_LAYOUT_OTX_START: u32 = 4278190084;
 
-    // witness
-    let witness = WitnessArgs::default();
-    let zero_lock: Bytes = {
-        let mut buf = Vec::new();
-        buf.resize(1 + SIGNATURE_SIZE, 0);
-        buf.into()
-    };
-    let witness_for_digest = witness
-        .clone()
-        .as_builder()
-        .lock(Some(zero_lock).pack())
+// encodes a molecule table, or a dynvec which shares the layout
+fn molecule_table(fields: &[Vec<u8>]) -> Vec<u8> {
+    let header_len = 4 * (fields.len() + 1);
+    let total_len = header_len + fields.iter().map(|field| field.len()).sum::<usize>();
+    let mut data = (total_len as u32).to_le_bytes().to_vec();
+    let mut offset = header_len;
+    for field in fields {
+        data.extend_from_slice(&(offset as u32).to_le_bytes());
+        offset += field.len();
+    }
+    for field in fields {
+        data.extend_from_slice(field);
+    }
+    data
+}
+
+fn molecule_bytes(data: &[u8]) -> Vec<u8> {
+    let mut bytes = (data.len() as u32).to_le_bytes().to_vec();
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+/// CoBuild `Message` without any action.
+pub fn cobuild_empty_message() -> Vec<u8> {
+    molecule_table(&[molecule_table(&[])])
+}
+
+/// Positions of an otx in the inputs, outputs, cell deps and header deps.
+#[derive(Clone, Copy)]
+pub struct OtxRange {
+    pub start_input_cell: usize,
+    pub input_cells: usize,
+    pub start_output_cell: usize,
+    pub output_cells: usize,
+    pub start_cell_deps: usize,
+    pub cell_deps: usize,
+    pub start_header_deps: usize,
+    pub header_deps: usize,
+}
+
+pub fn cobuild_otx_start_witness(range: &OtxRange) -> packed::Bytes {
+    let mut witness = WITNESS_LAYOUT_OTX_START.to_le_bytes().to_vec();
+    witness.extend(molecule_table(&[
+        (range.start_input_cell as u32).to_le_bytes().to_vec(),
+        (range.start_output_cell as u32).to_le_bytes().to_vec(),
+        (range.start_cell_deps as u32).to_le_bytes().to_vec(),
+        (range.start_header_deps as u32).to_le_bytes().to_vec(),
+    ]));
+    Bytes::from(witness).pack()
+}
+
+/// Signs the otx in `range` and returns its `Otx` witness, sealed for `script_hash`.
+///
+/// `input_cells` are the resolved cells and data of the otx inputs.
+pub fn sign_cobuild_otx(
+    tx: &TransactionView,
+    key: &Privkey,
+    script_hash: &Byte32,
+    range: &OtxRange,
+    input_cells: &[(CellOutput, Bytes)],
+    message: &[u8],
+) -> packed::Bytes {
+    let mut blake2b = Blake2bBuilder::new(32)
+        .personal(b"ckb-tcob-otxhash")
         .build();
-    let witness_len = witness_for_digest.as_bytes().len() as u64;
+    blake2b.update(message);
 
-    // hash
-    let mut message = [0u8; 32];
-    let mut blake2b = new_blake2b();
-    blake2b.update(&input_len.to_le_bytes());
-    blake2b.update(input.as_slice());
-    blake2b.update(&outputs_count.to_le_bytes());
-    blake2b.update(&outputs_len.to_le_bytes());
-    blake2b.update(outputs.as_slice());
-    blake2b.update(&outputs_data_count.to_le_bytes());
-    blake2b.update(&outputs_data_len.to_le_bytes());
-    blake2b.update(outputs_data.as_slice());
-    blake2b.update(&witness_len.to_le_bytes());
-    blake2b.update(&witness_for_digest.as_bytes());
+    blake2b.update(&(range.input_cells as u32).to_le_bytes());
+    for (i, (cell, data)) in input_cells.iter().enumerate() {
+        let input = tx.inputs().get(range.start_input_cell + i).unwrap();
+        blake2b.update(input.as_slice());
+        blake2b.update(cell.as_slice());
+        blake2b.update(&(data.len() as u32).to_le_bytes());
+        blake2b.update(data);
+    }
 
-    for i in 0..tx.inputs().len() {
-        if i == input_index {
-            continue;
-        }
-        let witness_for_digest = WitnessArgsBuilder::default().build();
-        let witness_len = witness_for_digest.as_bytes().len() as u64;
-        blake2b.update(&witness_len.to_le_bytes());
-        blake2b.update(&witness_for_digest.as_bytes());
+    blake2b.update(&(range.output_cells as u32).to_le_bytes());
+    for i in range.start_output_cell..range.start_output_cell + range.output_cells {
+        let output = tx.outputs().get(i).unwrap();
+        let data = tx.outputs_data().get(i).unwrap().raw_data();
+        blake2b.update(output.as_slice());
+        blake2b.update(&(data.len() as u32).to_le_bytes());
+        blake2b.update(&data);
     }
 
-    blake2b.finalize(&mut message);
+    blake2b.update(&(range.cell_deps as u32).to_le_bytes());
+    for i in range.start_cell_deps..range.start_cell_deps + range.cell_deps {
+        blake2b.update(tx.cell_deps().get(i).unwrap().as_slice());
+    }
+
+    blake2b.update(&(range.header_deps as u32).to_le_bytes());
+    for i in range.start_header_deps..range.start_header_deps + range.header_deps {
+        blake2b.update(tx.header_deps().get(i).unwrap().as_slice());
+    }
 
-    // add prefix
-    add_prefix(SighashMode::SingleAnyoneCanPay as u8, &mut message);
+    let mut signing_message = [0u8; 32];
+    blake2b.finalize(&mut signing_message);
 
     // sign
-    let message = H256::from(message);
-    let sig = key.sign_recoverable(&message).expect("sign");
+    let signing_message = H256::from(signing_message);
+    let sig = key.sign_recoverable(&signing_message).expect("sign");
 
     // witness
-    let mut witness_lock = vec![SighashMode::AllAnyoneCanPay as u8];
-    witness_lock.extend_from_slice(&sig.serialize());
-    let witness = witness
-        .as_builder()
-        .lock(Some(Bytes::from(witness_lock)).pack())
-        .build()
-        .as_bytes()
-        .pack();
+    let seal_pair = molecule_table(&[
+        script_hash.as_slice().to_vec(),
+        molecule_bytes(&sig.serialize()),
+    ]);
+    let mut witness = WITNESS_LAYOUT_OTX.to_le_bytes().to_vec();
+    witness.extend(molecule_table(&[
+        (range.input_cells as u32).to_le_bytes().to_vec(),
+        (range.output_cells as u32).to_le_bytes().to_vec(),
+        (range.cell_deps as u32).to_le_bytes().to_vec(),
+        (range.header_deps as u32).to_le_bytes().to_vec(),
+        message.to_vec(),
+        molecule_table(&[seal_pair]),
+    ]));
+    Bytes::from(witness).pack()
+}
+
+/// Signs a fully sealed transaction for the CoBuild `SighashAll` layout, or `SighashAllOnly`
+/// without a message.
+///
+/// `input_cells` are the resolved cells and data of all the inputs.
+pub fn sign_cobuild_sighash_all(
+    tx: &TransactionView,
+    key: &Privkey,
+    input_cells: &[(CellOutput, Bytes)],
+    message: Option<&[u8]>,
+) -> Vec<u8> {
+    let mut blake2b = match message {
+        Some(message) => {
+            let mut blake2b = Blake2bBuilder::new(32)
+                .personal(b"ckb-tcob-sighash")
+                .build();
+            blake2b.update(message);
+            blake2b
+        }
+        None => Blake2bBuilder::new(32)
+            .personal(b"ckb-tcob-sgohash")
+            .build(),
+    };
+    blake2b.update(tx.hash().as_slice());
+    for (cell, data) in input_cells {
+        blake2b.update(cell.as_slice());
+        blake2b.update(&(data.len() as u32).to_le_bytes());
+        blake2b.update(data);
+    }
+    for witness in tx.witnesses().into_iter().skip(input_cells.len()) {
+        let witness = witness.raw_data();
+        blake2b.update(&(witness.len() as u32).to_le_bytes());
+        blake2b.update(&witness);
+    }
+    let mut signing_message = [0u8; 32];
+    blake2b.finalize(&mut signing_message);
+
+    // sign
+    let signing_message = H256::from(signing_message);
+    let sig = key.sign_recoverable(&signing_message).expect("sign");
+    sig.serialize()
+}
 
-    // set witness
-    tx.as_advanced_builder().witness(witness).build()
+pub fn cobuild_sighash_all_witness(message: Option<&[u8]>, seal: &[u8]) -> packed::Bytes {
+    let witness = match message {
+        Some(message) => {
+            let mut witness = WITNESS_LAYOUT_SIGHASH_ALL.to_le_bytes().to_vec();
+            witness.extend(molecule_table(&[message.to_vec(), molecule_bytes(seal)]));
+            witness
+        }
+        None => {
+            let mut witness = WITNESS_LAYOUT_SIGHASH_ALL_ONLY.to_le_bytes().to_vec();
+            witness.extend(molecule_table(&[molecule_bytes(seal)]));
+            witness
+        }
+    };
+    Bytes::from(witness).pack()
 }
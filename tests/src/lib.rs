@@ -5,6 +5,7 @@ use std::path::PathBuf;
 use std::str::FromStr;
 
 pub mod helper;
+pub mod mock_tx;
 
 #[cfg(test)]
 mod tests;
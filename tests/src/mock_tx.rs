@@ -0,0 +1,132 @@
+//! Mock transactions of `ckb-debugger`, replaying a test scenario outside of the tests for
+//! step debugging or cycle flamegraphs:
+//!
+//! ```sh
+//! ckb-debugger --tx-file <test>.json --cell-type input --cell-index 0 --script-group-type lock
+//! ```
+
+use ckb_mock_tx_types::{MockCellDep, MockInfo, MockInput, MockTransaction, ReprMockTransaction};
+use ckb_testtool::ckb_types::{
+    core::{DepType, TransactionView},
+    packed::{CellDep, OutPoint, OutPointVec},
+    prelude::*,
+};
+use ckb_testtool::context::Context;
+use ckb_traits::HeaderProvider;
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+
+const MOCK_TX_DIR_VAR: &str = "MOCK_TX_DIR";
+
+// mock transactions written by each test so far
+static DUMPS: Mutex<BTreeMap<String, usize>> = Mutex::new(BTreeMap::new());
+
+/// `tx` with the cells it consumes and depends on, such as the secp256k1 data, resolved
+/// from `context`. Dep groups are listed along with the cells they expand to. Header deps
+/// unknown to `context` are left out.
+pub fn build_mock_tx(context: &Context, tx: &TransactionView) -> ReprMockTransaction {
+    let cell = |out_point: &OutPoint| {
+        let (output, data) = context.cells.get(out_point).expect("cell");
+        let header = context
+            .transaction_infos
+            .get(out_point)
+            .map(|info| info.block_hash.clone());
+        (output.clone(), data.clone(), header)
+    };
+
+    let inputs = tx
+        .inputs()
+        .into_iter()
+        .map(|input| {
+            let (output, data, header) = cell(&input.previous_output());
+            MockInput {
+                input,
+                output,
+                data,
+                header,
+            }
+        })
+        .collect();
+    let mut cell_deps = Vec::new();
+    for cell_dep in tx.cell_deps() {
+        let (output, data, header) = cell(&cell_dep.out_point());
+        let expanded = if cell_dep.dep_type() == DepType::DepGroup.into() {
+            OutPointVec::from_slice(&data)
+                .expect("dep group")
+                .into_iter()
+                .collect()
+        } else {
+            Vec::new()
+        };
+        cell_deps.push(MockCellDep {
+            cell_dep,
+            output,
+            data,
+            header,
+        });
+        for out_point in expanded {
+            let (output, data, header) = cell(&out_point);
+            cell_deps.push(MockCellDep {
+                cell_dep: CellDep::new_builder()
+                    .out_point(out_point)
+                    .dep_type(DepType::Code.into())
+                    .build(),
+                output,
+                data,
+                header,
+            });
+        }
+    }
+    let header_deps = tx
+        .header_deps()
+        .into_iter()
+        .filter_map(|hash| context.get_header(&hash))
+        .collect();
+
+    MockTransaction {
+        mock_info: MockInfo {
+            inputs,
+            cell_deps,
+            header_deps,
+        },
+        tx: tx.data(),
+    }
+    .into()
+}
+
+/// Writes the mock transaction of `tx` as `<test>.json` into the directory set in
+/// `MOCK_TX_DIR`, if any. The transactions a test verifies after its first one are
+/// numbered `<test>-1.json`, `<test>-2.json` and so on.
+pub fn dump_mock_tx(context: &Context, tx: &TransactionView) {
+    let dir = match env::var_os(MOCK_TX_DIR_VAR) {
+        Some(dir) => PathBuf::from(dir),
+        None => return,
+    };
+    let test = thread::current()
+        .name()
+        .unwrap_or("main")
+        .replace("::", "-");
+    let count = {
+        let mut dumps = DUMPS.lock().expect("dumps");
+        let count = dumps.entry(test.clone()).or_insert(0);
+        *count += 1;
+        *count - 1
+    };
+    let file_name = match count {
+        0 => format!("{}.json", test),
+        n => format!("{}-{}.json", test, n),
+    };
+
+    let mock_tx = build_mock_tx(context, tx);
+    fs::create_dir_all(&dir).expect("create mock tx dir");
+    fs::write(
+        dir.join(file_name),
+        serde_json::to_string_pretty(&mock_tx).expect("serialize mock tx"),
+    )
+    .expect("write mock tx");
+}
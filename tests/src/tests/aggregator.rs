@@ -0,0 +1,264 @@
+use crate::helper::{
+    blake160, build_limit_order_psot, build_psot, build_signed_psot, cell_output, deploy,
+    otx_scope_extension, Deployment, SighashMode, MAX_CYCLES,
+};
+
+use super::*;
+
+use ckb_testtool::ckb_crypto::secp::Generator;
+use ckb_testtool::ckb_types::{bytes::Bytes, packed::*, prelude::*};
+use ckb_testtool::context::Context;
+use otx_sighash_lock_aggregator::{
+    Aggregate, Aggregator, Conflict, ConflictReason, Error as AggregateError, FeeCell,
+};
+use otx_sighash_lock_sdk::Psot;
+
+const FEE_RATE: u64 = 1000;
+const FEE_CELL_CAPACITY: u64 = 10_000_000_000;
+
+// a cell of the aggregator under an always success lock
+fn fee_cell(context: &mut Context, deployment: &Deployment) -> FeeCell {
+    let lock_script = deployment.always_success_script(context, &[]);
+    let cell_output = cell_output(FEE_CELL_CAPACITY, &lock_script, None);
+    let out_point = context.create_cell(cell_output.clone(), Bytes::new());
+
+    FeeCell {
+        input: CellInput::new_builder().previous_output(out_point).build(),
+        cell_output,
+        data: Bytes::new(),
+        cell_deps: vec![CellDep::new_builder()
+            .out_point(deployment.always_success_out_point.clone())
+            .build()],
+        witness: Default::default(),
+    }
+}
+
+#[test]
+fn test_aggregate() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let mut aggregator = Aggregator::new(fee_cell(&mut context, &deployment), FEE_RATE);
+    for extension in [Vec::new(), Vec::new()] {
+        let otx = build_signed_psot(
+            &mut context,
+            &deployment,
+            SighashMode::SingleAnyoneCanPay,
+            &extension,
+            None,
+        );
+        aggregator.add_otx(otx);
+    }
+    let otx = build_signed_psot(
+        &mut context,
+        &deployment,
+        SighashMode::AllAnyoneCanPay,
+        &otx_scope_extension(0, 1, 0, 1),
+        None,
+    );
+    aggregator.add_otx(otx);
+
+    let aggregate = aggregator.aggregate().expect("aggregate");
+    assert!(aggregate.conflicts.is_empty());
+    assert_eq!(aggregate.otxs, vec![0, 1, 2]);
+    // the fee input is last, its change as well
+    let tx = aggregate.psot.tx;
+    assert_eq!(tx.inputs().len(), 4);
+    assert_eq!(tx.outputs().len(), 4);
+    let change: u64 = tx.outputs().get(3).unwrap().capacity().unpack();
+    assert_eq!(change, FEE_CELL_CAPACITY - aggregate.fee);
+    let tx = context.complete_tx(tx);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_aggregate_conflicts() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let mut aggregator = Aggregator::new(fee_cell(&mut context, &deployment), FEE_RATE);
+    let single = build_signed_psot(
+        &mut context,
+        &deployment,
+        SighashMode::SingleAnyoneCanPay,
+        &[],
+        None,
+    );
+    aggregator.add_otx(single.clone());
+    // commits to all outputs of the transaction
+    let all = build_signed_psot(
+        &mut context,
+        &deployment,
+        SighashMode::AllAnyoneCanPay,
+        &[],
+        None,
+    );
+    aggregator.add_otx(all);
+    aggregator.add_otx(single.clone());
+    let mut unsigned = single;
+    unsigned.inputs[0].signed = false;
+    aggregator.add_otx(unsigned);
+
+    let aggregate = aggregator.aggregate().expect("aggregate");
+    assert_eq!(aggregate.otxs, vec![0]);
+    assert_eq!(
+        aggregate.conflicts,
+        vec![
+            Conflict {
+                otx: 1,
+                reason: ConflictReason::OutputsAdded(0),
+            },
+            Conflict {
+                otx: 2,
+                reason: ConflictReason::DoubleSpend(0),
+            },
+            Conflict {
+                otx: 3,
+                reason: ConflictReason::Incomplete(
+                    "signature status of input 0 does not match its witness lock".to_string()
+                ),
+            },
+        ]
+    );
+    let tx = context.complete_tx(aggregate.psot.tx);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+// aggregates the otxs `build` signs for the same output, first worth what they spend to learn
+// the fee, then leaving exactly the fee to the fee cell, as there is no change output
+fn aggregate_all_outputs<F>(context: &mut Context, deployment: &Deployment, build: F) -> Aggregate
+where
+    F: Fn(&mut Context, &Deployment, Vec<CellOutput>) -> Vec<Psot>,
+{
+    let receiver = deployment.always_success_script(context, &[42]);
+    let aggregate = |context: &mut Context, capacity: u64| {
+        let mut aggregator = Aggregator::new(fee_cell(context, deployment), FEE_RATE);
+        for otx in build(
+            context,
+            deployment,
+            vec![cell_output(capacity, &receiver, None)],
+        ) {
+            aggregator.add_otx(otx);
+        }
+        aggregator.aggregate()
+    };
+    let excess = match aggregate(context, 2000) {
+        Err(AggregateError::ExcessFee(excess)) => excess,
+        _ => panic!("the fee cell should bring more than the fee"),
+    };
+    aggregate(context, 2000 + excess).expect("aggregate")
+}
+
+#[test]
+fn test_aggregate_all_outputs() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    // both makers sign the same outputs
+    let aggregate =
+        aggregate_all_outputs(&mut context, &deployment, |context, deployment, outputs| {
+            (0..2)
+                .map(|_| {
+                    build_signed_psot(
+                        context,
+                        deployment,
+                        SighashMode::AllAnyoneCanPay,
+                        &[],
+                        Some(outputs.clone()),
+                    )
+                })
+                .collect()
+        });
+    assert!(aggregate.conflicts.is_empty());
+    assert_eq!(aggregate.otxs, vec![0, 1]);
+    // the signed outputs take no change
+    let tx = aggregate.psot.tx;
+    assert_eq!(tx.inputs().len(), 3);
+    assert_eq!(tx.outputs().len(), 1);
+    let tx = context.complete_tx(tx);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_aggregate_all_outputs_of_one_lock_group() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    // one maker signs two otxs with the same outputs, each signature covering the witnesses
+    // of the whole lock group
+    let privkey = Generator::random_privkey();
+    let pubkey_hash = blake160(&privkey.pubkey().expect("pubkey").serialize());
+    let aggregate =
+        aggregate_all_outputs(&mut context, &deployment, |context, deployment, outputs| {
+            let lock_script = deployment.lock_script(context, &pubkey_hash);
+            (0..2)
+                .map(|_| {
+                    let mut otx = build_psot(
+                        context,
+                        deployment,
+                        vec![(cell_output(1000, &lock_script, None), Bytes::new())],
+                        outputs
+                            .iter()
+                            .map(|output| (output.clone(), Bytes::new()))
+                            .collect(),
+                        SighashMode::AllAnyoneCanPay,
+                    );
+                    otx.sign(&privkey, 0, &[], &[]).expect("sign");
+                    otx
+                })
+                .collect()
+        });
+    assert_eq!(
+        aggregate.conflicts,
+        vec![Conflict {
+            otx: 1,
+            reason: ConflictReason::LockGroupShared(0),
+        }]
+    );
+    assert_eq!(aggregate.otxs, vec![0]);
+    let tx = context.complete_tx(aggregate.psot.tx);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_aggregate_limit_orders() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let mut aggregator = Aggregator::new(fee_cell(&mut context, &deployment), FEE_RATE);
+    let single = build_signed_psot(
+        &mut context,
+        &deployment,
+        SighashMode::SingleAnyoneCanPay,
+        &[],
+        None,
+    );
+    aggregator.add_otx(single);
+    // both orders have a payment output beyond their remainder
+    for _ in 0..2 {
+        let order = build_limit_order_psot(&mut context, &deployment);
+        aggregator.add_otx(order);
+    }
+
+    let aggregate = aggregator.aggregate().expect("aggregate");
+    assert_eq!(aggregate.otxs, vec![0, 1]);
+    assert_eq!(
+        aggregate.conflicts,
+        vec![Conflict {
+            otx: 2,
+            reason: ConflictReason::Unaligned(1),
+        }]
+    );
+    let tx = context.complete_tx(aggregate.psot.tx);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
@@ -0,0 +1,198 @@
+use crate::helper::{blake160, build_tx, cell_output, deploy, sign_sighash_single_acp, MAX_CYCLES};
+
+use super::*;
+
+use ckb_testtool::ckb_crypto::secp::Generator;
+use ckb_testtool::ckb_types::{bytes::Bytes, core::TransactionView};
+use ckb_testtool::context::Context;
+
+// the data of a UDT cell, the amount followed by `extra` bytes
+fn udt_data(amount: u128, extra: &[u8]) -> Bytes {
+    let mut data = amount.to_le_bytes().to_vec();
+    data.extend_from_slice(extra);
+    Bytes::from(data)
+}
+
+// builds a transaction in which an always-success cell pays into an anyone-can-pay UDT cell
+// of 1000 shannons and 500 UDT followed by `[7]`, which is recreated as `outputs`
+//
+// The lock args end with `minimums`, `[2, 2]` accepts payments of at least 10^2 shannons or
+// 10^2 UDT.
+fn build_payment_tx(
+    context: &mut Context,
+    minimums: &[u8],
+    outputs: Vec<(u64, Bytes)>,
+) -> TransactionView {
+    let deployment = deploy(context);
+    let privkey = Generator::random_privkey();
+    let pubkey_hash = blake160(&privkey.pubkey().expect("pubkey").serialize());
+
+    // prepare scripts
+    let mut args = pubkey_hash.to_vec();
+    args.extend_from_slice(minimums);
+    let lock_script = deployment.lock_script(context, &args);
+    let payer_lock_script = deployment.always_success_script(context, &[]);
+    let udt_type_script = deployment.always_success_script(context, &[42]);
+
+    let change = 6000 - outputs.iter().map(|(capacity, _)| capacity).sum::<u64>();
+    let mut outputs: Vec<_> = outputs
+        .into_iter()
+        .map(|(capacity, data)| {
+            (
+                cell_output(capacity, &lock_script, Some(&udt_type_script)),
+                data,
+            )
+        })
+        .collect();
+    outputs.push((cell_output(change, &payer_lock_script, None), Bytes::new()));
+    build_tx(
+        context,
+        &deployment,
+        vec![
+            (
+                cell_output(1000, &lock_script, Some(&udt_type_script)),
+                udt_data(500, &[7]),
+            ),
+            (cell_output(5000, &payer_lock_script, None), Bytes::new()),
+        ],
+        outputs,
+    )
+}
+
+#[test]
+fn test_anyone_can_pay_receive() {
+    let mut context = Context::default();
+    let tx = build_payment_tx(&mut context, &[2, 2], vec![(1100, udt_data(500, &[7]))]);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_anyone_can_pay_receive_udt() {
+    let mut context = Context::default();
+    let tx = build_payment_tx(&mut context, &[2, 2], vec![(1000, udt_data(600, &[7]))]);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_anyone_can_pay_below_minimum() {
+    let mut context = Context::default();
+    let tx = build_payment_tx(&mut context, &[2, 2], vec![(1099, udt_data(599, &[7]))]);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_OUTPUT_AMOUNT_NOT_ENOUGH);
+}
+
+#[test]
+fn test_anyone_can_pay_decrease() {
+    let mut context = Context::default();
+    let tx = build_payment_tx(&mut context, &[2, 2], vec![(900, udt_data(500, &[7]))]);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_OUTPUT_AMOUNT_NOT_ENOUGH);
+}
+
+#[test]
+fn test_anyone_can_pay_udt_decrease() {
+    let mut context = Context::default();
+    let tx = build_payment_tx(&mut context, &[2, 2], vec![(1200, udt_data(400, &[7]))]);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_OUTPUT_AMOUNT_NOT_ENOUGH);
+}
+
+#[test]
+fn test_anyone_can_pay_data_changed() {
+    let mut context = Context::default();
+    let tx = build_payment_tx(&mut context, &[2, 2], vec![(1100, udt_data(500, &[8]))]);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_OUTPUT_DATA_CHANGED);
+}
+
+#[test]
+fn test_anyone_can_pay_duplicated_outputs() {
+    let mut context = Context::default();
+    let tx = build_payment_tx(
+        &mut context,
+        &[2, 2],
+        vec![(1100, udt_data(500, &[7])), (1100, udt_data(500, &[7]))],
+    );
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_DUPLICATED_OUTPUTS);
+}
+
+#[test]
+fn test_anyone_can_pay_disabled() {
+    let mut context = Context::default();
+    let tx = build_payment_tx(&mut context, &[], vec![(1100, udt_data(500, &[7]))]);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_ANYONE_CAN_PAY_DISABLED);
+}
+
+// builds a transaction in which the owner sells a cell of 1000 shannons under
+// SINGLE|ANYONECANPAY, committing to the output at index 0, next to an anyone-can-pay cell
+// of 2000 shannons of the same lock, without a witness, receiving a payment
+fn build_committed_output_tx(context: &mut Context, outputs: Vec<u64>) -> TransactionView {
+    let deployment = deploy(context);
+    let privkey = Generator::random_privkey();
+    let pubkey_hash = blake160(&privkey.pubkey().expect("pubkey").serialize());
+
+    // prepare scripts
+    let mut args = pubkey_hash.to_vec();
+    args.push(2);
+    let lock_script = deployment.lock_script(context, &args);
+    let payer_lock_script = deployment.always_success_script(context, &[]);
+
+    let change = 8000 - outputs.iter().sum::<u64>();
+    let mut outputs: Vec<_> = outputs
+        .into_iter()
+        .map(|capacity| (cell_output(capacity, &lock_script, None), Bytes::new()))
+        .collect();
+    outputs.push((cell_output(change, &payer_lock_script, None), Bytes::new()));
+    let tx = build_tx(
+        context,
+        &deployment,
+        vec![
+            (cell_output(1000, &lock_script, None), Bytes::new()),
+            (cell_output(2000, &lock_script, None), Bytes::new()),
+            (cell_output(5000, &payer_lock_script, None), Bytes::new()),
+        ],
+        outputs,
+    );
+    sign_sighash_single_acp(tx, &privkey, 0)
+}
+
+#[test]
+fn test_anyone_can_pay_beside_committed_output() {
+    let mut context = Context::default();
+    let tx = build_committed_output_tx(&mut context, vec![1200, 2100]);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_anyone_can_pay_committed_output() {
+    // the only output of the lock is committed by the signed input
+    let mut context = Context::default();
+    let tx = build_committed_output_tx(&mut context, vec![2100]);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_NO_PAIR);
+}
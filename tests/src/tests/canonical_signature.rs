@@ -0,0 +1,83 @@
+use crate::helper::{sign_sighash_single_acp, Otx, MAX_CYCLES};
+
+use super::*;
+
+use ckb_testtool::ckb_types::{bytes::Bytes, core::TransactionView, packed::*, prelude::*};
+use ckb_testtool::context::Context;
+
+// secp256k1 group order
+const SECP256K1_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+
+// replaces `s` with `n - s` and flips the recovery id, which recovers the same pubkey
+pub(super) fn malleate(signature: &mut [u8]) {
+    let mut borrow = 0i16;
+    for i in (0..32).rev() {
+        let diff = SECP256K1_ORDER[i] as i16 - signature[32 + i] as i16 - borrow;
+        borrow = (diff < 0) as i16;
+        signature[32 + i] = diff.rem_euclid(256) as u8;
+    }
+    signature[64] ^= 1;
+}
+
+// the maker signs a SINGLE|ANYONECANPAY otx, whose signature `tamper` then rewrites
+fn build_signed_tx(context: &mut Context, tamper: fn(&mut [u8])) -> TransactionView {
+    let Otx { privkey, tx, .. } = Otx::new(context, &[]);
+
+    // sign
+    let tx = sign_sighash_single_acp(tx, &privkey, 0);
+
+    // tamper with the signature, which follows the mode byte
+    let witness = WitnessArgs::new_unchecked(tx.witnesses().get(0).unwrap().raw_data());
+    let mut witness_lock: Vec<u8> = witness.lock().to_opt().unwrap().unpack();
+    tamper(&mut witness_lock[1..66]);
+    let witness = witness
+        .as_builder()
+        .lock(Some(Bytes::from(witness_lock)).pack())
+        .build();
+    tx.as_advanced_builder()
+        .set_witnesses(vec![witness.as_bytes().pack()])
+        .build()
+}
+
+#[test]
+fn test_canonical_signature() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, |_| {});
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_high_s() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, malleate);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_HIGH_S);
+}
+
+#[test]
+fn test_invalid_recovery_id() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, |signature| signature[64] += 2);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_INVALID_RECOVERY_ID);
+}
+
+#[test]
+fn test_zero_s() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, |signature| signature[32..64].fill(0));
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_ZERO_SIGNATURE_SCALAR);
+}
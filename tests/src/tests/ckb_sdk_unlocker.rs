@@ -0,0 +1,222 @@
+use crate::helper::{
+    blake160, build_tx, cell_output, deploy, fee_limit_extension, udt_requirement, Otx, MAX_CYCLES,
+    SIGNATURE_SIZE,
+};
+
+use super::*;
+
+use ckb_sdk::{
+    traits::{OffchainTransactionDependencyProvider, SecpCkbRawKeySigner},
+    unlock::ScriptUnlocker,
+    ScriptGroup, ScriptGroupType,
+};
+use ckb_testtool::ckb_crypto::secp::{Generator, Privkey, SECP256K1};
+use ckb_testtool::ckb_types::{bytes::Bytes, core::TransactionView, packed::*, prelude::*, H256};
+use ckb_testtool::context::Context;
+use otx_sighash_lock_ckb_sdk::{OtxScriptSigner, OtxUnlocker, SighashMode};
+use secp256k1::{PublicKey, SecretKey};
+
+// args flag naming an identity cell
+const ARGS_IDENTITY: u8 = 0x01;
+// leaves out the anyone-can-pay minimums
+const ARGS_NO_MINIMUM: u8 = 0xff;
+
+// builds an otx with one input of the returned unlocker, signing with SINGLE|ANYONECANPAY
+// and the witness extension
+fn build_unsigned_tx(
+    context: &mut Context,
+    extension: Vec<u8>,
+) -> (TransactionView, OtxUnlocker, Script) {
+    // generate key pair
+    let secret_key = Generator::random_secret_key();
+    let signer = SecpCkbRawKeySigner::new_with_secret_keys(vec![secret_key]);
+    let unlocker = OtxUnlocker::new(
+        OtxScriptSigner::new(Box::new(signer), SighashMode::SingleAnyoneCanPay)
+            .extension(extension),
+    );
+
+    let Otx {
+        lock_script, tx, ..
+    } = Otx::with_privkey(context, Privkey::from(secret_key), &[]);
+    (tx, unlocker, lock_script)
+}
+
+fn pubkey_hash(secret_key: &SecretKey) -> [u8; 20] {
+    blake160(&PublicKey::from_secret_key(&SECP256K1, secret_key).serialize())
+}
+
+// fills the placeholder witnesses of the inputs locked by `lock_script`, then unlocks them
+// with the cell deps of the context
+fn unlock(
+    context: &Context,
+    tx: TransactionView,
+    unlocker: &OtxUnlocker,
+    lock_script: Script,
+) -> TransactionView {
+    let input_indices = (0..tx.inputs().len())
+        .filter(|&i| {
+            let (cell, _) = context
+                .get_cell(&tx.inputs().get(i).unwrap().previous_output())
+                .unwrap();
+            cell.lock() == lock_script
+        })
+        .collect::<Vec<_>>();
+    let script_group = ScriptGroup {
+        script: lock_script,
+        group_type: ScriptGroupType::Lock,
+        input_indices: input_indices.clone(),
+        output_indices: vec![],
+    };
+    let mut tx_dep_provider = OffchainTransactionDependencyProvider::default();
+    for cell_dep in tx.cell_deps() {
+        let out_point = cell_dep.out_point();
+        let tx_hash: H256 = out_point.tx_hash().unpack();
+        let index: u32 = out_point.index().unpack();
+        let cell = context.get_cell(&out_point).unwrap();
+        tx_dep_provider.cells.insert((tx_hash, index), cell);
+    }
+
+    let tx = unlocker
+        .fill_placeholder_witness(&tx, &script_group, &tx_dep_provider)
+        .expect("fill placeholder witness");
+    let placeholder_sizes = input_indices
+        .iter()
+        .map(|&i| witness_lock_size(&tx, i))
+        .collect::<Vec<_>>();
+    let tx = unlocker
+        .unlock(&tx, &script_group, &tx_dep_provider)
+        .expect("unlock");
+    for (&i, &placeholder_size) in input_indices.iter().zip(&placeholder_sizes) {
+        assert_eq!(witness_lock_size(&tx, i), placeholder_size);
+    }
+    tx
+}
+
+fn witness_lock_size(tx: &TransactionView, index: usize) -> usize {
+    let witness = WitnessArgs::new_unchecked(tx.witnesses().get(index).unwrap().raw_data());
+    witness.lock().to_opt().unwrap().raw_data().len()
+}
+
+#[test]
+fn test_ckb_sdk_unlocker() {
+    let mut context = Context::default();
+    let (tx, unlocker, lock_script) = build_unsigned_tx(&mut context, Vec::new());
+    let tx = unlock(&context, tx, &unlocker, lock_script);
+    assert_eq!(witness_lock_size(&tx, 0), 1 + SIGNATURE_SIZE);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_ckb_sdk_unlocker_with_extension() {
+    let mut context = Context::default();
+    let extension = fee_limit_extension(100);
+    let (tx, unlocker, lock_script) = build_unsigned_tx(&mut context, extension.clone());
+    let tx = unlock(&context, tx, &unlocker, lock_script);
+    assert_eq!(
+        witness_lock_size(&tx, 0),
+        1 + SIGNATURE_SIZE + extension.len()
+    );
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_ckb_sdk_unlocker_identity() {
+    let mut context = Context::default();
+    let identity_out_point = context.deploy_cell(Loader::default().load_binary("identity-type"));
+
+    // the signer holds the second key listed in the identity cell
+    let secret_key = Generator::random_secret_key();
+    let signer = SecpCkbRawKeySigner::new_with_secret_keys(vec![secret_key]);
+    let unlocker = OtxUnlocker::new(OtxScriptSigner::new(
+        Box::new(signer),
+        SighashMode::SingleAnyoneCanPay,
+    ));
+
+    // prepare identity cell
+    let identity_type_script = context
+        .build_script(&identity_out_point, Bytes::from(vec![7u8; 32]))
+        .expect("script");
+    let identity_cell_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .type_(Some(identity_type_script.clone()).pack())
+            .build(),
+        Bytes::from([[1u8; 20], pubkey_hash(&secret_key)].concat()),
+    );
+
+    // the key of the args is not listed
+    let mut args_fields = vec![ARGS_NO_MINIMUM, ARGS_NO_MINIMUM, ARGS_IDENTITY];
+    args_fields.extend_from_slice(identity_type_script.calc_script_hash().as_slice());
+    let Otx {
+        lock_script, tx, ..
+    } = Otx::new(&mut context, &args_fields);
+    let identity_dep = CellDep::new_builder()
+        .out_point(identity_cell_out_point)
+        .build();
+    let tx = tx.as_advanced_builder().cell_dep(identity_dep).build();
+    let tx = unlock(&context, tx, &unlocker, lock_script);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_ckb_sdk_unlocker_input_payloads() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let secret_key = Generator::random_secret_key();
+
+    // prepare scripts
+    let lock_script = deployment.lock_script(&mut context, &pubkey_hash(&secret_key));
+    let taker_lock_script = deployment.always_success_script(&mut context, &[]);
+    let udt_type_scripts = [
+        deployment.always_success_script(&mut context, &[42]),
+        deployment.always_success_script(&mut context, &[43]),
+    ];
+    let lock_hash: [u8; 32] = lock_script.calc_script_hash().unpack().into();
+    let udt = |amount: u128| Bytes::from(amount.to_le_bytes().to_vec());
+
+    // each input of the maker asks for 100 units of another UDT
+    let mut signer = OtxScriptSigner::new(
+        Box::new(SecpCkbRawKeySigner::new_with_secret_keys(vec![secret_key])),
+        SighashMode::SingleUdtAnyoneCanPay,
+    );
+    for (i, udt_type_script) in udt_type_scripts.iter().enumerate() {
+        let udt_type_hash: [u8; 32] = udt_type_script.calc_script_hash().unpack().into();
+        signer = signer.input_payload(i, udt_requirement(&lock_hash, 500, &udt_type_hash, 100));
+    }
+    let unlocker = OtxUnlocker::new(signer);
+
+    let tx = build_tx(
+        &mut context,
+        &deployment,
+        vec![
+            (cell_output(1000, &lock_script, None), Bytes::new()),
+            (cell_output(1000, &lock_script, None), Bytes::new()),
+            (cell_output(1000, &taker_lock_script, None), Bytes::new()),
+        ],
+        vec![
+            (
+                cell_output(500, &lock_script, Some(&udt_type_scripts[0])),
+                udt(100),
+            ),
+            (
+                cell_output(500, &lock_script, Some(&udt_type_scripts[1])),
+                udt(100),
+            ),
+            (cell_output(2000, &taker_lock_script, None), Bytes::new()),
+        ],
+    );
+    let tx = unlock(&context, tx, &unlocker, lock_script);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
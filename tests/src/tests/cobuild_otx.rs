@@ -0,0 +1,72 @@
+use crate::helper::{
+    cobuild_empty_message, cobuild_otx_start_witness, sign_cobuild_otx, Otx, OtxRange, MAX_CYCLES,
+};
+
+use super::*;
+
+use ckb_testtool::ckb_types::{core::TransactionView, prelude::*};
+use ckb_testtool::context::Context;
+
+// signs a CoBuild otx spending one input and creating one output
+fn build_signed_tx(context: &mut Context) -> TransactionView {
+    let Otx {
+        privkey,
+        lock_script,
+        input_cell,
+        tx,
+        ..
+    } = Otx::new(context, &[]);
+
+    // sign
+    let range = OtxRange {
+        start_input_cell: 0,
+        input_cells: 1,
+        start_output_cell: 0,
+        output_cells: 1,
+        start_cell_deps: 0,
+        cell_deps: tx.cell_deps().len(),
+        start_header_deps: 0,
+        header_deps: 0,
+    };
+    let otx_witness = sign_cobuild_otx(
+        &tx,
+        &privkey,
+        &lock_script.calc_script_hash(),
+        &range,
+        &[input_cell],
+        &cobuild_empty_message(),
+    );
+    tx.as_advanced_builder()
+        .set_witnesses(vec![cobuild_otx_start_witness(&range), otx_witness])
+        .build()
+}
+
+#[test]
+fn test_cobuild_otx() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_cobuild_otx_with_tampered_output() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context);
+
+    // the output capacity is changed after signing
+    let output = tx
+        .outputs()
+        .get(0)
+        .unwrap()
+        .as_builder()
+        .capacity(900u64.pack())
+        .build();
+    let tx = tx.as_advanced_builder().set_outputs(vec![output]).build();
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_WRONG_PUBKEY);
+}
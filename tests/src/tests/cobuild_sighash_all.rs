@@ -0,0 +1,75 @@
+use crate::helper::{
+    cobuild_empty_message, cobuild_sighash_all_witness, sign_cobuild_sighash_all, Otx, MAX_CYCLES,
+};
+
+use super::*;
+
+use ckb_testtool::context::Context;
+
+#[test]
+fn test_cobuild_sighash_all() {
+    let mut context = Context::default();
+    let Otx {
+        privkey,
+        input_cell,
+        tx,
+        ..
+    } = Otx::new(&mut context, &[]);
+
+    // sign
+    let message = cobuild_empty_message();
+    let seal = sign_cobuild_sighash_all(&tx, &privkey, &[input_cell], Some(&message));
+    let tx = tx
+        .as_advanced_builder()
+        .set_witnesses(vec![cobuild_sighash_all_witness(Some(&message), &seal)])
+        .build();
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_cobuild_sighash_all_only() {
+    let mut context = Context::default();
+    let Otx {
+        privkey,
+        input_cell,
+        tx,
+        ..
+    } = Otx::new(&mut context, &[]);
+
+    // sign
+    let seal = sign_cobuild_sighash_all(&tx, &privkey, &[input_cell], None);
+    let tx = tx
+        .as_advanced_builder()
+        .set_witnesses(vec![cobuild_sighash_all_witness(None, &seal)])
+        .build();
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_cobuild_sighash_all_with_dropped_message() {
+    let mut context = Context::default();
+    let Otx {
+        privkey,
+        input_cell,
+        tx,
+        ..
+    } = Otx::new(&mut context, &[]);
+
+    // the seal signed along with a message is moved into a SighashAllOnly witness
+    let message = cobuild_empty_message();
+    let seal = sign_cobuild_sighash_all(&tx, &privkey, &[input_cell], Some(&message));
+    let tx = tx
+        .as_advanced_builder()
+        .set_witnesses(vec![cobuild_sighash_all_witness(None, &seal)])
+        .build();
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_WRONG_PUBKEY);
+}
@@ -0,0 +1,83 @@
+use crate::helper::{
+    blake160, block_header, cell_output, delegation_extension,
+    sign_sighash_single_acp_with_extension, Otx, MAX_CYCLES,
+};
+
+use super::*;
+
+use ckb_testtool::ckb_crypto::secp::Generator;
+use ckb_testtool::ckb_types::{bytes::Bytes, core::TransactionView};
+use ckb_testtool::context::Context;
+
+// SINGLE|ANYONECANPAY
+const ALLOW_SINGLE_ACP: u8 = 1 << 2;
+// ALL|ANYONECANPAY
+const ALLOW_ALL_ACP: u8 = 1 << 0;
+
+// a session key signs a SINGLE|ANYONECANPAY otx sending `output_capacity` of the 1000
+// shannons input back to the lock, under a certificate of the owner
+fn build_signed_tx(
+    context: &mut Context,
+    allowed_modes: u8,
+    max_capacity_out: u64,
+    output_capacity: u64,
+) -> TransactionView {
+    let Otx {
+        privkey: owner_privkey,
+        tx,
+        ..
+    } = Otx::new(context, &[]).output(context, |lock_script| {
+        (
+            cell_output(output_capacity, lock_script, None),
+            Bytes::new(),
+        )
+    });
+    let session_privkey = Generator::random_privkey();
+    let session_pubkey_hash = blake160(&session_privkey.pubkey().expect("pubkey").serialize());
+
+    // prepare header
+    let header = block_header(100);
+    context.insert_header(header.clone());
+
+    let tx = tx.as_advanced_builder().header_dep(header.hash()).build();
+
+    // sign
+    let extension = delegation_extension(
+        &owner_privkey,
+        &session_pubkey_hash,
+        allowed_modes,
+        200,
+        max_capacity_out,
+    );
+    sign_sighash_single_acp_with_extension(tx, &session_privkey, 0, &extension)
+}
+
+#[test]
+fn test_delegated_session_key() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, ALLOW_SINGLE_ACP, 100, 900);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_delegated_session_key_with_mode_not_allowed() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, ALLOW_ALL_ACP, 100, 900);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_MODE_NOT_DELEGATED);
+}
+
+#[test]
+fn test_delegated_session_key_exceeding_capacity() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, ALLOW_SINGLE_ACP, 100, 800);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_DELEGATED_CAPACITY_EXCEEDED);
+}
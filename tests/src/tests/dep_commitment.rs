@@ -0,0 +1,52 @@
+use crate::helper::{
+    block_header, sign_sighash_single_acp_with_extension, Otx, EXTENSION_CELL_DEPS,
+    EXTENSION_HEADER_DEPS, MAX_CYCLES,
+};
+
+use super::*;
+
+use ckb_testtool::ckb_types::core::TransactionView;
+use ckb_testtool::context::Context;
+
+// signs a SINGLE|ANYONECANPAY otx committing to its cell deps and header deps
+fn build_signed_tx(context: &mut Context) -> TransactionView {
+    let Otx { privkey, tx, .. } = Otx::new(context, &[]);
+
+    // prepare header
+    let header = block_header(100);
+    context.insert_header(header.clone());
+
+    let tx = tx.as_advanced_builder().header_dep(header.hash()).build();
+
+    // sign
+    let extension = vec![EXTENSION_CELL_DEPS | EXTENSION_HEADER_DEPS];
+    sign_sighash_single_acp_with_extension(tx, &privkey, 0, &extension)
+}
+
+#[test]
+fn test_commit_to_deps() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_commit_to_deps_with_replaced_header_dep() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context);
+
+    // an aggregator swaps the header dep after signing
+    let header = block_header(101);
+    context.insert_header(header.clone());
+    let tx = tx
+        .as_advanced_builder()
+        .set_header_deps(vec![header.hash()])
+        .build();
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_WRONG_PUBKEY);
+}
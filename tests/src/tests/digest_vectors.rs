@@ -0,0 +1,183 @@
+use ckb_testtool::ckb_types::{
+    bytes::Bytes,
+    core::{DepType, ScriptHashType, TransactionBuilder, TransactionView},
+    packed::*,
+    prelude::*,
+};
+use otx_sighash_core::{
+    delegation_message, limit_order_intent_hash, signing_message, signing_preimage, Delegation,
+    WitnessLock,
+};
+use otx_sighash_lock_sdk::TxReader;
+
+// Messages of a fixed transaction in every mode, recorded before the preimage parts were
+// named. Any change to them breaks every signature already made.
+const SINGLE_ACP: &str = "e31219a752838c2c26f23989607f4398c81f0aa888e5e281f3c21a61adc422f4";
+const SINGLE_ACP_READABLE_DEPS: &str =
+    "efa53cb5498ca0ee64ee1db7aa8194e409b04547820e5c3694d6c119485a83a9";
+const SINGLE_UDT_ACP: &str = "1d38b9c385c3b3a31c59940f0576ce23e814e91495fefd5d08125802814b84f8";
+const SINGLE_UDT_ACP_READABLE: &str =
+    "4ba339f1fa5df82cadb2d8d09c2748a1fdc656ab9e9e2749079fb9594f6062b1";
+const ALL_ACP: &str = "17c74ab5a54c6a05fdf3d7987e94af9eae9e3dbc1b1a3774672e56470cca4ffe";
+const ALL_ACP_SCOPE_EXPIRY: &str =
+    "aedee3db158a598616c992583f5587a04af7f864746dba953d25b41a6800ca7c";
+const LIMIT_ORDER_CELL_DEPS: &str =
+    "16f9e123c68395b17965deb5c5475800f707206c9d702fd9ab527b313c2ab789";
+const DELEGATION: &str = "cf5025545681061a2815f1f60b7d7404c306156424ca3f005e983b14dfcdd99f";
+const INTENT_HASH: &str = "0cade56cac47c51f889075aa79d8a13a83965688051c6bc456ea631e568a5ca0";
+
+fn script(code_hash: u8, hash_type: ScriptHashType, args: Vec<u8>) -> Script {
+    Script::new_builder()
+        .code_hash([code_hash; 32].pack())
+        .hash_type(hash_type.into())
+        .args(Bytes::from(args).pack())
+        .build()
+}
+
+// three inputs, outputs and witnesses, the witness of `index` carrying `lock`
+fn build_tx(index: usize, lock: &[u8]) -> TransactionView {
+    let lock_a = script(0xaa, ScriptHashType::Type, vec![1; 20]);
+    let lock_b = script(0xbb, ScriptHashType::Type, vec![2; 20]);
+    let udt = script(0xcc, ScriptHashType::Data, vec![3; 32]);
+    let mut udt_data = 1000u128.to_le_bytes().to_vec();
+    udt_data.extend_from_slice(&[9, 9]);
+
+    let mut builder = TransactionBuilder::default()
+        .output(
+            CellOutput::new_builder()
+                .capacity(100_0000_0000u64.pack())
+                .lock(lock_a.clone())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(200_0000_0000u64.pack())
+                .lock(lock_b)
+                .type_(Some(udt).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(udt_data).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(300_0000_0000u64.pack())
+                .lock(lock_a)
+                .build(),
+        )
+        .output_data(Bytes::from(vec![0xde, 0xad]).pack())
+        .header_dep([0x70; 32].pack());
+    for (i, dep_type) in [DepType::Code, DepType::DepGroup].into_iter().enumerate() {
+        builder = builder.cell_dep(
+            CellDep::new_builder()
+                .out_point(OutPoint::new([0x60 + i as u8; 32].pack(), i as u32))
+                .dep_type(dep_type.into())
+                .build(),
+        );
+    }
+    for i in 0..3 {
+        let input = CellInput::new(OutPoint::new([0x50 + i as u8; 32].pack(), i), i as u64 * 7);
+        let output_type = Some(Bytes::from(vec![0x0f, i as u8])).pack();
+        let witness = if i as usize == index {
+            WitnessArgs::new_builder()
+                .lock(Some(Bytes::from(lock.to_vec())).pack())
+                .input_type(Some(Bytes::from(vec![0x0e])).pack())
+                .output_type(output_type)
+                .build()
+        } else {
+            WitnessArgs::new_builder().output_type(output_type).build()
+        };
+        builder = builder.input(input).witness(witness.as_bytes().pack());
+    }
+    builder.build()
+}
+
+fn witness_lock(mode: u8, payload: &[u8], extension: &[u8]) -> Vec<u8> {
+    let mut lock = vec![mode];
+    lock.extend_from_slice(&[0x5a; 65]);
+    lock.extend_from_slice(payload);
+    lock.extend_from_slice(extension);
+    lock
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[test]
+fn test_digest_vectors() {
+    let lock_b = script(0xbb, ScriptHashType::Type, vec![2; 20]);
+    let udt = script(0xcc, ScriptHashType::Data, vec![3; 32]);
+    let mut requirement = lock_b.calc_script_hash().as_slice().to_vec();
+    requirement.extend_from_slice(&150_0000_0000u64.to_le_bytes());
+    requirement.extend_from_slice(udt.calc_script_hash().as_slice());
+    requirement.extend_from_slice(&500u128.to_le_bytes());
+
+    let mut order = vec![0u8; 32];
+    order.extend_from_slice(udt.calc_script_hash().as_slice());
+    order.extend_from_slice(&3u64.to_le_bytes());
+    order.extend_from_slice(&2u64.to_le_bytes());
+    order.extend_from_slice(&1u32.to_le_bytes());
+
+    // expiry and a scope of inputs 1..3 and outputs 1..3
+    let mut scope = vec![0x01 | 0x08];
+    scope.extend_from_slice(&0x2000_0000_0000_0100u64.to_le_bytes());
+    for field in [1u32, 2, 1, 2] {
+        scope.extend_from_slice(&field.to_le_bytes());
+    }
+
+    let cases = [
+        (0, vec![0], witness_lock(0x83, &[], &[]), SINGLE_ACP),
+        (
+            1,
+            vec![1],
+            witness_lock(0x83, &[], &[0x86]),
+            SINGLE_ACP_READABLE_DEPS,
+        ),
+        (
+            2,
+            vec![2],
+            witness_lock(0x84, &requirement, &[]),
+            SINGLE_UDT_ACP,
+        ),
+        (
+            2,
+            vec![2],
+            witness_lock(0x84, &requirement, &[0x80]),
+            SINGLE_UDT_ACP_READABLE,
+        ),
+        (0, vec![0, 1, 2], witness_lock(0x81, &[], &[]), ALL_ACP),
+        (
+            1,
+            vec![0, 1, 2],
+            witness_lock(0x81, &[], &scope),
+            ALL_ACP_SCOPE_EXPIRY,
+        ),
+        (
+            0,
+            vec![0],
+            witness_lock(0x85, &order, &[0x02]),
+            LIMIT_ORDER_CELL_DEPS,
+        ),
+    ];
+    for (index, group_inputs, lock, expected) in cases {
+        let tx = build_tx(index, &lock);
+        let witness_lock = WitnessLock::from_slice(&lock).expect("witness lock");
+        let message =
+            signing_message(&TxReader(&tx), index, &group_inputs, &witness_lock).expect("message");
+        assert_eq!(hex(&message), expected, "mode {:#04x}", lock[0]);
+
+        let preimage = signing_preimage(&TxReader(&tx), index, &group_inputs, &witness_lock)
+            .expect("preimage");
+        assert_eq!(preimage.message, message);
+    }
+
+    let delegation = Delegation {
+        session_pubkey_hash: [0x11; 20],
+        allowed_modes: 0b101,
+        expiry: 0x2000_0000_0000_0100,
+        max_capacity_out: 500,
+        owner_signature: [0; 65],
+    };
+    assert_eq!(hex(&delegation_message(&delegation)), DELEGATION);
+    assert_eq!(hex(&limit_order_intent_hash(&[7; 80])), INTENT_HASH);
+}
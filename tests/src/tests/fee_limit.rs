@@ -0,0 +1,59 @@
+use crate::helper::{
+    cell_output, fee_limit_extension, sign_sighash_all_acp_with_extension,
+    sign_sighash_single_acp_with_extension, Otx, MAX_CYCLES,
+};
+
+use super::*;
+
+use ckb_testtool::ckb_types::{bytes::Bytes, core::TransactionView};
+use ckb_testtool::context::Context;
+
+// the aggregator prepares an otx returning `output_capacity` of the 1000 shannons input, which
+// the maker signs with ALL|ANYONECANPAY when `all`, or SINGLE|ANYONECANPAY, allowing a fee of
+// at most 100 shannons
+fn build_signed_tx(context: &mut Context, all: bool, output_capacity: u64) -> TransactionView {
+    let Otx { privkey, tx, .. } = Otx::new(context, &[]).output(context, |lock_script| {
+        (
+            cell_output(output_capacity, lock_script, None),
+            Bytes::new(),
+        )
+    });
+
+    // sign
+    let extension = fee_limit_extension(100);
+    if all {
+        sign_sighash_all_acp_with_extension(tx, &privkey, 0, &[0], &extension)
+    } else {
+        sign_sighash_single_acp_with_extension(tx, &privkey, 0, &extension)
+    }
+}
+
+#[test]
+fn test_fee_within_limit() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, false, 900);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_single_acp_over_charged() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, false, 899);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_FEE_LIMIT_EXCEEDED);
+}
+
+#[test]
+fn test_all_acp_over_charged() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, true, 500);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_FEE_LIMIT_EXCEEDED);
+}
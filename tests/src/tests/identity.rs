@@ -0,0 +1,177 @@
+use crate::helper::{
+    blake160, build_tx, cell_output, deploy, sign_sighash_single_acp, Otx, MAX_CYCLES,
+};
+
+use super::*;
+
+use ckb_testtool::ckb_crypto::secp::Generator;
+use ckb_testtool::ckb_hash::new_blake2b;
+use ckb_testtool::ckb_types::{bytes::Bytes, core::TransactionView, packed::*, prelude::*};
+use ckb_testtool::context::Context;
+
+// args flag naming an identity cell
+const ARGS_IDENTITY: u8 = 0x01;
+// leaves out the anyone-can-pay minimums
+const ARGS_NO_MINIMUM: u8 = 0xff;
+
+// signs a SINGLE|ANYONECANPAY otx with the second key listed in the identity cell, which is
+// put into the cell deps when `with_identity_dep`
+fn build_signed_tx(context: &mut Context, with_identity_dep: bool) -> TransactionView {
+    let identity_out_point = context.deploy_cell(Loader::default().load_binary("identity-type"));
+
+    // generate key pair, the key of the args has been rotated out of the identity cell
+    let privkey = Generator::random_privkey();
+    let pubkey_hash = blake160(&privkey.pubkey().expect("pubkey").serialize());
+
+    // prepare identity cell
+    let identity_type_script = context
+        .build_script(&identity_out_point, Bytes::from(vec![7u8; 32]))
+        .expect("script");
+    let identity_cell_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .type_(Some(identity_type_script.clone()).pack())
+            .build(),
+        Bytes::from([[1u8; 20], pubkey_hash].concat()),
+    );
+
+    let mut args_fields = vec![ARGS_NO_MINIMUM, ARGS_NO_MINIMUM, ARGS_IDENTITY];
+    args_fields.extend_from_slice(identity_type_script.calc_script_hash().as_slice());
+    let Otx { tx, .. } = Otx::new(context, &args_fields);
+    let tx = if with_identity_dep {
+        let identity_dep = CellDep::new_builder()
+            .out_point(identity_cell_out_point)
+            .build();
+        tx.as_advanced_builder().cell_dep(identity_dep).build()
+    } else {
+        tx
+    };
+
+    // sign
+    sign_sighash_single_acp(tx, &privkey, 0)
+}
+
+// creates an identity cell listing `identity_data`
+fn build_create_identity_tx(context: &mut Context, identity_data: Bytes) -> TransactionView {
+    let deployment = deploy(context);
+    let identity_out_point = context.deploy_cell(Loader::default().load_binary("identity-type"));
+
+    let lock_script = deployment.always_success_script(context, &[]);
+    let tx = build_tx(
+        context,
+        &deployment,
+        vec![(cell_output(1000, &lock_script, None), Bytes::new())],
+        vec![],
+    );
+
+    // the type id of the first output
+    let mut type_id = [0u8; 32];
+    let mut blake2b = new_blake2b();
+    blake2b.update(tx.inputs().get(0).expect("input").as_slice());
+    blake2b.update(&0u64.to_le_bytes());
+    blake2b.finalize(&mut type_id);
+    let identity_type_script = context
+        .build_script(&identity_out_point, Bytes::from(type_id.to_vec()))
+        .expect("script");
+
+    let tx = tx
+        .as_advanced_builder()
+        .output(cell_output(1000, &lock_script, Some(&identity_type_script)))
+        .output_data(identity_data.pack())
+        .build();
+    context.complete_tx(tx)
+}
+
+#[test]
+fn test_identity_with_rotated_key() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, true);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_identity_missing() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, false);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_IDENTITY_MISSING);
+}
+
+#[test]
+fn test_create_identity() {
+    let mut context = Context::default();
+    let tx = build_create_identity_tx(&mut context, Bytes::from(vec![1u8; 40]));
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_create_identity_with_invalid_data() {
+    let mut context = Context::default();
+    let tx = build_create_identity_tx(&mut context, Bytes::from(vec![1u8; 19]));
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_INVALID_DATA);
+}
+
+#[test]
+fn test_identity_missing_for_anyone_can_pay_payment() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+
+    // the lock accepts payments of at least 10^2 shannons, its identity cell is not needed
+    let mut args = vec![0u8; 20];
+    args.extend_from_slice(&[2, ARGS_NO_MINIMUM, ARGS_IDENTITY]);
+    args.extend_from_slice(&[7u8; 32]);
+    let lock_script = deployment.lock_script(&mut context, &args);
+    let payer_lock_script = deployment.always_success_script(&mut context, &[]);
+    let tx = build_tx(
+        &mut context,
+        &deployment,
+        vec![
+            (cell_output(1000, &lock_script, None), Bytes::new()),
+            (cell_output(5000, &payer_lock_script, None), Bytes::new()),
+        ],
+        vec![
+            (cell_output(1100, &lock_script, None), Bytes::new()),
+            (cell_output(4900, &payer_lock_script, None), Bytes::new()),
+        ],
+    );
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_destroy_identity() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let identity_out_point = context.deploy_cell(Loader::default().load_binary("identity-type"));
+
+    let lock_script = deployment.always_success_script(&mut context, &[]);
+    let identity_type_script = context
+        .build_script(&identity_out_point, Bytes::from(vec![7u8; 32]))
+        .expect("script");
+    let tx = build_tx(
+        &mut context,
+        &deployment,
+        vec![(
+            cell_output(1000, &lock_script, Some(&identity_type_script)),
+            Bytes::from(vec![1u8; 20]),
+        )],
+        vec![(cell_output(1000, &lock_script, None), Bytes::new())],
+    );
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_CELL_DESTROYED);
+}
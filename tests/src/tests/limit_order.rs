@@ -0,0 +1,303 @@
+use crate::helper::{
+    blake160, build_tx, cell_output, deploy, limit_order_intent, sign_limit_order,
+    sign_sighash_single_acp, sign_sighash_single_udt_acp, udt_requirement, MAX_CYCLES,
+};
+
+use super::*;
+
+use ckb_testtool::ckb_crypto::secp::Generator;
+use ckb_testtool::ckb_hash::blake2b_256;
+use ckb_testtool::ckb_types::{bytes::Bytes, core::TransactionView, prelude::*};
+use ckb_testtool::context::Context;
+
+// The maker sells 1000 UDT for 2 shannons each. The taker buys 300 UDT and pays `payment`
+// shannons, the remaining 700 UDT stay on sale in the recreated order cell.
+fn build_fill_tx(context: &mut Context, payment: u64) -> TransactionView {
+    let deployment = deploy(context);
+    let privkey = Generator::random_privkey();
+    let pubkey_hash = blake160(&privkey.pubkey().expect("pubkey").serialize());
+
+    // prepare scripts
+    let lock_script = deployment.lock_script(context, &pubkey_hash);
+    let taker_lock_script = deployment.always_success_script(context, &[]);
+    let udt_type_script = deployment.always_success_script(context, &[42]);
+    let udt_type_hash: [u8; 32] = udt_type_script.calc_script_hash().unpack().into();
+
+    // the order cell commits to the intent hash
+    let intent = limit_order_intent(&udt_type_hash, &[0u8; 32], 2, 1);
+    let intent_hash = blake2b_256(&intent);
+    let order_data = |amount: u128| {
+        let mut data = amount.to_le_bytes().to_vec();
+        data.extend_from_slice(&intent_hash);
+        Bytes::from(data)
+    };
+
+    let tx = build_tx(
+        context,
+        &deployment,
+        vec![
+            (
+                cell_output(500, &lock_script, Some(&udt_type_script)),
+                order_data(1000),
+            ),
+            (cell_output(2000, &taker_lock_script, None), Bytes::new()),
+        ],
+        vec![
+            (
+                cell_output(500, &lock_script, Some(&udt_type_script)),
+                order_data(700),
+            ),
+            (cell_output(payment, &lock_script, None), Bytes::new()),
+            (
+                cell_output(2000 - payment, &taker_lock_script, Some(&udt_type_script)),
+                Bytes::from(300u128.to_le_bytes().to_vec()),
+            ),
+        ],
+    );
+
+    // sign
+    sign_limit_order(tx, &privkey, &intent, 1, &[])
+}
+
+#[test]
+fn test_limit_order_partial_fill() {
+    let mut context = Context::default();
+    let tx = build_fill_tx(&mut context, 600);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_limit_order_price_not_met() {
+    let mut context = Context::default();
+    let tx = build_fill_tx(&mut context, 599);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_PRICE_NOT_MET);
+}
+
+#[test]
+fn test_limit_order_ckb_full_fill() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let privkey = Generator::random_privkey();
+    let pubkey_hash = blake160(&privkey.pubkey().expect("pubkey").serialize());
+
+    // prepare scripts
+    let lock_script = deployment.lock_script(&mut context, &pubkey_hash);
+    let taker_lock_script = deployment.always_success_script(&mut context, &[]);
+    let udt_type_script = deployment.always_success_script(&mut context, &[42]);
+    let udt_type_hash: [u8; 32] = udt_type_script.calc_script_hash().unpack().into();
+    let udt = |amount: u128| Bytes::from(amount.to_le_bytes().to_vec());
+
+    // the maker sells 1000 shannons for 1 UDT per 10 shannons, the taker buys them all
+    let intent = limit_order_intent(&[0u8; 32], &udt_type_hash, 1, 10);
+    let tx = build_tx(
+        &mut context,
+        &deployment,
+        vec![
+            (
+                cell_output(1000, &lock_script, None),
+                Bytes::from(blake2b_256(&intent).to_vec()),
+            ),
+            (
+                cell_output(1000, &taker_lock_script, Some(&udt_type_script)),
+                udt(300),
+            ),
+        ],
+        vec![
+            (
+                cell_output(1800, &taker_lock_script, Some(&udt_type_script)),
+                udt(200),
+            ),
+            (
+                cell_output(200, &lock_script, Some(&udt_type_script)),
+                udt(100),
+            ),
+        ],
+    );
+
+    // sign
+    let tx = sign_limit_order(tx, &privkey, &intent, 1, &[]);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_limit_order_payment_as_anyone_can_pay_top_up() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let privkey = Generator::random_privkey();
+    let pubkey_hash = blake160(&privkey.pubkey().expect("pubkey").serialize());
+
+    // prepare scripts, the maker also accepts payments of at least 10^2 shannons
+    let mut args = pubkey_hash.to_vec();
+    args.push(2);
+    let lock_script = deployment.lock_script(&mut context, &args);
+    let taker_lock_script = deployment.always_success_script(&mut context, &[]);
+    let udt_type_script = deployment.always_success_script(&mut context, &[42]);
+    let udt_type_hash: [u8; 32] = udt_type_script.calc_script_hash().unpack().into();
+
+    let intent = limit_order_intent(&udt_type_hash, &[0u8; 32], 2, 1);
+    let intent_hash = blake2b_256(&intent);
+    let order_data = |amount: u128| {
+        let mut data = amount.to_le_bytes().to_vec();
+        data.extend_from_slice(&intent_hash);
+        Bytes::from(data)
+    };
+
+    // the payment of 600 shannons for 300 UDT would also top up the anyone-can-pay cell of
+    // 400 shannons, which the taker takes
+    let tx = build_tx(
+        &mut context,
+        &deployment,
+        vec![
+            (
+                cell_output(500, &lock_script, Some(&udt_type_script)),
+                order_data(1000),
+            ),
+            (cell_output(2000, &taker_lock_script, None), Bytes::new()),
+            (cell_output(400, &lock_script, None), Bytes::new()),
+        ],
+        vec![
+            (
+                cell_output(500, &lock_script, Some(&udt_type_script)),
+                order_data(700),
+            ),
+            (cell_output(600, &lock_script, None), Bytes::new()),
+            (
+                cell_output(1800, &taker_lock_script, Some(&udt_type_script)),
+                Bytes::from(300u128.to_le_bytes().to_vec()),
+            ),
+        ],
+    );
+
+    // sign
+    let tx = sign_limit_order(tx, &privkey, &intent, 1, &[]);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_NO_PAIR);
+}
+
+#[test]
+fn test_limit_order_payment_committed_by_single_anyonecanpay() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let privkey = Generator::random_privkey();
+    let pubkey_hash = blake160(&privkey.pubkey().expect("pubkey").serialize());
+
+    // prepare scripts
+    let lock_script = deployment.lock_script(&mut context, &pubkey_hash);
+    let taker_lock_script = deployment.always_success_script(&mut context, &[]);
+    let udt_type_script = deployment.always_success_script(&mut context, &[42]);
+    let udt_type_hash: [u8; 32] = udt_type_script.calc_script_hash().unpack().into();
+
+    let intent = limit_order_intent(&udt_type_hash, &[0u8; 32], 2, 1);
+    let intent_hash = blake2b_256(&intent);
+    let order_data = |amount: u128| {
+        let mut data = amount.to_le_bytes().to_vec();
+        data.extend_from_slice(&intent_hash);
+        Bytes::from(data)
+    };
+
+    // the payment of 600 shannons for 300 UDT is the output committed by the
+    // SINGLE|ANYONECANPAY signature of the first input
+    let tx = build_tx(
+        &mut context,
+        &deployment,
+        vec![
+            (cell_output(1000, &lock_script, None), Bytes::new()),
+            (
+                cell_output(500, &lock_script, Some(&udt_type_script)),
+                order_data(1000),
+            ),
+            (cell_output(2000, &taker_lock_script, None), Bytes::new()),
+        ],
+        vec![
+            (cell_output(600, &lock_script, None), Bytes::new()),
+            (
+                cell_output(500, &lock_script, Some(&udt_type_script)),
+                order_data(700),
+            ),
+            (
+                cell_output(2400, &taker_lock_script, Some(&udt_type_script)),
+                Bytes::from(300u128.to_le_bytes().to_vec()),
+            ),
+        ],
+    );
+
+    // sign
+    let tx = sign_sighash_single_acp(tx, &privkey, 0);
+    let tx = sign_limit_order(tx, &privkey, &intent, 0, &[]);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_OUTPUT_ALREADY_CLAIMED);
+}
+
+#[test]
+fn test_limit_order_payment_counted_by_another_group() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let maker_privkey = Generator::random_privkey();
+    let maker_pubkey_hash = blake160(&maker_privkey.pubkey().expect("pubkey").serialize());
+    let other_privkey = Generator::random_privkey();
+    let other_pubkey_hash = blake160(&other_privkey.pubkey().expect("pubkey").serialize());
+
+    // prepare scripts
+    let lock_script = deployment.lock_script(&mut context, &maker_pubkey_hash);
+    let other_lock_script = deployment.lock_script(&mut context, &other_pubkey_hash);
+    let taker_lock_script = deployment.always_success_script(&mut context, &[]);
+    let udt_type_script = deployment.always_success_script(&mut context, &[42]);
+    let lock_hash: [u8; 32] = lock_script.calc_script_hash().unpack().into();
+    let udt_type_hash: [u8; 32] = udt_type_script.calc_script_hash().unpack().into();
+
+    let intent = limit_order_intent(&udt_type_hash, &[0u8; 32], 2, 1);
+    let intent_hash = blake2b_256(&intent);
+    let order_data = |amount: u128| {
+        let mut data = amount.to_le_bytes().to_vec();
+        data.extend_from_slice(&intent_hash);
+        Bytes::from(data)
+    };
+
+    // the cell of another lock group asks for 600 shannons to the maker, which only the
+    // payment of the limit order provides
+    let tx = build_tx(
+        &mut context,
+        &deployment,
+        vec![
+            (
+                cell_output(500, &lock_script, Some(&udt_type_script)),
+                order_data(1000),
+            ),
+            (cell_output(1000, &other_lock_script, None), Bytes::new()),
+            (cell_output(2000, &taker_lock_script, None), Bytes::new()),
+        ],
+        vec![
+            (
+                cell_output(500, &lock_script, Some(&udt_type_script)),
+                order_data(700),
+            ),
+            (cell_output(600, &lock_script, None), Bytes::new()),
+            (
+                cell_output(2400, &taker_lock_script, Some(&udt_type_script)),
+                Bytes::from(300u128.to_le_bytes().to_vec()),
+            ),
+        ],
+    );
+
+    // sign
+    let tx = sign_limit_order(tx, &maker_privkey, &intent, 1, &[]);
+    let payload = udt_requirement(&lock_hash, 600, &[0u8; 32], 0);
+    let tx = sign_sighash_single_udt_acp(tx, &other_privkey, 1, &payload);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_OUTPUT_AMOUNT_NOT_ENOUGH);
+}
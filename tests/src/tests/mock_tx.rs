@@ -0,0 +1,123 @@
+use crate::helper::{blake160, block_header, sign_sighash_single_acp, MAX_CYCLES};
+use crate::mock_tx::build_mock_tx;
+
+use super::*;
+
+use ckb_system_scripts::BUNDLED_CELL;
+use ckb_testtool::ckb_crypto::secp::Generator;
+use ckb_testtool::ckb_types::{
+    bytes::Bytes,
+    core::{DepType, TransactionBuilder},
+    packed::*,
+    prelude::*,
+};
+use ckb_testtool::context::Context;
+use otx_sighash_lock_cli::verify;
+
+use std::fs;
+
+#[test]
+fn test_mock_tx_resolves_deps() {
+    // generate key pair
+    let privkey = Generator::random_privkey();
+    let pubkey = privkey.pubkey().expect("pubkey");
+    let pubkey_hash = blake160(&pubkey.serialize());
+
+    // deploy contract
+    let mut context = Context::default();
+    let contract_bin: Bytes = Loader::default().load_binary("otx-sighash-lock");
+    let out_point = context.deploy_cell(contract_bin);
+
+    // deploy secp256k1 lib and data behind a dep group
+    let secp256k1_bin: Bytes =
+        fs::read("../ckb-miscellaneous-scripts/build/secp256k1_blake2b_sighash_all_dual")
+            .expect("load secp256k1")
+            .into();
+    let secp256k1_out_point = context.deploy_cell(secp256k1_bin);
+    let secp256k1_data_bin = BUNDLED_CELL.get("specs/cells/secp256k1_data").unwrap();
+    let secp256k1_data_out_point = context.deploy_cell(secp256k1_data_bin.to_vec().into());
+    let dep_group_data = OutPointVec::new_builder()
+        .push(secp256k1_out_point.clone())
+        .push(secp256k1_data_out_point.clone())
+        .build();
+    let dep_group_out_point = context.deploy_cell(dep_group_data.as_bytes());
+    let dep_group_dep = CellDep::new_builder()
+        .out_point(dep_group_out_point)
+        .dep_type(DepType::DepGroup.into())
+        .build();
+
+    // prepare cells, the input committed in a block
+    let header = block_header(100);
+    context.insert_header(header.clone());
+    let lock_script = context
+        .build_script(&out_point, pubkey_hash.to_vec().into())
+        .expect("script");
+    let input_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+    context.link_cell_with_block(input_out_point.clone(), header.hash(), 0);
+    let input = CellInput::new_builder()
+        .previous_output(input_out_point.clone())
+        .build();
+    let output = CellOutput::new_builder()
+        .capacity(1000u64.pack())
+        .lock(lock_script)
+        .build();
+
+    // build transaction
+    let tx = TransactionBuilder::default()
+        .input(input)
+        .output(output)
+        .output_data(Bytes::new().pack())
+        .cell_dep(dep_group_dep.clone())
+        .header_dep(header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+    let tx = sign_sighash_single_acp(tx, &privkey, 0);
+
+    let mock_tx = build_mock_tx(&context, &tx);
+    let mock_info = &mock_tx.mock_info;
+    assert_eq!(mock_info.inputs.len(), 1);
+    assert_eq!(
+        OutPoint::from(mock_info.inputs[0].input.previous_output.clone()),
+        input_out_point
+    );
+    assert_eq!(
+        mock_info.inputs[0].header.clone().map(|hash| hash.pack()),
+        Some(header.hash())
+    );
+    assert_eq!(mock_info.header_deps.len(), 1);
+
+    // the dep group comes first, then the cells it expands to and the contract
+    let cell_deps: Vec<CellDep> = mock_info
+        .cell_deps
+        .iter()
+        .map(|cell_dep| cell_dep.cell_dep.clone().into())
+        .collect();
+    let code_dep = |out_point: &OutPoint| {
+        CellDep::new_builder()
+            .out_point(out_point.clone())
+            .dep_type(DepType::Code.into())
+            .build()
+    };
+    for cell_dep in [
+        dep_group_dep,
+        code_dep(&secp256k1_out_point),
+        code_dep(&secp256k1_data_out_point),
+        code_dep(&out_point),
+    ] {
+        assert!(cell_deps.contains(&cell_dep));
+    }
+    assert_eq!(
+        mock_info.cell_deps[0].data.clone().into_bytes(),
+        dep_group_data.as_bytes()
+    );
+
+    // replays outside of the context
+    let cycles = verify(mock_tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
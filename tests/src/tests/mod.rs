@@ -1,16 +1,78 @@
 use super::*;
 use crate::helper::MAX_CYCLES;
+use crate::mock_tx::dump_mock_tx;
 
+mod aggregator;
+mod anyone_can_pay;
+mod canonical_signature;
+mod ckb_sdk_unlocker;
+mod cobuild_otx;
+mod cobuild_sighash_all;
+mod delegation;
+mod dep_commitment;
+mod digest_vectors;
+mod fee_limit;
+mod identity;
+mod limit_order;
+mod mock_tx;
+mod native_verifier;
+mod otx_lock_cli;
+mod otx_pool;
+mod otx_scope;
+mod preimage_inspector;
+mod psot;
+mod readable_preimage;
+mod recovery;
+mod revocation;
 mod secp256k1_blake2b_sighash_all;
 mod sighash_all_anyonecanpay;
 mod sighash_single_anyonecanpay;
+mod sighash_single_udt_anyonecanpay;
+mod signature_expiry;
+mod witness_fields;
 
 use ckb_testtool::ckb_error::Error;
-use ckb_testtool::ckb_types::{bytes::Bytes, core::TransactionBuilder, packed::*, prelude::*};
+use ckb_testtool::ckb_types::{
+    bytes::Bytes,
+    core::{Cycle, TransactionBuilder, TransactionView},
+    packed::*,
+    prelude::*,
+};
 use ckb_testtool::context::Context;
 
 // error numbers
 const ERROR_EMPTY_ARGS: i8 = 2;
+const ERROR_WRONG_PUBKEY: i8 = 7;
+const ERROR_NO_PAIR: i8 = 11;
+const ERROR_DUPLICATED_OUTPUTS: i8 = 13;
+const ERROR_OUTPUT_AMOUNT_NOT_ENOUGH: i8 = 14;
+const ERROR_OUTPUT_DATA_CHANGED: i8 = 15;
+const ERROR_INVALID_ORDER_CELL: i8 = 17;
+const ERROR_PRICE_NOT_MET: i8 = 19;
+const ERROR_OUTPUT_ALREADY_CLAIMED: i8 = 20;
+const ERROR_SIGNATURE_EXPIRED: i8 = 21;
+const ERROR_HEADER_DEP_MISSING: i8 = 22;
+const ERROR_INVALID_OTX_SCOPE: i8 = 28;
+const ERROR_MODE_NOT_DELEGATED: i8 = 29;
+const ERROR_DELEGATED_CAPACITY_EXCEEDED: i8 = 30;
+const ERROR_IDENTITY_MISSING: i8 = 31;
+const ERROR_RECOVERY_DELAY_NOT_MET: i8 = 34;
+const ERROR_SIGNATURE_REVOKED: i8 = 36;
+const ERROR_FEE_LIMIT_EXCEEDED: i8 = 37;
+const ERROR_HIGH_S: i8 = 38;
+const ERROR_INVALID_RECOVERY_ID: i8 = 39;
+const ERROR_ZERO_SIGNATURE_SCALAR: i8 = 40;
+const ERROR_ANYONE_CAN_PAY_DISABLED: i8 = 41;
+
+// identity-type and revocation-type, see type-id-guard
+const ERROR_INVALID_DATA: i8 = 7;
+const ERROR_CELL_DESTROYED: i8 = 8;
+
+// verifies `tx`, dumping its mock transaction first when asked, see `dump_mock_tx`
+fn verify_tx(context: &Context, tx: &TransactionView, max_cycles: u64) -> Result<Cycle, Error> {
+    dump_mock_tx(context, tx);
+    context.verify_tx(tx, max_cycles)
+}
 
 fn assert_script_error(err: Error, err_code: i8) {
     let error_string = err.to_string();
@@ -67,6 +129,6 @@ fn test_empty_args() {
     let tx = context.complete_tx(tx);
 
     // run
-    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
     assert_script_error(err, ERROR_EMPTY_ARGS);
 }
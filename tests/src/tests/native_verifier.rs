@@ -0,0 +1,629 @@
+use super::canonical_signature::malleate;
+use crate::helper::{
+    blake160, block_header, build_tx, cell_output, cobuild_empty_message,
+    cobuild_otx_start_witness, cobuild_sighash_all_witness, deploy, limit_order_intent,
+    sign_cobuild_otx, sign_cobuild_sighash_all, sign_secp256k1_blake2b_sighash_all,
+    sign_witness_lock, udt_requirement, OtxRange, SighashMode, MAX_CYCLES, SIGNATURE_SIZE,
+};
+
+use super::*;
+
+use ckb_testtool::ckb_crypto::secp::Privkey;
+use ckb_testtool::ckb_hash::blake2b_256;
+use ckb_testtool::ckb_types::{
+    bytes::Bytes,
+    core::{HeaderView, ScriptHashType, TransactionView},
+    packed::*,
+    prelude::*,
+};
+use ckb_testtool::context::Context;
+use otx_sighash_core::{
+    ARGS_IDENTITY, ARGS_NO_MINIMUM, ARGS_RECOVERY, ARGS_REVOCATION, DELEGATION_UNLIMITED_CAPACITY,
+    REVOCATION_PREFIX_SIZE,
+};
+use otx_sighash_lock_sdk::{ExtensionBuilder, OtxSigner};
+use otx_sighash_lock_verifier::{OtxVerifier, ResolvedTx};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use std::ops::Range;
+
+const CASES: usize = 128;
+const HEADER_NUMBER: u64 = 100;
+// relative `since` in block numbers
+const SINCE_RELATIVE_BLOCK_NUMBER: u64 = 0x80 << 56;
+const RECOVERY_DELAY: u64 = 10;
+
+#[derive(Debug, Clone, Copy)]
+enum Mode {
+    SighashAll,
+    CobuildSighashAll,
+    CobuildOtx,
+    // every input signs its own witness lock
+    WitnessLock(SighashMode),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Mutation {
+    None,
+    ModeByte(u8),
+    FlipSignatureByte(usize),
+    Malleate,
+    RecoveryId(u8),
+    Truncate(usize),
+    LowerOutputCapacity,
+    DropHeaderDeps,
+    EmptyWitness,
+    GarbageWitness(usize),
+}
+
+#[derive(Debug)]
+struct DelegationCase {
+    allowed_modes: u8,
+    expiry: u64,
+    max_capacity_out: u64,
+}
+
+#[derive(Debug)]
+struct Case {
+    mode: Mode,
+    inputs: usize,
+    // anyone-can-pay minimum in the lock args
+    min_ckb: Option<u8>,
+    expiry: Option<u64>,
+    max_fee: Option<u64>,
+    header_dep: bool,
+    // whether the identity cell lists the owner key
+    identity: Option<bool>,
+    // age of the inputs in blocks, the recovery key signing in place of the owner
+    recovery: Option<u64>,
+    // the owner, or the recovery key, delegating to a session key
+    delegation: Option<DelegationCase>,
+    // whether the revocation cell lists the first signature
+    revocation: Option<bool>,
+    mutation: Mutation,
+}
+
+impl Case {
+    fn random(rng: &mut StdRng) -> Case {
+        let mode = match rng.gen_range(0..7) {
+            0 => Mode::SighashAll,
+            1 => Mode::CobuildSighashAll,
+            2 => Mode::CobuildOtx,
+            3 => Mode::WitnessLock(SighashMode::SingleAnyoneCanPay),
+            4 => Mode::WitnessLock(SighashMode::AllAnyoneCanPay),
+            5 => Mode::WitnessLock(SighashMode::SingleUdtAnyoneCanPay),
+            _ => Mode::WitnessLock(SighashMode::LimitOrder),
+        };
+        // the witness extension only exists in the witness lock modes
+        let extension = matches!(mode, Mode::WitnessLock(_));
+        let mutation = match rng.gen_range(0..10) {
+            0 => Mutation::None,
+            1 => Mutation::ModeByte(
+                [0x00, 0x01, 0x02, 0x03, 0x81, 0x82, 0x83, 0x84, 0x85, 0x86][rng.gen_range(0..10)],
+            ),
+            2 => Mutation::FlipSignatureByte(rng.gen_range(0..65)),
+            3 => Mutation::Malleate,
+            4 => Mutation::RecoveryId(rng.gen_range(2..=255)),
+            5 => Mutation::Truncate(rng.gen_range(1..80)),
+            6 => Mutation::LowerOutputCapacity,
+            7 => Mutation::DropHeaderDeps,
+            8 => Mutation::EmptyWitness,
+            _ => Mutation::GarbageWitness(rng.gen_range(1..40)),
+        };
+        Case {
+            mode,
+            inputs: rng.gen_range(1..=2),
+            min_ckb: if rng.gen_bool(0.5) {
+                Some(rng.gen_range(0..3))
+            } else {
+                None
+            },
+            expiry: if extension && rng.gen_bool(0.3) {
+                Some(rng.gen_range(HEADER_NUMBER - 5..HEADER_NUMBER + 5))
+            } else {
+                None
+            },
+            max_fee: if extension && rng.gen_bool(0.3) {
+                Some(rng.gen_range(0..200))
+            } else {
+                None
+            },
+            header_dep: rng.gen_bool(0.5),
+            identity: if rng.gen_bool(0.2) {
+                Some(rng.gen_bool(0.8))
+            } else {
+                None
+            },
+            recovery: if extension && rng.gen_bool(0.2) {
+                Some(rng.gen_range(RECOVERY_DELAY - 2..RECOVERY_DELAY + 3))
+            } else {
+                None
+            },
+            delegation: if extension && rng.gen_bool(0.2) {
+                Some(DelegationCase {
+                    allowed_modes: rng.gen(),
+                    expiry: rng.gen_range(HEADER_NUMBER - 5..HEADER_NUMBER + 5),
+                    max_capacity_out: if rng.gen_bool(0.5) {
+                        DELEGATION_UNLIMITED_CAPACITY
+                    } else {
+                        rng.gen_range(0..400)
+                    },
+                })
+            } else {
+                None
+            },
+            revocation: if rng.gen_bool(0.2) {
+                Some(rng.gen_bool(0.5))
+            } else {
+                None
+            },
+            mutation,
+        }
+    }
+}
+
+// keys are drawn from the seeded rng as well, so that a failing case can be replayed
+fn random_privkey(rng: &mut StdRng) -> Privkey {
+    Privkey::from_slice(&rng.gen::<[u8; 32]>())
+}
+
+// builds the transaction of `case`, each input paying a random fee, signs it then mutates it
+fn build_signed_tx(
+    context: &mut Context,
+    rng: &mut StdRng,
+    case: &Case,
+) -> (TransactionView, Vec<HeaderView>, Script) {
+    let deployment = deploy(context);
+    let owner_privkey = random_privkey(rng);
+    let owner_pubkey_hash = blake160(&owner_privkey.pubkey().expect("pubkey").serialize());
+
+    // cell deps are not run, any type script names the identity and revocation cells
+    let always_success_script = deployment.always_success_script(context, &[]);
+    let mut cell_deps = Vec::new();
+    let mut flags = 0;
+    let mut fields = Vec::new();
+    if let Some(listed) = case.identity {
+        // the first key has been rotated out
+        let old_privkey = random_privkey(rng);
+        let mut identity_data =
+            blake160(&old_privkey.pubkey().expect("pubkey").serialize()).to_vec();
+        if listed {
+            identity_data.extend_from_slice(&owner_pubkey_hash);
+        }
+        let identity_type_script = deployment.always_success_script(context, &[7]);
+        let identity_cell = cell_output(1000, &always_success_script, Some(&identity_type_script));
+        cell_deps.push(context.create_cell(identity_cell, Bytes::from(identity_data)));
+        flags |= ARGS_IDENTITY;
+        fields.extend_from_slice(identity_type_script.calc_script_hash().as_slice());
+    }
+    let recovery_privkey = random_privkey(rng);
+    if case.recovery.is_some() {
+        flags |= ARGS_RECOVERY;
+        fields.extend_from_slice(&blake160(
+            &recovery_privkey.pubkey().expect("pubkey").serialize(),
+        ));
+        fields.extend_from_slice(&(SINCE_RELATIVE_BLOCK_NUMBER | RECOVERY_DELAY).to_le_bytes());
+    }
+    // the revocation cell is in the cell deps from the start, as sealing modes sign them
+    let mut revocation_cell = None;
+    if case.revocation.is_some() {
+        let revocation_type_script = deployment.always_success_script(context, &[9]);
+        let cell = cell_output(1000, &always_success_script, Some(&revocation_type_script));
+        let out_point = context.create_cell(cell.clone(), Bytes::new());
+        cell_deps.push(out_point.clone());
+        revocation_cell = Some((out_point, cell));
+        flags |= ARGS_REVOCATION;
+        fields.extend_from_slice(revocation_type_script.calc_script_hash().as_slice());
+    }
+
+    let mut args = owner_pubkey_hash.to_vec();
+    if flags != 0 {
+        args.push(case.min_ckb.unwrap_or(ARGS_NO_MINIMUM));
+        args.extend_from_slice(&[ARGS_NO_MINIMUM, flags]);
+        args.extend(fields);
+    } else if let Some(min_ckb) = case.min_ckb {
+        args.push(min_ckb);
+    }
+    let lock_script = deployment.lock_script(context, &args);
+    let lock_hash: [u8; 32] = lock_script.calc_script_hash().unpack().into();
+
+    // limit orders sell CKB for the UDT at 1:1
+    let udt_type_script = deployment.always_success_script(context, &[42]);
+    let udt_type_hash: [u8; 32] = udt_type_script.calc_script_hash().unpack().into();
+    let udt = |amount: u128| Bytes::from(amount.to_le_bytes().to_vec());
+    let intent = limit_order_intent(&[0u8; 32], &udt_type_hash, 1, 1);
+    let intent_hash = Bytes::from(blake2b_256(&intent).to_vec());
+
+    // prepare cells, the output at the index of each input pays it back
+    let mut inputs = Vec::new();
+    let mut outputs = Vec::new();
+    let mut payments = Vec::new();
+    let mut payloads = Vec::new();
+    for i in 0..case.inputs {
+        let capacity: u64 = rng.gen_range(1000..2000);
+        let fee: u64 = rng.gen_range(0..100);
+        match case.mode {
+            Mode::WitnessLock(SighashMode::SingleUdtAnyoneCanPay) => {
+                let udt_amount: u128 = rng.gen_range(0..1000);
+                inputs.push((cell_output(capacity, &lock_script, None), Bytes::new()));
+                outputs.push((
+                    cell_output(capacity - fee, &lock_script, Some(&udt_type_script)),
+                    udt(udt_amount),
+                ));
+                payloads.push(udt_requirement(
+                    &lock_hash,
+                    capacity - fee,
+                    &udt_type_hash,
+                    udt_amount,
+                ));
+            }
+            Mode::WitnessLock(SighashMode::LimitOrder) => {
+                // the payment is appended after the remainders, its capacity paid by the order
+                let sold = 200 + fee;
+                inputs.push((
+                    cell_output(capacity, &lock_script, None),
+                    intent_hash.clone(),
+                ));
+                outputs.push((
+                    cell_output(capacity - sold, &lock_script, None),
+                    intent_hash.clone(),
+                ));
+                payments.push((
+                    cell_output(200, &lock_script, Some(&udt_type_script)),
+                    udt(sold.into()),
+                ));
+                let mut payload = intent.clone();
+                payload.extend_from_slice(&((case.inputs + i) as u32).to_le_bytes());
+                payloads.push(payload);
+            }
+            _ => {
+                inputs.push((cell_output(capacity, &lock_script, None), Bytes::new()));
+                outputs.push((
+                    cell_output(capacity - fee, &lock_script, None),
+                    Bytes::new(),
+                ));
+                payloads.push(Vec::new());
+            }
+        }
+    }
+    outputs.extend(payments);
+    let tx = build_tx(context, &deployment, inputs.clone(), outputs);
+
+    let mut builder = tx.as_advanced_builder();
+    for out_point in cell_deps {
+        builder = builder.cell_dep(CellDep::new_builder().out_point(out_point).build());
+    }
+    let mut headers = Vec::new();
+    if case.header_dep {
+        let header = block_header(HEADER_NUMBER);
+        context.insert_header(header.clone());
+        builder = builder.header_dep(header.hash());
+        headers.push(header);
+    }
+    if let Some(age) = case.recovery {
+        let inputs = tx
+            .inputs()
+            .into_iter()
+            .map(|input| {
+                input
+                    .as_builder()
+                    .since((SINCE_RELATIVE_BLOCK_NUMBER | age).pack())
+                    .build()
+            })
+            .collect::<Vec<_>>();
+        builder = builder.set_inputs(inputs);
+    }
+    let tx = builder.build();
+
+    // sign
+    let tx = match case.mode {
+        Mode::SighashAll => sign_secp256k1_blake2b_sighash_all(tx, &owner_privkey),
+        Mode::CobuildSighashAll => {
+            let message = cobuild_empty_message();
+            let seal = sign_cobuild_sighash_all(&tx, &owner_privkey, &inputs, Some(&message));
+            tx.as_advanced_builder()
+                .set_witnesses(vec![cobuild_sighash_all_witness(Some(&message), &seal)])
+                .build()
+        }
+        Mode::CobuildOtx => {
+            let range = OtxRange {
+                start_input_cell: 0,
+                input_cells: tx.inputs().len(),
+                start_output_cell: 0,
+                output_cells: tx.outputs().len(),
+                start_cell_deps: 0,
+                cell_deps: tx.cell_deps().len(),
+                start_header_deps: 0,
+                header_deps: tx.header_deps().len(),
+            };
+            let otx_witness = sign_cobuild_otx(
+                &tx,
+                &owner_privkey,
+                &lock_script.calc_script_hash(),
+                &range,
+                &inputs,
+                &cobuild_empty_message(),
+            );
+            tx.as_advanced_builder()
+                .set_witnesses(vec![cobuild_otx_start_witness(&range), otx_witness])
+                .build()
+        }
+        Mode::WitnessLock(mode) => {
+            let signer_privkey = match case.recovery {
+                Some(_) => recovery_privkey,
+                None => owner_privkey,
+            };
+            let mut extension = ExtensionBuilder::default();
+            if let Some(expiry) = case.expiry {
+                extension = extension.expiry(expiry);
+            }
+            let signer_privkey = match &case.delegation {
+                Some(delegation) => {
+                    let session_privkey = random_privkey(rng);
+                    let session_pubkey_hash =
+                        blake160(&session_privkey.pubkey().expect("pubkey").serialize());
+                    let delegation = OtxSigner::new(&signer_privkey)
+                        .sign_delegation(
+                            session_pubkey_hash,
+                            delegation.allowed_modes,
+                            delegation.expiry,
+                            delegation.max_capacity_out,
+                        )
+                        .expect("sign");
+                    extension = extension.delegation(&delegation);
+                    session_privkey
+                }
+                None => signer_privkey,
+            };
+            if case.recovery.is_some() {
+                extension = extension.recovery();
+            }
+            if let Some(max_fee) = case.max_fee {
+                extension = extension.max_fee(max_fee);
+            }
+            let extension = extension.build();
+            let group_inputs: Vec<usize> = (0..payloads.len()).collect();
+            payloads.iter().enumerate().fold(tx, |tx, (i, payload)| {
+                sign_witness_lock(
+                    tx,
+                    &signer_privkey,
+                    i,
+                    &group_inputs,
+                    mode,
+                    payload,
+                    &extension,
+                )
+            })
+        }
+    };
+
+    // the revocation cell lists the first bytes of the revoked signatures
+    if let (Some(revoked), Some((out_point, cell))) = (case.revocation, revocation_cell) {
+        let revocation_data = if revoked {
+            let (signed, signature) = signed_bytes(&tx, case.mode);
+            signed[signature][..REVOCATION_PREFIX_SIZE].to_vec()
+        } else {
+            rng.gen::<[u8; REVOCATION_PREFIX_SIZE]>().to_vec()
+        };
+        context.create_cell_with_out_point(out_point, cell, Bytes::from(revocation_data));
+    }
+
+    (mutate(tx, case), headers, lock_script)
+}
+
+// the witness carrying the first signature
+fn signature_witness_index(mode: Mode) -> usize {
+    match mode {
+        Mode::CobuildOtx => 1,
+        _ => 0,
+    }
+}
+
+// the bytes carrying the first signature and its range in them: the witness lock, where the
+// signature follows the mode byte but for sighash all, or the CoBuild witness ending with it
+fn signed_bytes(tx: &TransactionView, mode: Mode) -> (Vec<u8>, Range<usize>) {
+    let witness = tx
+        .witnesses()
+        .get(signature_witness_index(mode))
+        .expect("witness")
+        .raw_data();
+    match mode {
+        Mode::SighashAll | Mode::WitnessLock(_) => {
+            let witness_args = WitnessArgs::new_unchecked(witness);
+            let witness_lock = witness_args.lock().to_opt().expect("lock").raw_data();
+            let signature_start = match mode {
+                Mode::SighashAll => 0,
+                _ => 1,
+            };
+            (
+                witness_lock.to_vec(),
+                signature_start..signature_start + SIGNATURE_SIZE,
+            )
+        }
+        Mode::CobuildSighashAll | Mode::CobuildOtx => {
+            let len = witness.len();
+            (witness.to_vec(), len - SIGNATURE_SIZE..len)
+        }
+    }
+}
+
+fn mutate(tx: TransactionView, case: &Case) -> TransactionView {
+    let (mut signed, signature) = signed_bytes(&tx, case.mode);
+    let witness_index = signature_witness_index(case.mode);
+
+    let new_witness = match case.mutation {
+        Mutation::None => return tx,
+        Mutation::ModeByte(mode) => {
+            signed[0] = mode;
+            None
+        }
+        Mutation::FlipSignatureByte(i) => {
+            signed[signature.start + i] ^= 0x01;
+            None
+        }
+        Mutation::Malleate => {
+            malleate(&mut signed[signature]);
+            None
+        }
+        Mutation::RecoveryId(recovery_id) => {
+            signed[signature.end - 1] = recovery_id;
+            None
+        }
+        Mutation::Truncate(len) => {
+            signed.truncate(len);
+            None
+        }
+        Mutation::LowerOutputCapacity => {
+            let output = tx.outputs().get(0).expect("output");
+            let capacity: u64 = output.capacity().unpack();
+            let outputs = tx
+                .outputs()
+                .into_iter()
+                .enumerate()
+                .map(|(i, output)| match i {
+                    0 => output.as_builder().capacity((capacity - 1).pack()).build(),
+                    _ => output,
+                })
+                .collect::<Vec<_>>();
+            return tx.as_advanced_builder().set_outputs(outputs).build();
+        }
+        Mutation::DropHeaderDeps => {
+            return tx.as_advanced_builder().set_header_deps(vec![]).build();
+        }
+        Mutation::EmptyWitness => Some(Bytes::new()),
+        Mutation::GarbageWitness(len) => Some(Bytes::from(vec![0x5a; len])),
+    };
+    let new_witness = new_witness.unwrap_or_else(|| match case.mode {
+        Mode::SighashAll | Mode::WitnessLock(_) => {
+            let witness = tx.witnesses().get(witness_index).expect("witness");
+            WitnessArgs::new_unchecked(witness.raw_data())
+                .as_builder()
+                .lock(Some(Bytes::from(signed)).pack())
+                .build()
+                .as_bytes()
+        }
+        Mode::CobuildSighashAll | Mode::CobuildOtx => Bytes::from(signed),
+    });
+    let mut witnesses: Vec<_> = tx.witnesses().into_iter().collect();
+    witnesses[witness_index] = new_witness.pack();
+    tx.as_advanced_builder().set_witnesses(witnesses).build()
+}
+
+fn resolve(context: &Context, tx: &TransactionView, headers: &[HeaderView]) -> ResolvedTx {
+    ResolvedTx {
+        tx: tx.clone(),
+        inputs: tx
+            .inputs()
+            .into_iter()
+            .map(|input| context.get_cell(&input.previous_output()).expect("input"))
+            .collect(),
+        cell_deps: tx
+            .cell_deps()
+            .into_iter()
+            .map(|cell_dep| context.get_cell(&cell_dep.out_point()).expect("cell dep"))
+            .collect(),
+        header_deps: tx
+            .header_deps()
+            .into_iter()
+            .map(|hash| {
+                headers
+                    .iter()
+                    .find(|header| header.hash() == hash)
+                    .cloned()
+                    .expect("header")
+            })
+            .collect(),
+    }
+}
+
+// Both sides run `validate_lock_group` of otx-sighash-core, so this only compares the
+// `ScriptAccess` adapters: the syscalls of the contract and the resolved transaction of the
+// verifier must read the same fields and fail the same way on every mode and mutation.
+#[test]
+fn test_native_verifier_agrees_with_contract() {
+    let mut rng = StdRng::seed_from_u64(46);
+    for _ in 0..CASES {
+        let case = Case::random(&mut rng);
+        let mut context = Context::default();
+        let (tx, headers, lock_script) = build_signed_tx(&mut context, &mut rng, &case);
+
+        let verifier = OtxVerifier::new(
+            lock_script.code_hash(),
+            ScriptHashType::try_from(lock_script.hash_type()).expect("hash type"),
+        );
+        let native = verifier.verify(&resolve(&context, &tx, &headers));
+        let contract = verify_tx(&context, &tx, MAX_CYCLES);
+        match (native, contract) {
+            (Ok(()), Ok(_)) => {}
+            (Err(native), Err(contract)) => assert!(
+                contract
+                    .to_string()
+                    .contains(format!("error code {} ", native.error.code()).as_str()),
+                "case: {:?}, native: {}, contract: {}",
+                case,
+                native,
+                contract
+            ),
+            (native, contract) => panic!(
+                "case: {:?}, native: {:?}, contract: {:?}",
+                case, native, contract
+            ),
+        }
+    }
+}
+
+#[test]
+fn test_native_verifier_error_codes() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let case = Case {
+        mode: Mode::WitnessLock(SighashMode::SingleAnyoneCanPay),
+        inputs: 1,
+        min_ckb: None,
+        expiry: Some(HEADER_NUMBER),
+        max_fee: None,
+        header_dep: false,
+        identity: None,
+        recovery: None,
+        delegation: None,
+        revocation: None,
+        mutation: Mutation::None,
+    };
+    let mut context = Context::default();
+    let (tx, headers, lock_script) = build_signed_tx(&mut context, &mut rng, &case);
+
+    let verifier = OtxVerifier::new(
+        lock_script.code_hash(),
+        ScriptHashType::try_from(lock_script.hash_type()).expect("hash type"),
+    );
+    let err = verifier
+        .verify(&resolve(&context, &tx, &headers))
+        .unwrap_err();
+    assert_eq!(err.script_hash, lock_script.calc_script_hash());
+    assert_eq!(err.error.code(), ERROR_HEADER_DEP_MISSING);
+
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_HEADER_DEP_MISSING);
+
+    // a revoked CoBuild seal
+    let case = Case {
+        mode: Mode::CobuildSighashAll,
+        expiry: None,
+        revocation: Some(true),
+        ..case
+    };
+    let mut context = Context::default();
+    let (tx, headers, lock_script) = build_signed_tx(&mut context, &mut rng, &case);
+
+    let verifier = OtxVerifier::new(
+        lock_script.code_hash(),
+        ScriptHashType::try_from(lock_script.hash_type()).expect("hash type"),
+    );
+    let err = verifier
+        .verify(&resolve(&context, &tx, &headers))
+        .unwrap_err();
+    assert_eq!(err.script_hash, lock_script.calc_script_hash());
+    assert_eq!(err.error.code(), ERROR_SIGNATURE_REVOKED);
+
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_SIGNATURE_REVOKED);
+}
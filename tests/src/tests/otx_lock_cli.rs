@@ -0,0 +1,176 @@
+use crate::helper::{blake160, sign_sighash_single_acp, Otx, MAX_CYCLES};
+use crate::mock_tx::build_mock_tx;
+
+use super::*;
+
+use ckb_jsonrpc_types as json;
+use ckb_testtool::ckb_crypto::secp::Privkey;
+use ckb_testtool::ckb_types::{bytes::Bytes, core::ScriptHashType, prelude::*, H256};
+use ckb_testtool::context::Context;
+use otx_sighash_core::{
+    LockArgs, Recovery, SighashMode, ARGS_IDENTITY, ARGS_NO_MINIMUM, ARGS_RECOVERY, ARGS_REVOCATION,
+};
+use otx_sighash_lock_cli::{
+    encode_hex, inspect, lock_script, parse_mode, read_privkey, set_cell_dep_data, verify,
+    Error as CliError, TxFile,
+};
+
+use std::env;
+use std::fs;
+
+#[test]
+fn test_otx_lock_cli_lock_script() {
+    let privkey = Privkey::from(H256::from([0x11u8; 32]));
+    let path = env::temp_dir().join("otx-lock-cli-privkey");
+    fs::write(&path, format!("{}\n", encode_hex(&[0x11u8; 32]))).expect("write key");
+    let pubkey = read_privkey(&path)
+        .expect("read key")
+        .pubkey()
+        .expect("pubkey");
+    assert_eq!(pubkey, privkey.pubkey().expect("pubkey"));
+    let pubkey_hash = blake160(&pubkey.serialize());
+
+    let code_hash = H256::from([0x22u8; 32]);
+    let lock_args = |min_ckb, min_udt| LockArgs {
+        pubkey_hash,
+        min_ckb,
+        min_udt,
+        identity_type_hash: None,
+        recovery: None,
+        revocation_type_hash: None,
+    };
+    let lock = lock_script(&code_hash, ScriptHashType::Type, &lock_args(Some(2), None))
+        .expect("lock script");
+    assert_eq!(lock.code_hash(), code_hash.pack());
+    assert_eq!(lock.hash_type(), ScriptHashType::Type.into());
+    assert_eq!(lock.args().raw_data(), [&pubkey_hash[..], &[2]].concat());
+
+    // a UDT minimum alone leaves out the CKB one
+    let lock = lock_script(&code_hash, ScriptHashType::Type, &lock_args(None, Some(3)))
+        .expect("lock script");
+    assert_eq!(
+        lock.args().raw_data(),
+        [&pubkey_hash[..], &[ARGS_NO_MINIMUM, 3]].concat()
+    );
+
+    // the flag fields follow both minimums, in the order of their flag bits
+    let delay = 0x8000_0000_0000_0064;
+    let mut with_flags = lock_args(Some(2), None);
+    with_flags.identity_type_hash = Some([7u8; 32]);
+    with_flags.recovery = Some(Recovery {
+        pubkey_hash: [9u8; 20],
+        delay,
+    });
+    with_flags.revocation_type_hash = Some([8u8; 32]);
+    let lock = lock_script(&code_hash, ScriptHashType::Type, &with_flags).expect("lock script");
+    assert_eq!(
+        lock.args().raw_data(),
+        [
+            &pubkey_hash[..],
+            &[
+                2,
+                ARGS_NO_MINIMUM,
+                ARGS_IDENTITY | ARGS_RECOVERY | ARGS_REVOCATION
+            ],
+            &[7u8; 32],
+            &[9u8; 20],
+            &delay.to_le_bytes(),
+            &[8u8; 32],
+        ]
+        .concat()
+    );
+
+    // the lock only accepts a relative recovery delay
+    with_flags.recovery = Some(Recovery {
+        pubkey_hash: [9u8; 20],
+        delay: 100,
+    });
+    assert!(matches!(
+        lock_script(&code_hash, ScriptHashType::Type, &with_flags),
+        Err(CliError::InvalidArgs)
+    ));
+
+    assert_eq!(
+        parse_mode("single-acp").unwrap(),
+        SighashMode::SingleAnyoneCanPay
+    );
+    assert_eq!(
+        parse_mode("0x84").unwrap(),
+        SighashMode::SingleUdtAnyoneCanPay
+    );
+    assert!(parse_mode("0x86").is_err());
+}
+
+#[test]
+fn test_otx_lock_cli_inspect_and_verify() {
+    let mut context = Context::default();
+    let Otx { privkey, tx, .. } = Otx::new(&mut context, &[]);
+    let tx = sign_sighash_single_acp(tx, &privkey, 0);
+    let tx_file = TxFile::Mock(build_mock_tx(&context, &tx));
+
+    assert_eq!(tx_file.group_inputs(0).unwrap(), vec![0]);
+    let description = inspect(&tx_file.tx(), 0, &[0], None).expect("inspect");
+    let pubkey_hash = blake160(&privkey.pubkey().expect("pubkey").serialize());
+    assert!(description.starts_with("mode: 0x83 SingleAnyoneCanPay\n"));
+    assert!(description.contains("  input length: 44\n"));
+    assert!(description.contains(&format!("signer: {}\n", encode_hex(&pubkey_hash))));
+
+    let mock_tx = match tx_file {
+        TxFile::Mock(mock_tx) => mock_tx,
+        TxFile::Tx(_) => unreachable!(),
+    };
+    let cycles = verify(mock_tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_otx_lock_cli_verify_wrong_signature() {
+    let mut context = Context::default();
+    let Otx { tx, .. } = Otx::new(&mut context, &[]);
+    let tx = sign_sighash_single_acp(tx, &Privkey::from(H256::from([0x33u8; 32])), 0);
+
+    let err = verify(build_mock_tx(&context, &tx), MAX_CYCLES).unwrap_err();
+    match err {
+        CliError::Rejected(err) => assert!(
+            err.contains(&format!("error code {} ", ERROR_WRONG_PUBKEY)),
+            "{}",
+            err
+        ),
+        err => panic!("unexpected error {}", err),
+    }
+}
+
+#[test]
+fn test_otx_lock_cli_replace_cell_dep_loaded_by_data_hash() {
+    let mut context = Context::default();
+    let Otx {
+        deployment,
+        privkey,
+        tx,
+        ..
+    } = Otx::new(&mut context, &[]);
+    let tx = sign_sighash_single_acp(tx, &privkey, 0);
+    let mut mock_tx = build_mock_tx(&context, &tx);
+
+    // the lock loads the contract by data hash
+    let lock_out_point = json::OutPoint::from(deployment.lock_out_point);
+    let index = mock_tx
+        .mock_info
+        .cell_deps
+        .iter()
+        .position(|cell_dep| cell_dep.cell_dep.out_point == lock_out_point)
+        .expect("lock cell dep");
+    let contract_bin: Bytes = Loader::default().load_binary("otx-sighash-lock");
+
+    // the same build is accepted
+    set_cell_dep_data(&mut mock_tx, index, contract_bin.clone()).expect("same binary");
+
+    // another build would not be found by the lock
+    let mut other_bin = contract_bin.to_vec();
+    other_bin.push(0);
+    let err = set_cell_dep_data(&mut mock_tx, index, other_bin.into()).unwrap_err();
+    assert!(matches!(err, CliError::DataHashReference(i) if i == index));
+
+    let cycles = verify(mock_tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
@@ -0,0 +1,319 @@
+use crate::helper::{
+    blake160, block_header, build_psot, cell_output, deploy, expiry_extension, limit_order_intent,
+    udt_requirement, Deployment, SighashMode, MAX_CYCLES,
+};
+
+use super::*;
+
+use ckb_jsonrpc_types as json;
+use ckb_testtool::ckb_crypto::secp::Generator;
+use ckb_testtool::ckb_hash::blake2b_256;
+use ckb_testtool::ckb_types::{bytes::Bytes, core::ScriptHashType, packed::*, prelude::*, H256};
+use ckb_testtool::context::Context;
+use jsonrpc_core::IoHandler;
+use otx_sighash_lock_pool::{rpc, Asset, CellStore, Error, FeePayer, OtxPool};
+use otx_sighash_lock_sdk::Psot;
+use otx_sighash_lock_verifier::OtxVerifier;
+use serde_json::{json, Value};
+
+use std::sync::{Arc, Mutex};
+
+const FEE_RATE: u64 = 1000;
+const FEE_CELL_CAPACITY: u64 = 10_000_000_000;
+
+// copies the cells of `out_points` from the context into the store
+fn track(context: &Context, store: &mut CellStore, out_points: &[OutPoint]) {
+    for out_point in out_points {
+        let (cell_output, data) = context.get_cell(out_point).expect("cell");
+        store.insert_cell(out_point.clone(), cell_output, data);
+    }
+}
+
+// copies the cells consumed by `psot` from the context into the store of the pool
+fn track_inputs(context: &Context, pool: &mut OtxPool, psot: &Psot) {
+    let out_points: Vec<OutPoint> = psot
+        .tx
+        .inputs()
+        .into_iter()
+        .map(|input| input.previous_output())
+        .collect();
+    track(context, pool.store_mut(), &out_points);
+}
+
+// a pool of the deployed lock, paying the fees from a cell under the always success lock
+fn new_pool(context: &mut Context, deployment: &Deployment) -> OtxPool {
+    let lock_script = deployment.lock_script(context, &[]);
+    let verifier = OtxVerifier::new(
+        lock_script.code_hash(),
+        ScriptHashType::try_from(lock_script.hash_type()).expect("hash type"),
+    );
+
+    // fee cell
+    let fee_lock = deployment.always_success_script(context, &[]);
+    let fee_out_point = context.create_cell(
+        cell_output(FEE_CELL_CAPACITY, &fee_lock, None),
+        Bytes::new(),
+    );
+    let fee_payer = FeePayer {
+        out_point: fee_out_point,
+        cell_deps: vec![CellDep::new_builder()
+            .out_point(deployment.always_success_out_point.clone())
+            .build()],
+        witness: Default::default(),
+        fee_rate: FEE_RATE,
+    };
+
+    let mut store = CellStore::default();
+    let mut out_points: Vec<OutPoint> = deployment
+        .cell_deps
+        .iter()
+        .map(|cell_dep| cell_dep.out_point())
+        .collect();
+    out_points.push(fee_payer.out_point.clone());
+    track(context, &mut store, &out_points);
+    OtxPool::new(store, verifier, fee_payer)
+}
+
+// an otx of a new maker spending a cell of `input_capacity` to an output of
+// `output_capacity`, signed with SINGLE|ANYONECANPAY and `extension`, its input tracked by
+// the pool
+fn build_tracked_psot(
+    context: &mut Context,
+    pool: &mut OtxPool,
+    deployment: &Deployment,
+    capacities: (u64, u64),
+    extension: &[u8],
+    header_deps: Vec<Byte32>,
+) -> Psot {
+    let privkey = Generator::random_privkey();
+    let pubkey_hash = blake160(&privkey.pubkey().expect("pubkey").serialize());
+
+    let lock_script = deployment.lock_script(context, &pubkey_hash);
+    let mut psot = build_psot(
+        context,
+        deployment,
+        vec![(cell_output(capacities.0, &lock_script, None), Bytes::new())],
+        vec![(cell_output(capacities.1, &lock_script, None), Bytes::new())],
+        SighashMode::SingleAnyoneCanPay,
+    );
+    psot.tx = psot
+        .tx
+        .as_advanced_builder()
+        .set_header_deps(header_deps)
+        .build();
+    track_inputs(context, pool, &psot);
+
+    // sign
+    psot.sign(&privkey, 0, &[], extension).expect("sign");
+    psot
+}
+
+fn call(io: &IoHandler, method: &str, params: Value) -> Value {
+    let request = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+    let response = io
+        .handle_request_sync(&request.to_string())
+        .expect("response");
+    serde_json::from_str(&response).expect("json")
+}
+
+fn psot_value(psot: &Psot) -> Value {
+    serde_json::from_str(&psot.to_json()).expect("json")
+}
+
+#[test]
+fn test_otx_pool_rpc() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let mut pool = new_pool(&mut context, &deployment);
+    // offers CKB
+    let seller = build_tracked_psot(
+        &mut context,
+        &mut pool,
+        &deployment,
+        (1000, 900),
+        &[],
+        vec![],
+    );
+    // wants CKB
+    let buyer = build_tracked_psot(
+        &mut context,
+        &mut pool,
+        &deployment,
+        (1000, 1100),
+        &[],
+        vec![],
+    );
+    let io = rpc::build_handler(Arc::new(Mutex::new(pool)));
+
+    let response = call(&io, "submit_otx", json!([psot_value(&seller)]));
+    assert_eq!(response["result"], json!("0x0"));
+    let response = call(&io, "submit_otx", json!([psot_value(&buyer)]));
+    assert_eq!(response["result"], json!("0x1"));
+    let response = call(&io, "find_otxs", json!(["ckb", null]));
+    assert_eq!(response["result"], json!(["0x0"]));
+    let response = call(&io, "find_otxs", json!([null, "ckb"]));
+    assert_eq!(response["result"], json!(["0x1"]));
+
+    let response = call(&io, "aggregate", json!([[]]));
+    let aggregate = &response["result"];
+    assert_eq!(aggregate["otxs"], json!(["0x0", "0x1"]));
+    assert_eq!(aggregate["conflicts"], json!([]));
+    let psot = Psot::from_json(&aggregate["psot"].to_string()).expect("psot");
+    let tx = context.complete_tx(psot.tx);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+
+    // both otxs are spent once the transaction is committed
+    let tx: json::Transaction = tx.data().into();
+    let response = call(&io, "commit_transaction", json!([tx]));
+    assert_eq!(response["result"], json!(["0x0", "0x1"]));
+    let response = call(&io, "find_otxs", json!([null, null]));
+    assert_eq!(response["result"], json!([]));
+}
+
+#[test]
+fn test_otx_pool_rejects() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let mut pool = new_pool(&mut context, &deployment);
+    let psot = build_tracked_psot(
+        &mut context,
+        &mut pool,
+        &deployment,
+        (1000, 1000),
+        &[],
+        vec![],
+    );
+
+    // the signed output is changed
+    let mut tampered = psot.clone();
+    let output = tampered
+        .tx
+        .output(0)
+        .unwrap()
+        .as_builder()
+        .capacity(900u64.pack())
+        .build();
+    tampered.tx = tampered
+        .tx
+        .as_advanced_builder()
+        .set_outputs(vec![output])
+        .build();
+    match pool.submit(tampered) {
+        Err(Error::Rejected(err)) => assert_eq!(err.error.code(), ERROR_WRONG_PUBKEY),
+        _ => panic!("tampered otx accepted"),
+    }
+
+    // the same input twice
+    assert_eq!(pool.submit(psot.clone()).expect("submit"), 0);
+    assert!(matches!(pool.submit(psot), Err(Error::DoubleSpend(0))));
+
+    // the cell is not in the store
+    let mut unknown = build_tracked_psot(
+        &mut context,
+        &mut pool,
+        &deployment,
+        (1000, 1000),
+        &[],
+        vec![],
+    );
+    unknown.tx = unknown
+        .tx
+        .as_advanced_builder()
+        .set_inputs(vec![CellInput::new(
+            OutPoint::new(Default::default(), 0),
+            0,
+        )])
+        .build();
+    assert!(matches!(pool.submit(unknown), Err(Error::UnknownCell(0))));
+}
+
+#[test]
+fn test_otx_pool_expiry() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let mut pool = new_pool(&mut context, &deployment);
+    let header = block_header(100);
+    pool.store_mut().set_tip(header.clone());
+    let psot = build_tracked_psot(
+        &mut context,
+        &mut pool,
+        &deployment,
+        (1000, 1000),
+        &expiry_extension(110),
+        vec![header.hash()],
+    );
+    assert_eq!(pool.submit(psot).expect("submit"), 0);
+
+    let header = block_header(110);
+    assert_eq!(pool.set_tip(header).expect("set tip"), Vec::<u64>::new());
+    let header = block_header(111);
+    assert_eq!(pool.set_tip(header).expect("set tip"), vec![0]);
+    assert!(pool.get(0).is_none());
+}
+
+#[test]
+fn test_otx_pool_open_orders() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let mut pool = new_pool(&mut context, &deployment);
+    let privkey = Generator::random_privkey();
+    let pubkey_hash = blake160(&privkey.pubkey().expect("pubkey").serialize());
+
+    // prepare scripts
+    let lock_script = deployment.lock_script(&mut context, &pubkey_hash);
+    let udt_type_script = deployment.always_success_script(&mut context, &[42]);
+    let lock_hash: [u8; 32] = lock_script.calc_script_hash().unpack().into();
+    let udt_type_hash: [u8; 32] = udt_type_script.calc_script_hash().unpack().into();
+    let udt = Asset::Udt(H256(udt_type_hash));
+
+    // 1000 shannons for 500 shannons and 500 UDT, nobody pays yet
+    let mut payment = build_psot(
+        &mut context,
+        &deployment,
+        vec![(cell_output(1000, &lock_script, None), Bytes::new())],
+        vec![],
+        SighashMode::SingleUdtAnyoneCanPay,
+    );
+    track_inputs(&context, &mut pool, &payment);
+    let payload = udt_requirement(&lock_hash, 500, &udt_type_hash, 500);
+    payment.sign(&privkey, 0, &payload, &[]).expect("sign");
+    assert_eq!(pool.submit(payment).expect("submit"), 0);
+
+    // 1000 shannons for the UDT at 1:1, not filled yet
+    let intent = limit_order_intent(&[0u8; 32], &udt_type_hash, 1, 1);
+    let intent_hash = Bytes::from(blake2b_256(&intent).to_vec());
+    let mut order = build_psot(
+        &mut context,
+        &deployment,
+        vec![(cell_output(1000, &lock_script, None), intent_hash)],
+        vec![],
+        SighashMode::LimitOrder,
+    );
+    track_inputs(&context, &mut pool, &order);
+    // the payment follows the remainder once a taker fills the order
+    let mut payload = intent;
+    payload.extend_from_slice(&1u32.to_le_bytes());
+    order.sign(&privkey, 0, &payload, &[]).expect("sign");
+    assert_eq!(pool.submit(order).expect("submit"), 1);
+
+    assert_eq!(pool.find(Some(&Asset::Ckb), Some(&udt)), vec![0, 1]);
+    assert_eq!(pool.find(Some(&udt), None), Vec::<u64>::new());
+
+    // the order cell is still checked against the intent
+    let mut unbound = build_psot(
+        &mut context,
+        &deployment,
+        vec![(cell_output(1000, &lock_script, None), Bytes::new())],
+        vec![],
+        SighashMode::LimitOrder,
+    );
+    track_inputs(&context, &mut pool, &unbound);
+    unbound.sign(&privkey, 0, &payload, &[]).expect("sign");
+    match pool.submit(unbound) {
+        Err(Error::Rejected(err)) => assert_eq!(err.error.code(), ERROR_INVALID_ORDER_CELL),
+        _ => panic!("unbound order accepted"),
+    }
+}
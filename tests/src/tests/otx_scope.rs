@@ -0,0 +1,110 @@
+use crate::helper::{
+    blake160, build_tx, cell_output, deploy, otx_scope_extension,
+    sign_sighash_all_acp_with_extension, Otx, MAX_CYCLES, SIGNATURE_SIZE,
+};
+
+use super::*;
+
+use ckb_testtool::ckb_crypto::secp::Generator;
+use ckb_testtool::ckb_types::{bytes::Bytes, core::TransactionView, packed::*, prelude::*};
+use ckb_testtool::context::Context;
+
+// signs a standalone ALL|ANYONECANPAY otx scoped to its only input and output
+fn build_signed_otx(context: &mut Context) -> TransactionView {
+    let Otx { privkey, tx, .. } = Otx::new(context, &[]);
+
+    // sign
+    sign_sighash_all_acp_with_extension(tx, &privkey, 0, &[0], &otx_scope_extension(0, 1, 0, 1))
+}
+
+// moves the otx scope of a signed witness to new starts, which the signature does not cover
+fn relocate_otx_scope(witness: Bytes, input_start: u32, output_start: u32) -> Bytes {
+    let witness = WitnessArgs::new_unchecked(witness);
+    let mut lock: Vec<u8> = witness.lock().to_opt().unwrap().raw_data().to_vec();
+    let scope = 1 + SIGNATURE_SIZE + 1;
+    lock[scope..scope + 4].copy_from_slice(&input_start.to_le_bytes());
+    lock[scope + 8..scope + 12].copy_from_slice(&output_start.to_le_bytes());
+    witness
+        .as_builder()
+        .lock(Some(Bytes::from(lock)).pack())
+        .build()
+        .as_bytes()
+}
+
+// the batcher concatenates two otxs, relocating the scope of the second one when `relocate`
+fn build_merged_tx(context: &mut Context, relocate: bool) -> TransactionView {
+    let otx_1 = build_signed_otx(context);
+    let otx_2 = build_signed_otx(context);
+
+    let mut witness_2 = otx_2.witnesses().get(0).unwrap().raw_data();
+    if relocate {
+        witness_2 = relocate_otx_scope(witness_2, 1, 1);
+    }
+    otx_1
+        .as_advanced_builder()
+        .inputs(otx_2.inputs())
+        .outputs(otx_2.outputs())
+        .outputs_data(otx_2.outputs_data())
+        .witness(witness_2.pack())
+        .build()
+}
+
+#[test]
+fn test_merged_scoped_otxs() {
+    let mut context = Context::default();
+    let tx = build_merged_tx(&mut context, true);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_merged_scoped_otxs_without_relocation() {
+    let mut context = Context::default();
+    let tx = build_merged_tx(&mut context, false);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_INVALID_OTX_SCOPE);
+}
+
+#[test]
+fn test_overlapping_scoped_otxs() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let privkey = Generator::random_privkey();
+    let pubkey_hash = blake160(&privkey.pubkey().expect("pubkey").serialize());
+
+    // two otxs of the same lock claim the only output
+    let lock_script = deployment.lock_script(&mut context, &pubkey_hash);
+    let tx = build_tx(
+        &mut context,
+        &deployment,
+        vec![
+            (cell_output(1000, &lock_script, None), Bytes::new()),
+            (cell_output(1000, &lock_script, None), Bytes::new()),
+        ],
+        vec![(cell_output(1000, &lock_script, None), Bytes::new())],
+    );
+
+    // sign
+    let tx = sign_sighash_all_acp_with_extension(
+        tx,
+        &privkey,
+        0,
+        &[0, 1],
+        &otx_scope_extension(0, 1, 0, 1),
+    );
+    let tx = sign_sighash_all_acp_with_extension(
+        tx,
+        &privkey,
+        1,
+        &[0, 1],
+        &otx_scope_extension(1, 1, 0, 1),
+    );
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_INVALID_OTX_SCOPE);
+}
@@ -0,0 +1,191 @@
+use crate::helper::{sign_witness_lock, SighashMode};
+
+use ckb_testtool::ckb_crypto::secp::Generator;
+use ckb_testtool::ckb_types::{
+    bytes::Bytes,
+    core::{TransactionBuilder, TransactionView},
+    packed::*,
+    prelude::*,
+};
+use otx_sighash_lock_sdk::{
+    diff_preimages, explain_preimage, signing_preimage, FieldDiff, OtxSigner,
+};
+
+// two inputs of the same lock paying two outputs, the second witness carrying an
+// output type
+fn build_tx() -> TransactionView {
+    let lock_script = Script::new_builder()
+        .code_hash([1u8; 32].pack())
+        .args(Bytes::from(vec![2u8; 20]).pack())
+        .build();
+    let input = |index: u32| {
+        CellInput::new_builder()
+            .previous_output(
+                OutPoint::new_builder()
+                    .tx_hash([3u8; 32].pack())
+                    .index(index.pack())
+                    .build(),
+            )
+            .build()
+    };
+    let output = |capacity: u64| {
+        CellOutput::new_builder()
+            .capacity(capacity.pack())
+            .lock(lock_script.clone())
+            .build()
+    };
+    let witness = WitnessArgs::new_builder()
+        .output_type(Some(Bytes::from(vec![4u8; 3])).pack())
+        .build();
+
+    TransactionBuilder::default()
+        .input(input(0))
+        .input(input(1))
+        .output(output(1000))
+        .output(output(500))
+        .output_data(Bytes::new().pack())
+        .output_data(Bytes::from(vec![5u8; 2]).pack())
+        .witness(Bytes::new().pack())
+        .witness(witness.as_bytes().pack())
+        .build()
+}
+
+#[test]
+fn test_preimage_components() {
+    let privkey = Generator::random_privkey();
+    let tx = sign_witness_lock(
+        build_tx(),
+        &privkey,
+        0,
+        &[0, 1],
+        SighashMode::AllAnyoneCanPay,
+        &[],
+        &[],
+    );
+
+    let preimage = signing_preimage(&tx, 0, &[0, 1], None).expect("preimage");
+    let lock = WitnessArgs::from_slice(&tx.witnesses().get(0).unwrap().raw_data())
+        .unwrap()
+        .lock()
+        .to_opt()
+        .unwrap()
+        .raw_data();
+    let message = OtxSigner::new(&privkey)
+        .group_inputs(vec![0, 1])
+        .signing_message(&tx, 0, &lock)
+        .expect("message");
+    assert_eq!(preimage.message, message);
+
+    let components = explain_preimage(&preimage);
+    let names: Vec<&str> = components.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "input length",
+            "input",
+            "outputs count",
+            "outputs length",
+            "outputs",
+            "outputs data count",
+            "outputs data length",
+            "outputs data",
+            "witness length",
+            "witness",
+            "witness 1 length",
+            "witness 1",
+            "digest",
+            "prefixed",
+            "message",
+        ]
+    );
+    let field = |component: &str, name: &str| {
+        components
+            .iter()
+            .find(|c| c.name == component)
+            .and_then(|c| c.fields.iter().find(|(field, _)| field == name))
+            .map(|(_, value)| value.clone())
+            .expect("field")
+    };
+    assert_eq!(field("input length", ""), "44");
+    assert_eq!(field("input", "previous_output.index"), "0");
+    assert_eq!(field("outputs count", ""), "2");
+    assert_eq!(field("outputs", "1.capacity"), "500");
+    assert_eq!(field("outputs data", "1"), "0x0505");
+
+    // the mode and the signature are zeroed, the other group witness only keeps its
+    // output type
+    assert_eq!(field("witness", "lock"), format!("0x{}", "00".repeat(66)));
+    assert_eq!(field("witness 1", "lock"), "none");
+    assert_eq!(field("witness 1", "output_type"), "0x040404");
+}
+
+#[test]
+fn test_preimage_diff() {
+    let privkey = Generator::random_privkey();
+    let tx = sign_witness_lock(
+        build_tx(),
+        &privkey,
+        0,
+        &[0, 1],
+        SighashMode::AllAnyoneCanPay,
+        &[],
+        &[],
+    );
+    let preimage = signing_preimage(&tx, 0, &[0, 1], None).expect("preimage");
+    assert!(diff_preimages(&preimage, &preimage).is_empty());
+
+    // an aggregator lowering the second output
+    let outputs: Vec<CellOutput> = tx.outputs().into_iter().collect();
+    let lowered = tx
+        .as_advanced_builder()
+        .set_outputs(vec![
+            outputs[0].clone(),
+            outputs[1]
+                .clone()
+                .as_builder()
+                .capacity(400u64.pack())
+                .build(),
+        ])
+        .build();
+    let other = signing_preimage(&lowered, 0, &[0, 1], None).expect("preimage");
+    let diffs = diff_preimages(&preimage, &other);
+    assert_eq!(
+        diffs[0],
+        FieldDiff {
+            field: "outputs.1.capacity".to_string(),
+            left: Some("500".to_string()),
+            right: Some("400".to_string()),
+        }
+    );
+    let fields: Vec<&str> = diffs.iter().map(|diff| diff.field.as_str()).collect();
+    assert_eq!(
+        fields,
+        vec!["outputs.1.capacity", "digest", "prefixed", "message"]
+    );
+
+    // an output added past the ones signed
+    let extended = tx
+        .as_advanced_builder()
+        .output(outputs[0].clone())
+        .output_data(Bytes::new().pack())
+        .build();
+    let other = signing_preimage(&extended, 0, &[0, 1], None).expect("preimage");
+    let diffs = diff_preimages(&preimage, &other);
+    assert_eq!(diffs[0].field, "outputs count");
+    assert!(diffs.contains(&FieldDiff {
+        field: "outputs.2.capacity".to_string(),
+        left: None,
+        right: Some("1000".to_string()),
+    }));
+
+    // the same transaction seen in another mode
+    let other =
+        signing_preimage(&tx, 0, &[0, 1], Some(SighashMode::SingleAnyoneCanPay)).expect("preimage");
+    let diffs = diff_preimages(&preimage, &other);
+    assert!(diffs.iter().any(|diff| diff.field == "prefixed"));
+    assert!(diffs.contains(&FieldDiff {
+        field: "output.capacity".to_string(),
+        left: None,
+        right: Some("1000".to_string()),
+    }));
+}
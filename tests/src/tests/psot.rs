@@ -0,0 +1,156 @@
+use crate::helper::{
+    build_limit_order_psot, build_signed_psot, deploy, otx_scope_extension, SighashMode, MAX_CYCLES,
+};
+
+use super::*;
+
+use ckb_testtool::ckb_types::bytes::Bytes;
+use ckb_testtool::context::Context;
+use otx_sighash_lock_sdk::{Error, MergeConflictKind, Psot};
+
+#[test]
+fn test_psot_encoding() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let mut psot = build_signed_psot(
+        &mut context,
+        &deployment,
+        SighashMode::SingleAnyoneCanPay,
+        &[],
+        None,
+    );
+    psot.metadata
+        .insert("maker".to_string(), Bytes::from("alice"));
+
+    let decoded = Psot::from_slice(&psot.to_bytes()).expect("decode");
+    assert_eq!(decoded.to_bytes(), psot.to_bytes());
+    assert!(decoded.is_complete());
+
+    let decoded = Psot::from_json(&psot.to_json()).expect("decode json");
+    assert_eq!(decoded.to_bytes(), psot.to_bytes());
+
+    // unknown versions are rejected
+    let mut data = psot.to_bytes().to_vec();
+    data[20] = 2;
+    assert!(matches!(
+        Psot::from_slice(&data),
+        Err(Error::UnsupportedPsotVersion(2))
+    ));
+}
+
+#[test]
+fn test_psot_signature_status() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let mut psot = build_signed_psot(
+        &mut context,
+        &deployment,
+        SighashMode::SingleAnyoneCanPay,
+        &[],
+        None,
+    );
+
+    psot.inputs[0].signed = false;
+    assert!(matches!(psot.validate(), Err(Error::SignatureStatus(0))));
+    psot.inputs[0].signed = true;
+    psot.inputs[0].mode = Some(SighashMode::AllAnyoneCanPay);
+    assert!(matches!(psot.validate(), Err(Error::ModeMismatch(0))));
+}
+
+#[test]
+fn test_psot_merge() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let single = build_signed_psot(
+        &mut context,
+        &deployment,
+        SighashMode::SingleAnyoneCanPay,
+        &[],
+        None,
+    );
+    // the scope is placed by the merge
+    let all = build_signed_psot(
+        &mut context,
+        &deployment,
+        SighashMode::AllAnyoneCanPay,
+        &otx_scope_extension(0, 1, 0, 1),
+        None,
+    );
+
+    let merged = single.merge(&all).expect("merge");
+    assert!(merged.is_complete());
+    assert_eq!(merged.tx.cell_deps().len(), deployment.cell_deps.len());
+    let tx = context.complete_tx(merged.tx);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_psot_merge_conflict() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let single = build_signed_psot(
+        &mut context,
+        &deployment,
+        SighashMode::SingleAnyoneCanPay,
+        &[],
+        None,
+    );
+    // commits to all outputs of the transaction
+    let all = build_signed_psot(
+        &mut context,
+        &deployment,
+        SighashMode::AllAnyoneCanPay,
+        &[],
+        None,
+    );
+
+    assert!(matches!(
+        single.merge(&all),
+        Err(Error::MergeConflict(1, MergeConflictKind::AllOutputs))
+    ));
+    assert!(matches!(
+        all.merge(&single),
+        Err(Error::MergeConflict(0, MergeConflictKind::AllOutputs))
+    ));
+}
+
+#[test]
+fn test_psot_merge_limit_order() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let single = build_signed_psot(
+        &mut context,
+        &deployment,
+        SighashMode::SingleAnyoneCanPay,
+        &[],
+        None,
+    );
+    let order = build_limit_order_psot(&mut context, &deployment);
+
+    // the payment index is moved past the outputs of the first otx
+    let merged = single.merge(&order).expect("merge");
+    assert!(merged.is_complete());
+    let tx = context.complete_tx(merged.tx);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_psot_merge_limit_order_misaligned() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let order = build_limit_order_psot(&mut context, &deployment);
+
+    // the first order has two outputs for its input, the remainder of the second one would
+    // not be at the index of its input
+    let other = build_limit_order_psot(&mut context, &deployment);
+    assert!(matches!(
+        order.merge(&other),
+        Err(Error::MergeConflict(1, MergeConflictKind::Unaligned))
+    ));
+}
@@ -0,0 +1,48 @@
+use crate::helper::{
+    cell_output, sign_sighash_single_acp_with_extension, Otx, EXTENSION_READABLE, MAX_CYCLES,
+};
+
+use super::*;
+
+use ckb_testtool::ckb_types::{bytes::Bytes, core::TransactionView};
+use ckb_testtool::context::Context;
+
+// signs a SINGLE|ANYONECANPAY otx with a readable preimage, the output carrying 300 UDT
+// when `with_udt`
+fn build_signed_tx(context: &mut Context, with_udt: bool) -> TransactionView {
+    let otx = Otx::new(context, &[]);
+    let Otx { privkey, tx, .. } = if with_udt {
+        let udt_type_script = otx.deployment.always_success_script(context, &[42]);
+        otx.output(context, |lock_script| {
+            (
+                cell_output(1000, lock_script, Some(&udt_type_script)),
+                Bytes::from(300u128.to_le_bytes().to_vec()),
+            )
+        })
+    } else {
+        otx
+    };
+
+    // sign
+    sign_sighash_single_acp_with_extension(tx, &privkey, 0, &[EXTENSION_READABLE])
+}
+
+#[test]
+fn test_readable_preimage() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, false);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_readable_preimage_with_udt() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, true);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
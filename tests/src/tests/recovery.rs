@@ -0,0 +1,216 @@
+use crate::helper::{
+    blake160, build_tx, cell_output, cobuild_empty_message, cobuild_otx_start_witness,
+    cobuild_sighash_all_witness, deploy, sign_cobuild_otx, sign_cobuild_sighash_all,
+    sign_secp256k1_blake2b_sighash_all, sign_sighash_single_acp_with_extension, Otx, OtxRange,
+    EXTENSION_RECOVERY, MAX_CYCLES,
+};
+
+use super::*;
+
+use ckb_testtool::ckb_crypto::secp::{Generator, Privkey};
+use ckb_testtool::ckb_types::{bytes::Bytes, core::TransactionView, packed::*, prelude::*};
+use ckb_testtool::context::Context;
+
+// args flag naming an identity cell
+const ARGS_IDENTITY: u8 = 0x01;
+// args flag adding a recovery key
+const ARGS_RECOVERY: u8 = 0x02;
+// args flag naming a revocation cell
+const ARGS_REVOCATION: u8 = 0x04;
+// leaves out the anyone-can-pay minimums
+const ARGS_NO_MINIMUM: u8 = 0xff;
+// relative `since` in block numbers
+const SINCE_RELATIVE_BLOCK_NUMBER: u64 = 0x80 << 56;
+
+// the recovery key, delayed by 10 blocks, signs with `sign` an otx spending an input aged by
+// `since` blocks
+fn build_signed_tx<F>(context: &mut Context, since: u64, sign: F) -> TransactionView
+where
+    F: FnOnce(TransactionView, &Privkey, (CellOutput, Bytes)) -> TransactionView,
+{
+    let recovery_privkey = Generator::random_privkey();
+    let recovery_pubkey_hash = blake160(&recovery_privkey.pubkey().expect("pubkey").serialize());
+
+    let mut args_fields = vec![ARGS_NO_MINIMUM, ARGS_NO_MINIMUM, ARGS_RECOVERY];
+    args_fields.extend_from_slice(&recovery_pubkey_hash);
+    args_fields.extend_from_slice(&(SINCE_RELATIVE_BLOCK_NUMBER | 10).to_le_bytes());
+    let Otx { input_cell, tx, .. } = Otx::new(context, &args_fields);
+    let input = tx
+        .inputs()
+        .get(0)
+        .expect("input")
+        .as_builder()
+        .since((SINCE_RELATIVE_BLOCK_NUMBER | since).pack())
+        .build();
+    let tx = tx.as_advanced_builder().set_inputs(vec![input]).build();
+
+    // sign
+    sign(tx, &recovery_privkey, input_cell)
+}
+
+fn sign_single_acp(
+    tx: TransactionView,
+    privkey: &Privkey,
+    _input_cell: (CellOutput, Bytes),
+) -> TransactionView {
+    sign_sighash_single_acp_with_extension(tx, privkey, 0, &[EXTENSION_RECOVERY])
+}
+
+fn sign_sighash_all(
+    tx: TransactionView,
+    privkey: &Privkey,
+    _input_cell: (CellOutput, Bytes),
+) -> TransactionView {
+    sign_secp256k1_blake2b_sighash_all(tx, privkey)
+}
+
+fn sign_cobuild(
+    tx: TransactionView,
+    privkey: &Privkey,
+    input_cell: (CellOutput, Bytes),
+) -> TransactionView {
+    let seal = sign_cobuild_sighash_all(&tx, privkey, &[input_cell], None);
+    tx.as_advanced_builder()
+        .set_witnesses(vec![cobuild_sighash_all_witness(None, &seal)])
+        .build()
+}
+
+fn sign_cobuild_otx_seal(
+    tx: TransactionView,
+    privkey: &Privkey,
+    input_cell: (CellOutput, Bytes),
+) -> TransactionView {
+    let range = OtxRange {
+        start_input_cell: 0,
+        input_cells: 1,
+        start_output_cell: 0,
+        output_cells: 1,
+        start_cell_deps: 0,
+        cell_deps: tx.cell_deps().len(),
+        start_header_deps: 0,
+        header_deps: 0,
+    };
+    let script_hash = input_cell.0.lock().calc_script_hash();
+    let otx_witness = sign_cobuild_otx(
+        &tx,
+        privkey,
+        &script_hash,
+        &range,
+        &[input_cell],
+        &cobuild_empty_message(),
+    );
+    tx.as_advanced_builder()
+        .set_witnesses(vec![cobuild_otx_start_witness(&range), otx_witness])
+        .build()
+}
+
+#[test]
+fn test_recovery_after_delay() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, 10, sign_single_acp);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_recovery_before_delay() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, 9, sign_single_acp);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_RECOVERY_DELAY_NOT_MET);
+}
+
+#[test]
+fn test_recovery_sighash_all_after_delay() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, 10, sign_sighash_all);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_recovery_sighash_all_before_delay() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, 9, sign_sighash_all);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_RECOVERY_DELAY_NOT_MET);
+}
+
+#[test]
+fn test_recovery_cobuild_sighash_all_after_delay() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, 10, sign_cobuild);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_recovery_cobuild_otx_after_delay() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, 10, sign_cobuild_otx_seal);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_recovery_cobuild_otx_before_delay() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, 9, sign_cobuild_otx_seal);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_RECOVERY_DELAY_NOT_MET);
+}
+
+#[test]
+fn test_recovery_without_identity_and_revocation() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let recovery_privkey = Generator::random_privkey();
+    let recovery_pubkey_hash = blake160(&recovery_privkey.pubkey().expect("pubkey").serialize());
+
+    // the owner keys and revocations are kept in cells left out of the cell deps
+    let mut args = vec![0u8; 20];
+    args.extend_from_slice(&[
+        ARGS_NO_MINIMUM,
+        ARGS_NO_MINIMUM,
+        ARGS_IDENTITY | ARGS_RECOVERY | ARGS_REVOCATION,
+    ]);
+    args.extend_from_slice(&[7u8; 32]);
+    args.extend_from_slice(&recovery_pubkey_hash);
+    args.extend_from_slice(&(SINCE_RELATIVE_BLOCK_NUMBER | 10).to_le_bytes());
+    args.extend_from_slice(&[8u8; 32]);
+    let lock_script = deployment.lock_script(&mut context, &args);
+    let tx = build_tx(
+        &mut context,
+        &deployment,
+        vec![(cell_output(1000, &lock_script, None), Bytes::new())],
+        vec![(cell_output(1000, &lock_script, None), Bytes::new())],
+    );
+    let input = tx
+        .inputs()
+        .get(0)
+        .expect("input")
+        .as_builder()
+        .since((SINCE_RELATIVE_BLOCK_NUMBER | 10).pack())
+        .build();
+    let tx = tx.as_advanced_builder().set_inputs(vec![input]).build();
+    let tx =
+        sign_sighash_single_acp_with_extension(tx, &recovery_privkey, 0, &[EXTENSION_RECOVERY]);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
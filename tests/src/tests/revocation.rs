@@ -0,0 +1,250 @@
+use crate::helper::{
+    build_tx, cell_output, cobuild_sighash_all_witness, deploy, sign_cobuild_sighash_all,
+    sign_secp256k1_blake2b_sighash_all, sign_sighash_single_acp, Otx, MAX_CYCLES,
+};
+
+use super::*;
+
+use ckb_testtool::ckb_crypto::secp::Privkey;
+use ckb_testtool::ckb_hash::new_blake2b;
+use ckb_testtool::ckb_types::{bytes::Bytes, core::TransactionView, packed::*, prelude::*};
+use ckb_testtool::context::Context;
+
+// args flag naming a revocation cell
+const ARGS_REVOCATION: u8 = 0x04;
+// leaves out the anyone-can-pay minimums
+const ARGS_NO_MINIMUM: u8 = 0xff;
+
+// signs the otx with `sign`, returning the signed transaction and the signature, then the
+// maker publishes the revocation cell, revoking the signature when `revoke`
+fn build_signed_tx<F>(context: &mut Context, sign: F, revoke: bool) -> TransactionView
+where
+    F: FnOnce(TransactionView, &Privkey, (CellOutput, Bytes)) -> (TransactionView, Bytes),
+{
+    let revocation_out_point =
+        context.deploy_cell(Loader::default().load_binary("revocation-type"));
+    let revocation_type_script = context
+        .build_script(&revocation_out_point, Bytes::from(vec![9u8; 32]))
+        .expect("script");
+    let mut args_fields = vec![ARGS_NO_MINIMUM, ARGS_NO_MINIMUM, ARGS_REVOCATION];
+    args_fields.extend_from_slice(revocation_type_script.calc_script_hash().as_slice());
+    let Otx {
+        deployment,
+        privkey,
+        input_cell,
+        tx,
+        ..
+    } = Otx::new(context, &args_fields);
+
+    // the revocation cell is in the cell deps from the start, as sealing modes sign them
+    let always_success_script = deployment.always_success_script(context, &[]);
+    let revocation_cell = cell_output(1000, &always_success_script, Some(&revocation_type_script));
+    let revocation_cell_out_point = context.create_cell(revocation_cell.clone(), Bytes::new());
+    let revocation_dep = CellDep::new_builder()
+        .out_point(revocation_cell_out_point.clone())
+        .build();
+    let tx = tx.as_advanced_builder().cell_dep(revocation_dep).build();
+
+    // sign
+    let (tx, signature) = sign(tx, &privkey, input_cell);
+
+    // the revocation cell lists the first 8 bytes of the revoked signatures
+    if revoke {
+        context.create_cell_with_out_point(
+            revocation_cell_out_point,
+            revocation_cell,
+            signature.slice(0..8),
+        );
+    }
+    tx
+}
+
+fn first_witness_lock(tx: &TransactionView) -> Bytes {
+    let witness = WitnessArgs::new_unchecked(tx.witnesses().get(0).unwrap().raw_data());
+    witness.lock().to_opt().unwrap().raw_data()
+}
+
+fn sign_single_acp(
+    tx: TransactionView,
+    privkey: &Privkey,
+    _input_cell: (CellOutput, Bytes),
+) -> (TransactionView, Bytes) {
+    let tx = sign_sighash_single_acp(tx, privkey, 0);
+    // skips the mode byte
+    let signature = first_witness_lock(&tx).slice(1..);
+    (tx, signature)
+}
+
+fn sign_sighash_all(
+    tx: TransactionView,
+    privkey: &Privkey,
+    _input_cell: (CellOutput, Bytes),
+) -> (TransactionView, Bytes) {
+    let tx = sign_secp256k1_blake2b_sighash_all(tx, privkey);
+    let signature = first_witness_lock(&tx);
+    (tx, signature)
+}
+
+fn sign_cobuild(
+    tx: TransactionView,
+    privkey: &Privkey,
+    input_cell: (CellOutput, Bytes),
+) -> (TransactionView, Bytes) {
+    let seal = sign_cobuild_sighash_all(&tx, privkey, &[input_cell], None);
+    let tx = tx
+        .as_advanced_builder()
+        .set_witnesses(vec![cobuild_sighash_all_witness(None, &seal)])
+        .build();
+    (tx, Bytes::from(seal))
+}
+
+// creates a revocation cell listing `revocation_data`
+fn build_create_revocation_tx(context: &mut Context, revocation_data: Bytes) -> TransactionView {
+    let deployment = deploy(context);
+    let revocation_out_point =
+        context.deploy_cell(Loader::default().load_binary("revocation-type"));
+
+    let lock_script = deployment.always_success_script(context, &[]);
+    let tx = build_tx(
+        context,
+        &deployment,
+        vec![(cell_output(1000, &lock_script, None), Bytes::new())],
+        vec![],
+    );
+
+    // the type id of the first output
+    let mut type_id = [0u8; 32];
+    let mut blake2b = new_blake2b();
+    blake2b.update(tx.inputs().get(0).expect("input").as_slice());
+    blake2b.update(&0u64.to_le_bytes());
+    blake2b.finalize(&mut type_id);
+    let revocation_type_script = context
+        .build_script(&revocation_out_point, Bytes::from(type_id.to_vec()))
+        .expect("script");
+
+    let tx = tx
+        .as_advanced_builder()
+        .output(cell_output(
+            1000,
+            &lock_script,
+            Some(&revocation_type_script),
+        ))
+        .output_data(revocation_data.pack())
+        .build();
+    context.complete_tx(tx)
+}
+
+#[test]
+fn test_signature_not_revoked() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, sign_single_acp, false);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_signature_revoked() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, sign_single_acp, true);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_SIGNATURE_REVOKED);
+}
+
+#[test]
+fn test_sighash_all_signature_revoked() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, sign_sighash_all, true);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_SIGNATURE_REVOKED);
+}
+
+#[test]
+fn test_cobuild_sighash_all_signature_revoked() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, sign_cobuild, true);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_SIGNATURE_REVOKED);
+}
+
+#[test]
+fn test_create_revocation() {
+    let mut context = Context::default();
+    let tx = build_create_revocation_tx(&mut context, Bytes::new());
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_create_revocation_with_invalid_data() {
+    let mut context = Context::default();
+    let tx = build_create_revocation_tx(&mut context, Bytes::from(vec![1u8; 12]));
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_INVALID_DATA);
+}
+
+#[test]
+fn test_revocation_missing_for_anyone_can_pay_payment() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+
+    // the lock accepts payments of at least 10^2 shannons, its revocation cell is not needed
+    let mut args = vec![0u8; 20];
+    args.extend_from_slice(&[2, ARGS_NO_MINIMUM, ARGS_REVOCATION]);
+    args.extend_from_slice(&[7u8; 32]);
+    let lock_script = deployment.lock_script(&mut context, &args);
+    let payer_lock_script = deployment.always_success_script(&mut context, &[]);
+    let tx = build_tx(
+        &mut context,
+        &deployment,
+        vec![
+            (cell_output(1000, &lock_script, None), Bytes::new()),
+            (cell_output(5000, &payer_lock_script, None), Bytes::new()),
+        ],
+        vec![
+            (cell_output(1100, &lock_script, None), Bytes::new()),
+            (cell_output(4900, &payer_lock_script, None), Bytes::new()),
+        ],
+    );
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_destroy_revocation() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let revocation_out_point =
+        context.deploy_cell(Loader::default().load_binary("revocation-type"));
+
+    let lock_script = deployment.always_success_script(&mut context, &[]);
+    let revocation_type_script = context
+        .build_script(&revocation_out_point, Bytes::from(vec![7u8; 32]))
+        .expect("script");
+    let tx = build_tx(
+        &mut context,
+        &deployment,
+        vec![(
+            cell_output(1000, &lock_script, Some(&revocation_type_script)),
+            Bytes::new(),
+        )],
+        vec![(cell_output(1000, &lock_script, None), Bytes::new())],
+    );
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_CELL_DESTROYED);
+}
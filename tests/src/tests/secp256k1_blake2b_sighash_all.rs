@@ -81,8 +81,6 @@ fn test_secp256k1_blake2b_sighash_all() {
     let tx = sign_secp256k1_blake2b_sighash_all(tx, &privkey);
 
     // run
-    let cycles = context
-        .verify_tx(&tx, MAX_CYCLES)
-        .expect("pass verification");
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
     println!("consume cycles: {}", cycles);
 }
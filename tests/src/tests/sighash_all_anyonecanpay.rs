@@ -1,4 +1,4 @@
-use crate::helper::{blake160, sign_sighash_all_acp, MAX_CYCLES};
+use crate::helper::{blake160, build_tx, cell_output, deploy, sign_sighash_all_acp, MAX_CYCLES};
 
 use super::*;
 
@@ -89,12 +89,47 @@ fn test_sighash_all_anyonecanpay() {
     let tx = context.complete_tx(tx);
 
     // sign
-    let tx = sign_sighash_all_acp(tx, &privkey, 0);
-    let tx = sign_sighash_all_acp(tx, &privkey, 1);
+    let tx = sign_sighash_all_acp(tx, &privkey, 0, &[0, 1]);
+    let tx = sign_sighash_all_acp(tx, &privkey, 1, &[0, 1]);
 
     // run
-    let cycles = context
-        .verify_tx(&tx, MAX_CYCLES)
-        .expect("pass verification");
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_sighash_all_anyonecanpay_beside_witnessless_input() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let privkey = Generator::random_privkey();
+    let pubkey_hash = blake160(&privkey.pubkey().expect("pubkey").serialize());
+
+    // prepare scripts, accepting payments of at least 10^2 shannons
+    let mut args = pubkey_hash.to_vec();
+    args.push(2);
+    let lock_script = deployment.lock_script(&mut context, &args);
+    let receiver_lock_script = deployment.always_success_script(&mut context, &[]);
+
+    // the signed input pays 700 shannons away and 300 to the second input of the group,
+    // which has no witness at all
+    let tx = build_tx(
+        &mut context,
+        &deployment,
+        vec![
+            (cell_output(1000, &lock_script, None), Bytes::new()),
+            (cell_output(200, &lock_script, None), Bytes::new()),
+        ],
+        vec![
+            (cell_output(700, &receiver_lock_script, None), Bytes::new()),
+            (cell_output(500, &lock_script, None), Bytes::new()),
+        ],
+    );
+
+    // sign
+    let tx = sign_sighash_all_acp(tx, &privkey, 0, &[0, 1]);
+    assert_eq!(tx.witnesses().len(), 1);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
     println!("consume cycles: {}", cycles);
 }
@@ -93,8 +93,6 @@ fn test_sighash_single_anyonecanpay() {
     let tx = sign_sighash_single_acp(tx, &privkey, 1);
 
     // run
-    let cycles = context
-        .verify_tx(&tx, MAX_CYCLES)
-        .expect("pass verification");
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
     println!("consume cycles: {}", cycles);
 }
@@ -0,0 +1,231 @@
+use crate::helper::{
+    blake160, build_tx, cell_output, deploy, sign_sighash_single_udt_acp, udt_requirement,
+    MAX_CYCLES,
+};
+
+use super::*;
+
+use ckb_testtool::ckb_crypto::secp::Generator;
+use ckb_testtool::ckb_types::{bytes::Bytes, core::TransactionView, prelude::*};
+use ckb_testtool::context::Context;
+
+// The maker sells 1000 CKB and asks for at least 500 CKB and `udt_amount` UDT back.
+// The taker pays 150 UDT, split across two outputs of the maker's lock.
+fn build_signed_tx(context: &mut Context, udt_amount: u128) -> TransactionView {
+    let deployment = deploy(context);
+    let privkey = Generator::random_privkey();
+    let pubkey_hash = blake160(&privkey.pubkey().expect("pubkey").serialize());
+
+    // prepare scripts
+    let lock_script = deployment.lock_script(context, &pubkey_hash);
+    let taker_lock_script = deployment.always_success_script(context, &[]);
+    let udt_type_script = deployment.always_success_script(context, &[42]);
+    let lock_hash: [u8; 32] = lock_script.calc_script_hash().unpack().into();
+    let udt_type_hash: [u8; 32] = udt_type_script.calc_script_hash().unpack().into();
+    let udt = |amount: u128| Bytes::from(amount.to_le_bytes().to_vec());
+
+    let tx = build_tx(
+        context,
+        &deployment,
+        vec![
+            (cell_output(1000, &lock_script, None), Bytes::new()),
+            (
+                cell_output(1000, &taker_lock_script, Some(&udt_type_script)),
+                udt(300),
+            ),
+        ],
+        vec![
+            (
+                cell_output(300, &lock_script, Some(&udt_type_script)),
+                udt(100),
+            ),
+            (
+                cell_output(200, &lock_script, Some(&udt_type_script)),
+                udt(50),
+            ),
+            (
+                cell_output(1500, &taker_lock_script, Some(&udt_type_script)),
+                udt(150),
+            ),
+        ],
+    );
+
+    // sign
+    let payload = udt_requirement(&lock_hash, 500, &udt_type_hash, udt_amount);
+    sign_sighash_single_udt_acp(tx, &privkey, 0, &payload)
+}
+
+#[test]
+fn test_sighash_single_udt_anyonecanpay() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, 150);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_sighash_single_udt_anyonecanpay_amount_not_enough() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, 151);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_OUTPUT_AMOUNT_NOT_ENOUGH);
+}
+
+#[test]
+fn test_sighash_single_udt_anyonecanpay_shared_output() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let privkey = Generator::random_privkey();
+    let pubkey_hash = blake160(&privkey.pubkey().expect("pubkey").serialize());
+
+    // prepare scripts
+    let lock_script = deployment.lock_script(&mut context, &pubkey_hash);
+    let taker_lock_script = deployment.always_success_script(&mut context, &[]);
+    let udt_type_script = deployment.always_success_script(&mut context, &[42]);
+    let lock_hash: [u8; 32] = lock_script.calc_script_hash().unpack().into();
+    let udt_type_hash: [u8; 32] = udt_type_script.calc_script_hash().unpack().into();
+    let udt = |amount: u128| Bytes::from(amount.to_le_bytes().to_vec());
+
+    // two cells of the maker ask for the same payment, which is only made once
+    let tx = build_tx(
+        &mut context,
+        &deployment,
+        vec![
+            (cell_output(1000, &lock_script, None), Bytes::new()),
+            (cell_output(1000, &lock_script, None), Bytes::new()),
+            (
+                cell_output(1000, &taker_lock_script, Some(&udt_type_script)),
+                udt(300),
+            ),
+        ],
+        vec![
+            (
+                cell_output(500, &lock_script, Some(&udt_type_script)),
+                udt(150),
+            ),
+            (
+                cell_output(2500, &taker_lock_script, Some(&udt_type_script)),
+                udt(150),
+            ),
+        ],
+    );
+
+    // sign
+    let payload = udt_requirement(&lock_hash, 500, &udt_type_hash, 150);
+    let tx = sign_sighash_single_udt_acp(tx, &privkey, 0, &payload);
+    let tx = sign_sighash_single_udt_acp(tx, &privkey, 1, &payload);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_OUTPUT_AMOUNT_NOT_ENOUGH);
+}
+
+#[test]
+fn test_sighash_single_udt_anyonecanpay_shared_output_across_groups() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let privkey_1 = Generator::random_privkey();
+    let pubkey_hash_1 = blake160(&privkey_1.pubkey().expect("pubkey").serialize());
+    let privkey_2 = Generator::random_privkey();
+    let pubkey_hash_2 = blake160(&privkey_2.pubkey().expect("pubkey").serialize());
+
+    // prepare scripts
+    let lock_script_1 = deployment.lock_script(&mut context, &pubkey_hash_1);
+    let lock_script_2 = deployment.lock_script(&mut context, &pubkey_hash_2);
+    let payee_lock_script = deployment.always_success_script(&mut context, &[1]);
+    let taker_lock_script = deployment.always_success_script(&mut context, &[]);
+    let udt_type_script = deployment.always_success_script(&mut context, &[42]);
+    let payee_lock_hash: [u8; 32] = payee_lock_script.calc_script_hash().unpack().into();
+    let udt_type_hash: [u8; 32] = udt_type_script.calc_script_hash().unpack().into();
+    let udt = |amount: u128| Bytes::from(amount.to_le_bytes().to_vec());
+
+    // two makers of different lock groups ask for the same payment, which is only made once
+    let tx = build_tx(
+        &mut context,
+        &deployment,
+        vec![
+            (cell_output(1000, &lock_script_1, None), Bytes::new()),
+            (cell_output(1000, &lock_script_2, None), Bytes::new()),
+            (
+                cell_output(1000, &taker_lock_script, Some(&udt_type_script)),
+                udt(300),
+            ),
+        ],
+        vec![
+            (
+                cell_output(500, &payee_lock_script, Some(&udt_type_script)),
+                udt(150),
+            ),
+            (
+                cell_output(2500, &taker_lock_script, Some(&udt_type_script)),
+                udt(150),
+            ),
+        ],
+    );
+
+    // sign
+    let payload = udt_requirement(&payee_lock_hash, 500, &udt_type_hash, 150);
+    let tx = sign_sighash_single_udt_acp(tx, &privkey_1, 0, &payload);
+    let tx = sign_sighash_single_udt_acp(tx, &privkey_2, 1, &payload);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_OUTPUT_AMOUNT_NOT_ENOUGH);
+}
+
+#[test]
+fn test_sighash_single_udt_anyonecanpay_anyone_can_pay_output() {
+    let mut context = Context::default();
+    let deployment = deploy(&mut context);
+    let privkey = Generator::random_privkey();
+    let pubkey_hash = blake160(&privkey.pubkey().expect("pubkey").serialize());
+
+    // prepare scripts, the maker's lock receives payments of at least 10^2 shannons
+    let mut args = pubkey_hash.to_vec();
+    args.push(2);
+    let lock_script = deployment.lock_script(&mut context, &args);
+    let taker_lock_script = deployment.always_success_script(&mut context, &[]);
+    let udt_type_script = deployment.always_success_script(&mut context, &[42]);
+    let lock_hash: [u8; 32] = lock_script.calc_script_hash().unpack().into();
+    let udt_type_hash: [u8; 32] = udt_type_script.calc_script_hash().unpack().into();
+    let udt = |amount: u128| Bytes::from(amount.to_le_bytes().to_vec());
+
+    // the only payment to the maker tops up its anyone-can-pay UDT cell
+    let tx = build_tx(
+        &mut context,
+        &deployment,
+        vec![
+            (cell_output(1000, &lock_script, None), Bytes::new()),
+            (
+                cell_output(200, &lock_script, Some(&udt_type_script)),
+                udt(100),
+            ),
+            (
+                cell_output(1000, &taker_lock_script, Some(&udt_type_script)),
+                udt(300),
+            ),
+        ],
+        vec![
+            (
+                cell_output(700, &lock_script, Some(&udt_type_script)),
+                udt(250),
+            ),
+            (
+                cell_output(1500, &taker_lock_script, Some(&udt_type_script)),
+                udt(150),
+            ),
+        ],
+    );
+
+    // sign
+    let payload = udt_requirement(&lock_hash, 500, &udt_type_hash, 150);
+    let tx = sign_sighash_single_udt_acp(tx, &privkey, 0, &payload);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_OUTPUT_AMOUNT_NOT_ENOUGH);
+}
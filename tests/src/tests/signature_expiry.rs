@@ -0,0 +1,57 @@
+use crate::helper::{
+    block_header, expiry_extension, sign_sighash_single_acp_with_extension, Otx, MAX_CYCLES,
+};
+
+use super::*;
+
+use ckb_testtool::ckb_types::core::TransactionView;
+use ckb_testtool::context::Context;
+
+// signs a SINGLE|ANYONECANPAY otx expiring at block `expiry`, assembled against a
+// header dep of block 100 when `with_header_dep` is set
+fn build_signed_tx(context: &mut Context, expiry: u64, with_header_dep: bool) -> TransactionView {
+    let Otx { privkey, tx, .. } = Otx::new(context, &[]);
+
+    // prepare header
+    let header = block_header(100);
+    context.insert_header(header.clone());
+
+    let tx = if with_header_dep {
+        tx.as_advanced_builder().header_dep(header.hash()).build()
+    } else {
+        tx
+    };
+
+    // sign
+    sign_sighash_single_acp_with_extension(tx, &privkey, 0, &expiry_extension(expiry))
+}
+
+#[test]
+fn test_signature_not_expired() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, 100, true);
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_signature_expired() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, 99, true);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_SIGNATURE_EXPIRED);
+}
+
+#[test]
+fn test_signature_expiry_without_header_dep() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context, 100, false);
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_HEADER_DEP_MISSING);
+}
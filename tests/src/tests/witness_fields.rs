@@ -0,0 +1,73 @@
+use crate::helper::{sign_sighash_single_acp, Otx, MAX_CYCLES};
+
+use super::*;
+
+use ckb_testtool::ckb_types::{bytes::Bytes, core::TransactionView, packed::*, prelude::*};
+use ckb_testtool::context::Context;
+use otx_sighash_lock_sdk::{Error, OtxSigner, SighashMode};
+
+// the witness of the input already carries an `output_type` when the maker signs with
+// SINGLE|ANYONECANPAY
+fn build_signed_tx(context: &mut Context) -> TransactionView {
+    let Otx { privkey, tx, .. } = Otx::new(context, &[]);
+    let witness = WitnessArgs::new_builder()
+        .output_type(Some(Bytes::from(vec![42])).pack())
+        .build();
+    let tx = tx
+        .as_advanced_builder()
+        .witness(witness.as_bytes().pack())
+        .build();
+
+    // sign
+    sign_sighash_single_acp(tx, &privkey, 0)
+}
+
+fn witness_args(tx: &TransactionView) -> WitnessArgs {
+    WitnessArgs::new_unchecked(tx.witnesses().get(0).unwrap().raw_data())
+}
+
+#[test]
+fn test_witness_fields_kept() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context);
+    assert_eq!(
+        witness_args(&tx).output_type().to_opt().unwrap().raw_data(),
+        Bytes::from(vec![42])
+    );
+
+    // run
+    let cycles = verify_tx(&context, &tx, MAX_CYCLES).expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_witness_fields_signed() {
+    let mut context = Context::default();
+    let tx = build_signed_tx(&mut context);
+
+    // replace the output type after signing
+    let witness = witness_args(&tx)
+        .as_builder()
+        .output_type(Some(Bytes::from(vec![43])).pack())
+        .build();
+    let tx = tx
+        .as_advanced_builder()
+        .set_witnesses(vec![witness.as_bytes().pack()])
+        .build();
+
+    // run
+    let err = verify_tx(&context, &tx, MAX_CYCLES).unwrap_err();
+    assert_script_error(err, ERROR_WRONG_PUBKEY);
+}
+
+#[test]
+fn test_sign_all_anyonecanpay_without_group_inputs() {
+    let mut context = Context::default();
+    let Otx { privkey, tx, .. } = Otx::new(&mut context, &[]);
+
+    // the witnesses ALL|ANYONECANPAY commits to are unknown without the lock group
+    let err = OtxSigner::new(&privkey)
+        .sign(&tx, 0, SighashMode::AllAnyoneCanPay, &[], &[])
+        .unwrap_err();
+    assert!(matches!(err, Error::NoGroupInputs));
+}
@@ -0,0 +1,83 @@
+//! The type script shared by the cells otx-sighash-lock args name by type hash, the identity
+//! and the revocation cells.
+
+#![no_std]
+
+mod error;
+
+pub use error::Error;
+
+// Import from `core` instead of from `std` since we are in no-std mode
+use core::result::Result;
+
+use blake2b_ref::Blake2bBuilder;
+
+// Import CKB syscalls and structures
+// https://docs.rs/ckb-std/
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::{bytes::Bytes, prelude::*},
+    error::SysError,
+    high_level::{
+        load_cell_data, load_cell_type_hash, load_input, load_script, load_script_hash, QueryIter,
+    },
+};
+
+const TYPE_ID_SIZE: usize = 32;
+
+/// Guards a cell named by its type hash in lock args.
+///
+/// The args hold a type id, so the cell is unique and its type hash stays the same across
+/// updates. The cell cannot be destroyed, the locks naming it would lose their keys or
+/// revocations, and its data must pass `is_valid_data` whenever it is created or updated.
+/// Who may update the cell is left to its lock.
+pub fn validate_type_id(is_valid_data: fn(&[u8]) -> bool) -> Result<(), Error> {
+    let script = load_script()?;
+    let args: Bytes = script.args().unpack();
+    if args.len() != TYPE_ID_SIZE {
+        return Err(Error::InvalidArgs);
+    }
+
+    // at most one cell is consumed and created
+    if has_cell(1, Source::GroupInput)? || has_cell(1, Source::GroupOutput)? {
+        return Err(Error::InvalidTypeId);
+    }
+
+    // every update creates the cell again
+    if !has_cell(0, Source::GroupOutput)? {
+        return Err(Error::CellDestroyed);
+    }
+
+    // creation, the type id is derived from the first input and the output index
+    if !has_cell(0, Source::GroupInput)? {
+        let current_script_hash = load_script_hash()?;
+        let output_index = QueryIter::new(load_cell_type_hash, Source::Output)
+            .position(|type_hash| type_hash == Some(current_script_hash))
+            .ok_or(Error::ItemMissing)?;
+        let first_input = load_input(0, Source::Input)?;
+        let mut type_id = [0u8; TYPE_ID_SIZE];
+        let mut blake2b = Blake2bBuilder::new(32)
+            .personal(b"ckb-default-hash")
+            .build();
+        blake2b.update(first_input.as_slice());
+        blake2b.update(&(output_index as u64).to_le_bytes());
+        blake2b.finalize(&mut type_id);
+        if args[..] != type_id[..] {
+            return Err(Error::InvalidTypeId);
+        }
+    }
+
+    if !is_valid_data(&load_cell_data(0, Source::GroupOutput)?) {
+        return Err(Error::InvalidData);
+    }
+
+    Ok(())
+}
+
+fn has_cell(index: usize, source: Source) -> Result<bool, Error> {
+    match load_cell_data(index, source) {
+        Ok(_) => Ok(true),
+        Err(SysError::IndexOutOfBound) => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
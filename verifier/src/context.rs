@@ -0,0 +1,302 @@
+use crate::resolved::ResolvedTx;
+
+use ckb_crypto::secp::Signature;
+use ckb_types::{
+    bytes::Bytes,
+    packed::{CellOutput, Script, WitnessArgs},
+    prelude::*,
+    H256,
+};
+use otx_sighash_core::{
+    new_blake2b, Error, Header, ScriptAccess, Source, TxAccess, BLAKE160_SIZE,
+    SIGHASH_ALL_SIGNATURE_SIZE,
+};
+
+// the secp256k1 library loads the first witness of the group into a buffer of this size
+const MAX_WITNESS_SIZE: usize = 32768;
+
+/// The lock group being verified, loading from the resolved transaction what the contract
+/// loads with syscalls. Loads fail with `IndexOutOfBound` like the syscalls do.
+pub(crate) struct ScriptContext<'a> {
+    pub resolved: &'a ResolvedTx,
+    pub script: Script,
+    pub script_hash: [u8; 32],
+    /// Indexes of the inputs locked by the script.
+    pub group_inputs: Vec<usize>,
+}
+
+impl<'a> ScriptContext<'a> {
+    pub fn new(resolved: &'a ResolvedTx, script: &Script) -> ScriptContext<'a> {
+        let script_hash: H256 = script.calc_script_hash().unpack();
+        let script_hash = script_hash.0;
+        let group_inputs = resolved
+            .inputs
+            .iter()
+            .enumerate()
+            .filter(|(_, (cell_output, _))| {
+                cell_output.lock().calc_script_hash().as_slice() == script_hash
+            })
+            .map(|(i, _)| i)
+            .collect();
+        ScriptContext {
+            resolved,
+            script: script.clone(),
+            script_hash,
+            group_inputs,
+        }
+    }
+
+    fn load_cell_with_data(
+        &self,
+        index: usize,
+        source: Source,
+    ) -> Result<(CellOutput, Bytes), Error> {
+        let tx = &self.resolved.tx;
+        match source {
+            Source::Input => self.resolved.inputs.get(index).cloned(),
+            Source::Output => tx
+                .outputs()
+                .get(index)
+                .zip(tx.outputs_data().get(index).map(|data| data.raw_data())),
+            Source::CellDep => self.resolved.cell_deps.get(index).cloned(),
+            Source::GroupInput => self
+                .group_inputs
+                .get(index)
+                .and_then(|i| self.resolved.inputs.get(*i).cloned()),
+        }
+        .ok_or(Error::IndexOutOfBound)
+    }
+
+    fn load_cell(&self, index: usize, source: Source) -> Result<CellOutput, Error> {
+        Ok(self.load_cell_with_data(index, source)?.0)
+    }
+
+    // `validate_blake2b_sighash_all` of the secp256k1 library, whose failures all end up as
+    // `Secp256k1`: the signature in the first witness of the group covers the transaction
+    // hash, that witness with the signature zeroed, the other witnesses of the group and
+    // the witnesses beyond the inputs, each prefixed with its length as u64.
+    fn recover_sighash_all_pubkey_hash(&self) -> Option<[u8; BLAKE160_SIZE]> {
+        let witness = self.witness(0, Source::GroupInput).ok()?;
+        if witness.len() > MAX_WITNESS_SIZE {
+            return None;
+        }
+        let witness_args = WitnessArgs::from_slice(&witness).ok()?;
+        let signature: Bytes = witness_args.lock().to_opt()?.unpack();
+        if signature.len() != SIGHASH_ALL_SIGNATURE_SIZE {
+            return None;
+        }
+        let zeroed = witness_args
+            .as_builder()
+            .lock(Some(Bytes::from(vec![0u8; SIGHASH_ALL_SIGNATURE_SIZE])).pack())
+            .build();
+
+        let mut blake2b = new_blake2b();
+        blake2b.update(&self.tx_hash().ok()?);
+        blake2b.update(&(zeroed.as_slice().len() as u64).to_le_bytes());
+        blake2b.update(zeroed.as_slice());
+        let other_witnesses = (1..)
+            .map_while(|i| self.witness(i, Source::GroupInput).ok())
+            .chain(
+                (self.resolved.inputs.len()..).map_while(|i| self.witness(i, Source::Input).ok()),
+            );
+        for witness in other_witnesses {
+            blake2b.update(&(witness.len() as u64).to_le_bytes());
+            blake2b.update(&witness);
+        }
+        let mut message = [0u8; 32];
+        blake2b.finalize(&mut message);
+
+        let pubkey = Signature::from_slice(&signature)
+            .and_then(|signature| signature.recover(&H256(message)))
+            .ok()?;
+        let mut pubkey_hash = [0u8; 32];
+        let mut blake2b = new_blake2b();
+        blake2b.update(&pubkey.serialize());
+        blake2b.finalize(&mut pubkey_hash);
+        let mut buf = [0u8; BLAKE160_SIZE];
+        buf.copy_from_slice(&pubkey_hash[..BLAKE160_SIZE]);
+        Some(buf)
+    }
+}
+
+impl TxAccess for ScriptContext<'_> {
+    type Error = Error;
+
+    fn inputs_len(&self) -> Result<usize, Error> {
+        Ok(self.resolved.tx.inputs().len())
+    }
+
+    fn input(&self, index: usize) -> Result<Vec<u8>, Error> {
+        let input = self
+            .resolved
+            .tx
+            .inputs()
+            .get(index)
+            .ok_or(Error::IndexOutOfBound)?;
+        Ok(input.as_slice().to_vec())
+    }
+
+    fn outputs_len(&self) -> Result<usize, Error> {
+        Ok(self.resolved.tx.outputs().len())
+    }
+
+    fn output(&self, index: usize) -> Result<Vec<u8>, Error> {
+        self.cell(index, Source::Output)
+    }
+
+    fn output_data(&self, index: usize) -> Result<Vec<u8>, Error> {
+        self.cell_data(index, Source::Output)
+    }
+
+    fn output_lock_hash(&self, index: usize) -> Result<[u8; 32], Error> {
+        self.cell_lock_hash(index, Source::Output)
+    }
+
+    fn output_type_hash(&self, index: usize) -> Result<Option<[u8; 32]>, Error> {
+        self.cell_type_hash(index, Source::Output)
+    }
+
+    fn input_witness(&self, index: usize) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self
+            .resolved
+            .tx
+            .witnesses()
+            .get(index)
+            .map(|witness| witness.raw_data().to_vec()))
+    }
+
+    fn cell_deps(&self) -> Result<Vec<u8>, Error> {
+        Ok(self
+            .resolved
+            .tx
+            .data()
+            .raw()
+            .cell_deps()
+            .as_slice()
+            .to_vec())
+    }
+
+    fn header_deps(&self) -> Result<Vec<u8>, Error> {
+        Ok(self
+            .resolved
+            .tx
+            .data()
+            .raw()
+            .header_deps()
+            .as_slice()
+            .to_vec())
+    }
+}
+
+impl ScriptAccess for ScriptContext<'_> {
+    fn script_args(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.script.args().raw_data().to_vec())
+    }
+
+    fn script_hash(&self) -> Result<[u8; 32], Error> {
+        Ok(self.script_hash)
+    }
+
+    fn tx_hash(&self) -> Result<[u8; 32], Error> {
+        let tx_hash: H256 = self.resolved.tx.hash().unpack();
+        Ok(tx_hash.0)
+    }
+
+    fn cells_len(&self, source: Source) -> Result<usize, Error> {
+        Ok(match source {
+            Source::Input => self.resolved.inputs.len(),
+            Source::Output => self.resolved.tx.outputs().len(),
+            Source::CellDep => self.resolved.cell_deps.len(),
+            Source::GroupInput => self.group_inputs.len(),
+        })
+    }
+
+    fn cell(&self, index: usize, source: Source) -> Result<Vec<u8>, Error> {
+        Ok(self.load_cell(index, source)?.as_slice().to_vec())
+    }
+
+    fn cell_capacity(&self, index: usize, source: Source) -> Result<u64, Error> {
+        Ok(self.load_cell(index, source)?.capacity().unpack())
+    }
+
+    fn cell_data(&self, index: usize, source: Source) -> Result<Vec<u8>, Error> {
+        Ok(self.load_cell_with_data(index, source)?.1.to_vec())
+    }
+
+    fn cell_lock_hash(&self, index: usize, source: Source) -> Result<[u8; 32], Error> {
+        let lock_hash: H256 = self
+            .load_cell(index, source)?
+            .lock()
+            .calc_script_hash()
+            .unpack();
+        Ok(lock_hash.0)
+    }
+
+    fn cell_type_hash(&self, index: usize, source: Source) -> Result<Option<[u8; 32]>, Error> {
+        Ok(self
+            .load_cell(index, source)?
+            .type_()
+            .to_opt()
+            .map(|script| {
+                let type_hash: H256 = script.calc_script_hash().unpack();
+                type_hash.0
+            }))
+    }
+
+    fn input_since(&self, index: usize) -> Result<u64, Error> {
+        let input = self
+            .resolved
+            .tx
+            .inputs()
+            .get(index)
+            .ok_or(Error::IndexOutOfBound)?;
+        Ok(input.since().unpack())
+    }
+
+    fn witnesses_len(&self) -> Result<usize, Error> {
+        Ok(self.resolved.tx.witnesses().len())
+    }
+
+    /// Witnesses are loaded from `Input`, the whole list, or from `GroupInput`.
+    fn witness(&self, index: usize, source: Source) -> Result<Vec<u8>, Error> {
+        let index = match source {
+            Source::Input => index,
+            Source::GroupInput => *self.group_inputs.get(index).ok_or(Error::IndexOutOfBound)?,
+            _ => return Err(Error::IndexOutOfBound),
+        };
+        self.resolved
+            .tx
+            .witnesses()
+            .get(index)
+            .map(|witness| witness.raw_data().to_vec())
+            .ok_or(Error::IndexOutOfBound)
+    }
+
+    fn headers(&self) -> Result<Vec<Header>, Error> {
+        Ok(self
+            .resolved
+            .header_deps
+            .iter()
+            .map(|header| Header {
+                number: header.data().raw().number().unpack(),
+                epoch: header.data().raw().epoch().unpack(),
+            })
+            .collect())
+    }
+
+    fn recover_pubkey(
+        &self,
+        message: &[u8; 32],
+        signature: &[u8; SIGHASH_ALL_SIGNATURE_SIZE],
+    ) -> Result<Vec<u8>, Error> {
+        let pubkey = Signature::from_slice(signature)
+            .and_then(|signature| signature.recover(&H256(*message)))
+            .map_err(|_| Error::RecoverPubkey)?;
+        Ok(pubkey.serialize())
+    }
+
+    fn sighash_all_pubkey_hash(&self) -> Result<[u8; BLAKE160_SIZE], Error> {
+        self.recover_sighash_all_pubkey_hash()
+            .ok_or(Error::Secp256k1)
+    }
+}
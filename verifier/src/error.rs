@@ -0,0 +1,13 @@
+use ckb_types::packed::Byte32;
+use thiserror::Error;
+
+/// The errors of the contract, with the same codes.
+pub use otx_sighash_core::Error;
+
+/// A lock group rejected by the verifier.
+#[derive(Error, Debug, Clone)]
+#[error("lock group {script_hash} failed: {error}")]
+pub struct GroupError {
+    pub script_hash: Byte32,
+    pub error: Error,
+}
@@ -0,0 +1,16 @@
+//! Native verifier of the otx-sighash-lock.
+//!
+//! `OtxVerifier` runs the checks of the lock script on a resolved transaction, group by
+//! group, and fails with the same error codes as the contract, so that pools and
+//! aggregators can reject an otx before paying for a full script run. The checks are the
+//! ones of the contract, `otx_sighash_core::validate_lock_group`, run on a resolved
+//! transaction instead of syscalls.
+
+mod context;
+mod error;
+mod resolved;
+mod verifier;
+
+pub use error::{Error, GroupError};
+pub use resolved::ResolvedTx;
+pub use verifier::OtxVerifier;
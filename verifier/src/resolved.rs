@@ -0,0 +1,17 @@
+use ckb_types::{
+    bytes::Bytes,
+    core::{HeaderView, TransactionView},
+    packed::CellOutput,
+};
+
+/// A transaction with everything its scripts may load.
+pub struct ResolvedTx {
+    pub tx: TransactionView,
+    /// The cell consumed by each input, with its data.
+    pub inputs: Vec<(CellOutput, Bytes)>,
+    /// The cells of the cell deps, with their data. Dep groups are expanded into their
+    /// members, as the node does.
+    pub cell_deps: Vec<(CellOutput, Bytes)>,
+    /// The headers of the header deps, in the same order.
+    pub header_deps: Vec<HeaderView>,
+}
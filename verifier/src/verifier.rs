@@ -0,0 +1,76 @@
+use crate::context::ScriptContext;
+use crate::error::{Error, GroupError};
+use crate::resolved::ResolvedTx;
+
+use ckb_types::{
+    core::ScriptHashType,
+    packed::{self, Script},
+    prelude::*,
+};
+use otx_sighash_core::{validate_lock_group, validate_open_lock_group};
+
+/// Verifies the lock groups of one deployment of the otx-sighash-lock.
+///
+/// Only the lock groups of the deployment are verified, other scripts of the transaction
+/// are not run, nor are the consensus rules checked.
+pub struct OtxVerifier {
+    code_hash: packed::Byte32,
+    hash_type: ScriptHashType,
+}
+
+impl OtxVerifier {
+    /// The deployment whose lock scripts use `code_hash` and `hash_type`.
+    pub fn new(code_hash: packed::Byte32, hash_type: ScriptHashType) -> Self {
+        OtxVerifier {
+            code_hash,
+            hash_type,
+        }
+    }
+
+    /// Verifies every lock group of the deployment in the transaction, in the order of
+    /// their first input, stopping at the first group rejected.
+    pub fn verify(&self, resolved: &ResolvedTx) -> Result<(), GroupError> {
+        self.verify_groups(resolved, |context| validate_lock_group(context))
+    }
+
+    /// Verifies an otx waiting for its counterparty like `verify`, leaving out the payments
+    /// SINGLE|ANYONECANPAY|UDT signatures require and the fills of limit orders.
+    pub fn verify_open(&self, resolved: &ResolvedTx) -> Result<(), GroupError> {
+        self.verify_groups(resolved, |context| validate_open_lock_group(context))
+    }
+
+    /// Verifies the lock group of `script`, failing with the error the contract exits with.
+    pub fn verify_group(&self, resolved: &ResolvedTx, script: &Script) -> Result<(), Error> {
+        validate_lock_group(&ScriptContext::new(resolved, script))
+    }
+
+    fn verify_groups<F>(&self, resolved: &ResolvedTx, validate: F) -> Result<(), GroupError>
+    where
+        F: Fn(&ScriptContext) -> Result<(), Error>,
+    {
+        let mut scripts: Vec<Script> = Vec::new();
+        for (cell_output, _) in resolved.inputs.iter() {
+            let lock = cell_output.lock();
+            if self.is_otx_lock(&lock)
+                && !scripts
+                    .iter()
+                    .any(|script| script.as_slice() == lock.as_slice())
+            {
+                scripts.push(lock);
+            }
+        }
+        for script in scripts {
+            validate(&ScriptContext::new(resolved, &script)).map_err(|error| GroupError {
+                script_hash: script.calc_script_hash(),
+                error,
+            })?;
+        }
+        Ok(())
+    }
+
+    fn is_otx_lock(&self, script: &Script) -> bool {
+        let hash_type: packed::Byte = self.hash_type.into();
+        script.code_hash().as_slice() == self.code_hash.as_slice()
+            && script.hash_type().as_slice() == hash_type.as_slice()
+    }
+}